@@ -1,6 +1,8 @@
 use std::{collections::VecDeque, fmt::{Display, Debug}};
 
-pub type RuntimeErrorArguments<'a> = (usize, Option<String>, &'a mut ErrorManager);
+use super::{Span, Token};
+
+pub type RuntimeErrorArguments<'a> = (Span, Option<String>, &'a mut ErrorManager);
 
 pub struct ErrorManager
 {
@@ -26,6 +28,17 @@ impl ErrorManager
     {
         self.errors.append(&mut other.errors);
     }
+
+    // Merges the errors of another manager, marking each one with the script it came from.
+    pub fn merge_script(&mut self, mut other: ErrorManager, script_name: &str)
+    {
+        for error in other.errors.iter_mut()
+        {
+            error.set_script_name(script_name);
+        }
+
+        self.merge(other);
+    }
 }
 
 // Use as a function on the error manager. (Eg. error_manager.error!(ParseError::new(0, 0, "Error.")); )
@@ -44,7 +57,24 @@ pub (crate) use push_error;
 // For parser
 pub trait DrythonError: Display
 {
+    // Sets the file path or virtual name of the script the error occured in.
+    fn set_script_name(&mut self, _script_name: &str) {}
+
+    // Location of the error in the script, if known.
+    fn span(&self) -> Option<Span> { None }
 
+    // Warnings are kept with the errors, but don't stop the script from running.
+    fn is_warning(&self) -> bool { false }
+}
+
+// Prefix used when displaying an error that knows which script it came from.
+fn script_prefix(script_name: &Option<String>) -> String
+{
+    match script_name
+    {
+        Some(name) => format!("[{}] ", name),
+        None => "".to_string()
+    }
 }
 
 impl Debug for dyn DrythonError
@@ -57,37 +87,96 @@ impl Debug for dyn DrythonError
 
 pub struct ParseError
 {
-    location: usize,
+    script_name: Option<String>,
+    location: Span,
     message: String
 }
 
 impl ParseError
 {
-    pub fn new(line_number: usize, message: &str) -> Self
+    pub fn new(location: Span, message: &str) -> Self
     {
         ParseError
         {
-            location: line_number,
+            script_name: None,
+            location,
             message: message.to_string()
         }
     }
 }
-impl DrythonError for ParseError {}
+impl DrythonError for ParseError
+{
+    fn set_script_name(&mut self, script_name: &str)
+    {
+        self.script_name = Some(script_name.to_string());
+    }
+
+    fn span(&self) -> Option<Span>
+    {
+        Some(self.location)
+    }
+}
 
 impl Display for ParseError
 {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
     {
-        write!(f, "Drython Parse Error: Line [{}] - {}", self.location, self.message)
+        write!(f, "Drython Parse Error: {}Line [{}:{}] - {}", script_prefix(&self.script_name), self.location.line, self.location.column, self.message)
+    }
+}
+
+// Code that will run, but likely not as intended. (Eg. a match that misses a case)
+pub struct ParseWarning
+{
+    script_name: Option<String>,
+    location: Span,
+    message: String
+}
+
+impl ParseWarning
+{
+    pub fn new(location: Span, message: &str) -> Self
+    {
+        ParseWarning
+        {
+            script_name: None,
+            location,
+            message: message.to_string()
+        }
+    }
+}
+impl DrythonError for ParseWarning
+{
+    fn set_script_name(&mut self, script_name: &str)
+    {
+        self.script_name = Some(script_name.to_string());
+    }
+
+    fn span(&self) -> Option<Span>
+    {
+        Some(self.location)
+    }
+
+    fn is_warning(&self) -> bool
+    {
+        true
+    }
+}
+
+impl Display for ParseWarning
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        write!(f, "Drython Parse Warning: {}Line [{}:{}] - {}", script_prefix(&self.script_name), self.location.line, self.location.column, self.message)
     }
 }
 
 #[macro_export]
 macro_rules! parse_error
 {
-    ($manager: ident, $line_number: expr, $message: expr) =>
+    ($manager: ident, $span: expr, $message: expr) =>
     {
-        push_error!($manager, ParseError::new($line_number, $message));
+        push_error!($manager, ParseError::new($span, $message));
     };
 }
 
@@ -121,24 +210,37 @@ impl Display for ScriptTypeError
 // For runner
 pub struct RuntimeError
 {
-    line_number: usize,
+    script_name: Option<String>,
+    location: Span,
     function_name: Option<String>,
     message: String
 }
 
 impl RuntimeError
 {
-    pub fn new(line_number: usize, function_name: Option<String>, message: &str) -> Self
+    pub fn new(location: Span, function_name: Option<String>, message: &str) -> Self
     {
         RuntimeError
         {
-            line_number,
+            script_name: None,
+            location,
             function_name,
             message: message.to_string()
         }
     }
 }
-impl DrythonError for RuntimeError {}
+impl DrythonError for RuntimeError
+{
+    fn set_script_name(&mut self, script_name: &str)
+    {
+        self.script_name = Some(script_name.to_string());
+    }
+
+    fn span(&self) -> Option<Span>
+    {
+        Some(self.location)
+    }
+}
 
 impl Display for RuntimeError
 {
@@ -150,10 +252,56 @@ impl Display for RuntimeError
             in_function = true;
         }
 
-        write!(f, "Drython Runtime Error: {}Line [{}] - {}",
+        write!(f, "Drython Runtime Error: {}{}Line [{}:{}] - {}",
+            script_prefix(&self.script_name),
             if in_function { format!("Function ['{}'] ", self.function_name.clone().unwrap()) } else {"".to_string()},
-            self.line_number,
+            self.location.line,
+            self.location.column,
             self.message
         )
     }
 }
+
+// Error leaving running script code, until a catch or the error manager takes it.
+// Everything a catch is given travels with it, so nothing about it is kept on the runner.
+#[derive(Debug, Clone)]
+pub struct ScriptError
+{
+    pub message: String,
+    // Where it's reported.
+    pub span: Span,
+    // Function it happened in. Set as it leaves the first one.
+    pub function: Option<String>,
+    // Caught error passed on by a raise, given to the next catch as it was. (Eg. raise err)
+    pub value: Option<Box<Token>>,
+}
+
+impl ScriptError
+{
+    pub fn new(message: String, span: Span) -> Self
+    {
+        ScriptError
+        {
+            message,
+            span,
+            function: None,
+            value: None
+        }
+    }
+
+    // Error to report. Errors that never left a function happened in the one called.
+    pub fn to_runtime_error(&self, function_name: &str) -> RuntimeError
+    {
+        let function = self.function.clone().unwrap_or_else(|| function_name.to_string());
+
+        RuntimeError::new(self.span, Some(function), self.message.as_str())
+    }
+}
+
+impl From<(String, Span)> for ScriptError
+{
+    fn from((message, span): (String, Span)) -> Self
+    {
+        ScriptError::new(message, span)
+    }
+}
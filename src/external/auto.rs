@@ -1,9 +1,11 @@
 use crate::types::{Token, ExFnRef};
+use super::format::format_values;
 use super::{expect, RegisteredFunction, RegisteredVariable, register_custom_function};
 
 pub fn register_functs(functions: &mut Vec<RegisteredFunction>)
 {
     register_custom_function!(functions, "print", print);    
+    register_custom_function!(functions, "format", format);
 }
 
 pub fn register_vars(_variables: &mut Vec<RegisteredVariable>)
@@ -27,8 +29,19 @@ fn print(_: Option<*mut dyn ExFnRef>, args: Vec<Token>) -> Result<Option<Token>,
         Token::Char(c) => println!("{}", c),
         Token::Bool(b) => println!("{}", b),
         Token::Collection(c) => println!("{:?}", c),
+        Token::Map(_) | Token::Struct(_, _) | Token::Object(_) | Token::Function(_) => println!("{}", args[0]),
         _ => { return Err(format!("Cannot print a variable of this type: {:?}", args[0])); }
     }
 
     Ok(None)
 }
+
+// format(template, values...)
+fn format(_: Option<*mut dyn ExFnRef>, args: Vec<Token>) -> Result<Option<Token>, String>
+{
+    match args.split_first()
+    {
+        Some((Token::String(template), values)) => Ok(Some(Token::String(format_values(template, values)?))),
+        _ => Err("Expected a string to format as the first argument.".to_string())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::{Token, RegisteredFunction, RegisteredVariable, ExFnRef};
+use crate::types::{Token, Function, RegisteredFunction, RegisteredVariable, RegisteredCallback, ValueCaller, ExFnRef};
 use super::{expect, IsToken};
 
 pub fn register_functs(functions: &mut Vec<RegisteredFunction>)
@@ -7,6 +7,12 @@ pub fn register_functs(functions: &mut Vec<RegisteredFunction>)
     functions.push(("remove_collection".to_string(), (None, Some(Box::new(remove_collection)))));
 }
 
+pub fn register_callback_functs(functions: &mut Vec<RegisteredCallback>)
+{
+    functions.push(("map_collection".to_string(), map_collection));
+    functions.push(("filter_collection".to_string(), filter_collection));
+}
+
 pub fn register_vars(_variables: &mut Vec<RegisteredVariable>)
 {
 
@@ -71,3 +77,46 @@ fn remove_collection(_: Option<*mut dyn ExFnRef>, mut args: Vec<Token>) -> Resul
 
     Ok(Some(Token::Collection(new_collection)))
 }
+
+// Calls the function on every item, keeping the results.
+// (Eg. hps = map_collection(enemies, fn(x) => x.hp))
+fn map_collection(call: &mut ValueCaller, args: Vec<Token>) -> Result<Option<Token>, String>
+{
+    let (items, function) = collection_and_function("map_collection", args)?;
+
+    let result = items.into_iter()
+        .map(|item| call(&function, vec![item]).map(|x| x.unwrap_or(Token::Null)))
+        .collect::<Result<Vec<Token>, String>>()?;
+
+    Ok(Some(Token::Collection(result)))
+}
+
+// Keeps the items the function returned true for.
+// (Eg. alive = enemies.filter_collection(fn(x) => x.hp > 0))
+fn filter_collection(call: &mut ValueCaller, args: Vec<Token>) -> Result<Option<Token>, String>
+{
+    let (items, function) = collection_and_function("filter_collection", args)?;
+
+    let mut result: Vec<Token> = Vec::new();
+
+    for item in items
+    {
+        if let Some(Token::Bool(true)) = call(&function, vec![item.clone()])?
+        {
+            result.push(item);
+        }
+    }
+
+    Ok(Some(Token::Collection(result)))
+}
+
+fn collection_and_function(name: &str, args: Vec<Token>) -> Result<(Vec<Token>, Function), String>
+{
+    let mut args = args.into_iter();
+
+    match (args.next(), args.next(), args.next())
+    {
+        (Some(Token::Collection(items)), Some(Token::Function(function)), None) => Ok((items, function)),
+        _ => Err(format!("'{}' expects a collection and a function.", name))
+    }
+}
@@ -0,0 +1,208 @@
+use crate::types::Token;
+
+// Formats values into a template. Shared by f-strings and the format function.
+// (Eg. format("HP: {}/{:.1}", hp, max_hp))
+// Each {} takes the next value, and {{ or }} are literal braces.
+pub(crate) fn format_values(template: &str, values: &[Token]) -> Result<String, String>
+{
+    let mut result = String::new();
+    let value_count = values.len();
+    let mut values = values.iter();
+    let mut placeholders = 0;
+
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next()
+    {
+        match c
+        {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); result.push('{'); },
+            '}' if chars.peek() == Some(&'}') => { chars.next(); result.push('}'); },
+            '{' =>
+            {
+                let mut inside = String::new();
+                loop
+                {
+                    match chars.next()
+                    {
+                        Some('}') => { break; }
+                        Some(c) => inside.push(c),
+                        None => { return Err("Unclosed '{' in format string.".to_string()); }
+                    }
+                }
+
+                let spec = match inside.strip_prefix(':')
+                {
+                    Some(spec) => parse_spec(spec)?,
+                    None if inside.is_empty() => FormatSpec::default(),
+                    None => { return Err(format!("Unexpected '{}' in format string. Placeholders are '{{}}' or '{{:spec}}'.", inside)); }
+                };
+
+                placeholders += 1;
+                if let Some(value) = values.next()
+                {
+                    result.push_str(&spec.apply(value));
+                }
+            },
+            '}' => { return Err("Unmatched '}' in format string. Use '}}' for a brace.".to_string()); }
+            c => result.push(c)
+        }
+    }
+
+    if placeholders != value_count
+    {
+        return Err(format!("Format string has {} placeholders, but {} values were given.", placeholders, value_count));
+    }
+
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align
+{
+    Left,
+    Right,
+    Center
+}
+
+// How a single value is formatted. Written as [[fill]align][0][width][.precision]
+// (Eg. {:>8} right aligns in 8 characters, {:*^9} centers with '*', {:05.1} pads with zeros to 1 decimal place)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FormatSpec
+{
+    fill: char,
+    align: Option<Align>,
+    zero_pad: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Default for FormatSpec
+{
+    fn default() -> Self
+    {
+        FormatSpec { fill: ' ', align: None, zero_pad: false, width: 0, precision: None }
+    }
+}
+
+fn align_of(c: char) -> Option<Align>
+{
+    match c
+    {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None
+    }
+}
+
+// Widths and precisions past this are almost surely mistakes, and would pad by gigabytes.
+const MAX_WIDTH: usize = 4096;
+
+pub(crate) fn parse_spec(spec: &str) -> Result<FormatSpec, String>
+{
+    let mut result = FormatSpec::default();
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+
+    if let Some(align) = chars.get(1).and_then(|x| align_of(*x))
+    {
+        result.fill = chars[0];
+        result.align = Some(align);
+        i = 2;
+    }
+    else if let Some(align) = chars.first().and_then(|x| align_of(*x))
+    {
+        result.align = Some(align);
+        i = 1;
+    }
+
+    if chars.get(i) == Some(&'0')
+    {
+        result.zero_pad = true;
+        i += 1;
+    }
+
+    let (width, next) = read_number(&chars, i);
+    result.width = width.unwrap_or(0);
+    i = next;
+
+    if result.width > MAX_WIDTH
+    {
+        return Err(format!("Width in format spec '{}' is over the maximum of {}.", spec, MAX_WIDTH));
+    }
+
+    if chars.get(i) == Some(&'.')
+    {
+        let (precision, next) = read_number(&chars, i+1);
+        if precision.is_none()
+        {
+            return Err(format!("Expected a precision after '.' in format spec '{}'.", spec));
+        }
+        if precision > Some(MAX_WIDTH)
+        {
+            return Err(format!("Precision in format spec '{}' is over the maximum of {}.", spec, MAX_WIDTH));
+        }
+
+        result.precision = precision;
+        i = next;
+    }
+
+    if i < chars.len()
+    {
+        return Err(format!("Invalid format spec '{}'. Expected [[fill]align][0][width][.precision].", spec));
+    }
+
+    Ok(result)
+}
+
+fn read_number(chars: &[char], start: usize) -> (Option<usize>, usize)
+{
+    let digits: String = chars.iter().skip(start).take_while(|x| x.is_ascii_digit()).collect();
+
+    // Too many digits for a usize is still a number, just one over any maximum.
+    let number = if digits.is_empty() { None } else { Some(digits.parse().unwrap_or(usize::MAX)) };
+
+    (number, start + digits.len())
+}
+
+impl FormatSpec
+{
+    fn apply(&self, value: &Token) -> String
+    {
+        let is_number = matches!(value, Token::Int(_) | Token::Float(_));
+
+        let text = match (value, self.precision)
+        {
+            (Token::Float(f), Some(precision)) => format!("{:.*}", precision, f),
+            (Token::Int(i), Some(precision)) => format!("{:.*}", precision, *i as f64),
+            (Token::String(s), Some(precision)) => s.chars().take(precision).collect(),
+            (Token::String(s), None) => s.clone(),
+            (Token::Char(c), _) => c.to_string(),
+            (value, _) => value.to_string()
+        };
+
+        let length = text.chars().count();
+        if length >= self.width
+        {
+            return text;
+        }
+        let padding = self.width - length;
+
+        // Zeros go after the sign, unless an alignment was asked for.
+        if self.zero_pad && is_number && self.align.is_none()
+        {
+            let (sign, digits) = if let Some(digits) = text.strip_prefix('-') { ("-", digits) } else { ("", text.as_str()) };
+            return format!("{}{}{}", sign, "0".repeat(padding), digits);
+        }
+
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+
+        // Numbers are right aligned by default, everything else left.
+        match self.align.unwrap_or(if is_number { Align::Right } else { Align::Left })
+        {
+            Align::Left => format!("{}{}", text, fill(padding)),
+            Align::Right => format!("{}{}", fill(padding), text),
+            Align::Center => format!("{}{}{}", fill(padding / 2), text, fill(padding - padding / 2))
+        }
+    }
+}
@@ -1,10 +1,10 @@
-use crate::types::{RegisteredFunction, RegisteredVariable};
+use crate::types::{RegisteredFunction, RegisteredVariable, IntoDrythonFunction};
 
-use super::{FunctionCall, attach, register_function_return};
+use super::register_function;
 
 pub fn register_functs(functions: &mut Vec<RegisteredFunction>)
 {
-    register_function_return!(functions, "sqrt", f32::sqrt, f32, f32);
+    register_function!(functions, "sqrt", f64::sqrt);
 }
 
 pub fn register_vars(_variables: &mut Vec<RegisteredVariable>)
@@ -1,9 +1,10 @@
-use crate::types::{Token, DynamicFunctionCall, RegisteredFunction, RegisteredVariable};
+use crate::types::{Token, RegisteredFunction, RegisteredVariable, RegisteredCallback};
 
 mod vector;
 pub mod auto;
 mod math;
 mod collection;
+pub(crate) mod format;
 
 // Allows for quick checking if a token is of a certain type.
 #[derive(Debug)]
@@ -17,6 +18,8 @@ pub enum IsToken
     IsString,
     IsChar,
     IsCollection,
+    IsMap,
+    IsStruct,
     IsNone
 }
 
@@ -41,6 +44,8 @@ impl PartialEq<Token> for IsToken
             (IsToken::IsString, Token::String(_)) => true,
             (IsToken::IsChar, Token::Char(_)) => true,
             (IsToken::IsCollection, Token::Collection(_)) => true,
+            (IsToken::IsMap, Token::Map(_)) => true,
+            (IsToken::IsStruct, Token::Struct(_, _)) => true,
             _ => false
         }
     }
@@ -56,6 +61,8 @@ impl PartialEq<Token> for IsToken
             (IsToken::IsString, Token::String(_)) => false,
             (IsToken::IsChar, Token::Char(_)) => false,
             (IsToken::IsCollection, Token::Collection(_)) => false,
+            (IsToken::IsMap, Token::Map(_)) => false,
+            (IsToken::IsStruct, Token::Struct(_, _)) => false,
             _ => false
         }
     }
@@ -77,6 +84,19 @@ pub fn get_lib(lib: &str) -> Result<(Vec<RegisteredFunction>, Vec<RegisteredVari
     Ok((functions, vars))
 }
 
+// Functions of a library that call the function values they're given.
+pub(crate) fn get_lib_callbacks(lib: &str) -> Vec<RegisteredCallback>
+{
+    let mut functions: Vec<RegisteredCallback> = Vec::new();
+
+    if lib == "collection"
+    {
+        collection::register_callback_functs(&mut functions);
+    }
+
+    functions
+}
+
 // These functions allow for extracting and expecting a specific token
 // from the passed token arguments.
 
@@ -144,102 +164,13 @@ pub fn expect_collection(arg: &Token, argument_number: usize, token_checks: Vec<
     Ok(())
 }
 
-#[allow(dead_code)]
-pub enum FunctionCall<T, U, R>
-    where Token: From<R>
-{
-    A0(fn()),
-    A0R(fn()->R), 
-
-    A1(fn(T)),
-    A1R(fn(T)->R),
-
-    A2(fn(T, U)),
-    A2R(fn(T, U)->R)
-}
-
-pub fn attach<T, U, R>(function_call: FunctionCall<T, U, R>) -> DynamicFunctionCall
-    where
-        Token: From<T>,
-        T: 'static,
-        T: From<Token>,
-
-        Token: From<U>,
-        U: 'static,
-        U: From<Token>,
-        
-        Token: From<R>,
-        R: 'static
-{
-    match function_call
-    {
-        FunctionCall::A0(call) =>
-        {
-            return (None, Some(Box::new(move |_, _| { call(); Ok(None) })));
-        },
-        FunctionCall::A0R(call) =>
-        {
-            return (None, Some(Box::new(move |_, _|
-                    {
-                        Ok(Some(Token::from(call())))
-                    })));
-        },
-        FunctionCall::A1(call) =>
-        {
-            return (None, Some(Box::new(move |_, args| { call(T::from(args[0].clone())); Ok(None) })));
-        },
-        FunctionCall::A1R(call) =>
-        {
-            return (None, Some(Box::new(move |_, args|
-                    {
-                        Ok(Some(Token::from(call(T::from(args[0].clone())))))
-                    })));
-        },
-        FunctionCall::A2(call) =>
-        {
-            return (None, Some(Box::new(move |_, args| { call(T::from(args[0].clone()), U::from(args[1].clone())); Ok(None) })));
-        },
-        FunctionCall::A2R(call) =>
-        {
-            return (None, Some(Box::new(move |_, args|
-                    {
-                        Ok(Some(Token::from(call(T::from(args[0].clone()), U::from(args[1].clone())))))
-                    })));
-        },
-    }
-}
-
+// Argument and return types are converted by IntoDrythonFunction.
 #[macro_export]
 macro_rules! register_function
 {
     ($functions: expr, $name:expr, $call:expr) =>
     {
-        $functions.push(($name.to_string(), attach::<i32, i32, i32>(FunctionCall::A0($call))));
-    };
-    ($functions: expr, $name:expr, $call:expr, $t:ty) =>
-    {
-        $functions.push(($name.to_string(), attach::<$t, $t, $t>(FunctionCall::A1($call))));
-    };
-    ($functions: expr, $name:expr, $call:expr, $t:ty, $u:ty) =>
-    {
-        $functions.push(($name.to_string(), attach::<$t, $u, $u>(FunctionCall::A2($call))));
-    };
-}
-
-#[macro_export]
-macro_rules! register_function_return
-{
-    ($functions: expr, $name:expr, $call:expr, $r:ty) =>
-    {
-        $functions.push(($name.to_string(), attach::<$r, $r, $r>(FunctionCall::A0R($call))));
-    };
-    ($functions: expr, $name:expr, $call:expr, $t:ty, $r:ty) =>
-    {
-        $functions.push(($name.to_string(), attach::<$t, $t, $r>(FunctionCall::A1R($call))));
-    };
-    ($functions: expr, $name:expr, $call:expr, $t:ty, $u:ty, $r:ty) =>
-    {
-        $functions.push((name.to_string(), attach::<$t, $u, $r>(FunctionCall::A2R($call))));
+        $functions.push(($name.to_string(), (None, Some(IntoDrythonFunction::<_>::into_function($call, $name)))));
     };
 }
 
@@ -252,7 +183,5 @@ macro_rules! register_custom_function
     }
 }
 
-#[allow(unused_imports)]
 pub (crate) use register_function;
-pub (crate) use register_function_return;
 pub (crate) use register_custom_function;
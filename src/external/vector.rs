@@ -1,10 +1,10 @@
-use crate::types::{Token::{self, *}, RegisteredFunction, RegisteredVariable};
+use crate::types::{Token::{self, *}, RegisteredFunction, RegisteredVariable, IntoDrythonFunction};
 
-use super::{FunctionCall, attach, register_function_return};
+use super::register_function;
 
 pub fn register_functs(functions: &mut Vec<RegisteredFunction>)
 {
-    register_function_return!(functions, "divide2", divide2, Vec<f32>, Vec<f32>);
+    register_function!(functions, "divide2", divide2);
 }
 
 pub fn register_vars(variables: &mut Vec<RegisteredVariable>)
@@ -12,9 +12,9 @@ pub fn register_vars(variables: &mut Vec<RegisteredVariable>)
     variables.push(("vector3.one".to_string(), Collection(vec![Float(1.0), Float(1.0), Float(1.0)])));
 }
 
-impl From<Vec<f32>> for Token
+impl From<Vec<f64>> for Token
 {
-    fn from(value: Vec<f32>) -> Self
+    fn from(value: Vec<f64>) -> Self
     {
         let mut tokens: Vec<Token> = vec![];
         for f in value
@@ -26,35 +26,7 @@ impl From<Vec<f32>> for Token
     }
 }
 
-impl From<Token> for Vec<f32>
+fn divide2(vector: Vec<f64>) -> Vec<f64>
 {
-    fn from(value: Token) -> Self
-    {
-        match &value
-        {
-            Token::Collection(collection) =>
-            {
-                let mut vector: Vec<f32> = vec![];
-                for item in collection
-                {
-                    if let Token::Float(f) = item
-                    {
-                        vector.push(*f);
-                    }
-                    else
-                    {
-                        return vec![];
-                    }
-                }
-
-                vector
-            },
-            _ => vec![]
-        }
-    }
-}
-
-fn divide2(vector: Vec<f32>) -> Vec<f32>
-{
-    return vector.iter().map(|f| *f/2.0).collect::<Vec<f32>>();
+    vector.iter().map(|f| *f/2.0).collect::<Vec<f64>>()
 }
@@ -1,32 +1,39 @@
-use crate::utility;
+use crate::parser::lexer::{self, LexToken};
 
-// Parses a function call into the name of the function, and the arguments.
-pub fn parse_call(call: &str) -> Result<(String, Vec<String>), String>
+// Parses a call into the name of the function, and the tokens of each argument.
+pub fn parse_call(call: &[LexToken]) -> Result<(String, Vec<Vec<LexToken>>), String>
 {
     let function: String;
-    let arguments: Vec<String>;
-    
-    if let Some(first) = call.split_once("(")
+    let arguments: Vec<Vec<LexToken>>;
+
+    if let Some(open) = call.iter().position(|x| x.is_symbol("("))
     {
-        if first.1.len() > 0 && first.1.as_bytes()[first.1.len()-1] == b')'
+        function = call[..open].iter().map(|x| x.to_string()).collect();
+
+        match lexer::find_closing(call, open)
         {
-            function = first.0.to_string();
-            
-            match utility::split_by(&first.1[0..first.1.len()-1], ',')
+            Some(close) if close == call.len()-1 =>
             {
-                Ok(result) =>
+                match lexer::split_tokens(&call[open+1..close], ",")
                 {
-                    arguments = result;
-                }
-                Err(error) =>
-                {
-                    return Err(error);
+                    Ok(result) =>
+                    {
+                        arguments = result.into_iter().map(|x| x.to_vec()).collect();
+                    }
+                    Err(error) =>
+                    {
+                        return Err(error.0);
+                    }
                 }
+            },
+            Some(_) =>
+            {
+                return Err("Unexpected values found after function call.".to_string());
+            }
+            None =>
+            {
+                return Err("No closing parenthesis found on function call.".to_string());
             }
-        }
-        else
-        {
-            return Err("No closing parenthesis found on function call.".to_string());
         }
     }
     else
@@ -3,382 +3,760 @@ mod scope_parser;
 #[path="call_parser.rs"]
 mod call_parser;
 
-use std::collections::HashMap;
+use std::rc::Rc;
 
-use crate::utility;
-use crate::types::{Token, ExpressionList, error::*, ExpressionListType, SingleOp, Internal, MultiOp};
+use crate::types::{Token, Span, Statement, Branch, Case, Pattern, FunctionDef, StructDef, Operation, VariableModifier, error::*};
 
+use super::lexer::{self, LexKind, LexToken};
 use super::variable_parser::parse_var;
 use super::{operation_parser, ExpressionType};
-use scope_parser::parse_scope;
+use scope_parser::{parse_scope, ScopeHeader};
 
-pub fn parse_expressions(expressions: &Vec<String>, line_start:usize, error_manager: &mut ErrorManager, in_expression: &ExpressionType, in_function: bool) -> ExpressionList
+// Where statements are being parsed. Decides which statements are allowed.
+#[derive(Clone, Copy)]
+struct ScopeContext
 {
-    let mut expression_order: Vec<ExpressionListType> = Vec::new();
-    let mut order_pushed_flag = false;
-
-    let mut single_op: Vec<SingleOp> = Vec::new();
-    let mut multi_ops: Vec<MultiOp> = Vec::new();
-    let mut internal_expressions: Vec<Internal> = Vec::new();
-    let mut includes: HashMap<String, usize> = HashMap::new(); 
-
-    // For internal expressions lists.
-    // Will be split out for parsing once the end is found.
-    let mut inside_scope = false;
-    let mut scope_start = 0;
-    let mut scope_count = 0;
-    let mut scope_expression = in_expression.clone();
-
-    // The actual order index that each expression will use.
-    // (i in the enumerate is unrealiable with nested blocks.)
-    let mut operation_index = 0;
-
-    for (i, expression) in expressions.iter().enumerate()
-    {
-        let exp;
+    in_function: bool,
+    in_loop: bool,
+    // Inside an if, loop or function. Allows the scope to be closed.
+    nested: bool,
+}
 
-        if let Some(result) = expression.split_once(")")
-        {
-            exp = result.1;
-        }
-        else {continue;}
+// Parses the lines of a script into its global statements.
+pub fn parse_statements(lines: &[Vec<LexToken>], error_manager: &mut ErrorManager) -> Vec<Statement>
+{
+    let mut index = 0;
+    let context = ScopeContext { in_function: false, in_loop: false, nested: false };
 
-        let expression_type_result = get_expression_type(exp);
-        let expression_type: ExpressionType;
+    parse_block(lines, &mut index, context, error_manager).0
+}
+
+// Parses statements until the end of the current scope.
+// Returns the statements along with the expression that closed the scope. (end, elif or else)
+// The index is left on the closing line.
+fn parse_block(lines: &[Vec<LexToken>], index: &mut usize, context: ScopeContext, error_manager: &mut ErrorManager) -> (Vec<Statement>, Option<ExpressionType>)
+{
+    let mut statements: Vec<Statement> = Vec::new();
 
-        match expression_type_result
+    while *index < lines.len()
+    {
+        let exp = &lines[*index];
+        let exp_span = lexer::tokens_span(exp);
+
+        let expression_type = match get_expression_type(exp)
         {
-            Ok(result) =>
-            {
-                expression_type = result;
-            },
+            Ok(result) => result,
             Err(message) =>
             {
-                if in_function
+                if context.in_function
                 {
-                    push_error!(error_manager, ParseError::new(line_start+i+1, message.as_str()));
+                    push_error!(error_manager, ParseError::new(exp_span, message.as_str()));
                 }
+
+                *index += 1;
                 continue;
             }
-        }
+        };
 
-        if inside_scope
+        match expression_type
         {
-            order_pushed_flag = true;
+            ExpressionType::End | ExpressionType::Elif | ExpressionType::Else | ExpressionType::Catch | ExpressionType::Case =>
+            {
+                if context.nested
+                {
+                    return (statements, Some(expression_type));
+                }
 
-            if expression_type.is_scope()
+                if expression_type == ExpressionType::End
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, "Too many 'end' statements are present. Are you missing a ':' after a function decleration?"));
+                }
+                else if expression_type == ExpressionType::Catch || expression_type == ExpressionType::Case
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, misplaced_branch(&expression_type)));
+                }
+                else
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, format!("Found '{}' without an if statement before it.", lexer::tokens_to_string(exp)).as_str()));
+                }
+            },
+            // Scope change (if/loop).
+            ExpressionType::If =>
             {
-                scope_count += 1;
-            }
+                if !context.in_function
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, "If statements are only allowed inside functions."));
+                }
 
-            if expression_type == ExpressionType::End || expression_type == ExpressionType::Elif || expression_type == ExpressionType::Else
+                if let Some(statement) = parse_if(lines, index, context, error_manager)
+                {
+                    statements.push(statement);
+                }
+                continue;
+            },
+            ExpressionType::Match =>
             {
-                if scope_count == 0
+                if !context.in_function
                 {
-                    let mut internal_expression = parse_expressions(
-                        &expressions[scope_start+1..i].to_vec(),
-                        scope_start+1+line_start,
-                        error_manager,
-                        &scope_expression,
-                        true
-                    );
+                    push_error!(error_manager, ParseError::new(exp_span, "Match statements are only allowed inside functions."));
+                }
 
-                    match parse_scope(&expressions[scope_start].split_once(")").unwrap().1, &scope_expression)
-                    {
-                        Ok(result) => {internal_expression.scope_info = result;}
-                        Err(error) => {push_error!(error_manager, ParseError::new(line_start+scope_start, error.as_str()));}
-                    }
+                if let Some(statement) = parse_match(lines, index, context, error_manager)
+                {
+                    statements.push(statement);
+                }
+                continue;
+            },
+            ExpressionType::Try =>
+            {
+                if !context.in_function
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, "Try statements are only allowed inside functions."));
+                }
 
-                    internal_expressions.push((internal_expression, line_start+scope_start+1));
+                if let Some(statement) = parse_try(lines, index, context, error_manager)
+                {
+                    statements.push(statement);
+                }
+                continue;
+            },
+            ExpressionType::Loop =>
+            {
+                if !context.in_function
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, "Loops are only allowed inside functions."));
+                }
 
-                    if expression_type == ExpressionType::Elif || expression_type == ExpressionType::Else
-                    {
-                        scope_start = i;
-                        scope_expression = expression_type.clone();
-                    }
-                    else
-                    {
-                        inside_scope = false;
-                        scope_expression = ExpressionType::None;
-                    }
+                let header = parse_scope(exp, &expression_type);
+                let body = parse_scope_body(lines, index, ScopeContext { in_loop: true, nested: true, ..context }, error_manager);
 
-                    operation_index += 1;
+                match header
+                {
+                    Ok(ScopeHeader::Loop(kind)) => statements.push(Statement::Loop { kind, body: body.0, span: exp_span.to(&body.1) }),
+                    Ok(_) => (),
+                    Err(error) => push_error!(error_manager, ParseError::new(error.1, error.0.as_str()))
                 }
-                else if expression_type == ExpressionType::End
+                continue;
+            },
+            ExpressionType::Function =>
+            {
+                if context.nested
                 {
-                    scope_count -= 1;
+                    push_error!(error_manager, ParseError::new(exp_span, "Functions can only be defined outside of other scopes."));
+                }
 
-                    // Handle error when reached end of expression but no 'end' in sight.
-                    // But still within a scope.
-                    if i == expressions.len()-1
+                let header = parse_scope(exp, &expression_type);
+                let body = parse_scope_body(lines, index, ScopeContext { in_function: true, in_loop: false, nested: true }, error_manager);
+
+                match header
+                {
+                    Ok(ScopeHeader::Function(name, parameters)) =>
                     {
-                        push_error!(error_manager, 
-                            ParseError::new(line_start+scope_start+1,
-                                format!("Scope starting at '{}' was not closed with an 'end' statement internally.", &expressions[i].split_once(")").unwrap().1).as_str()));
-                    }
+                        statements.push(Statement::Function(Rc::new(FunctionDef
+                        {
+                            name,
+                            parameters,
+                            body: body.0,
+                            span: exp_span.to(&body.1)
+                        })));
+                    },
+                    Ok(_) => (),
+                    Err(error) => push_error!(error_manager, ParseError::new(error.1, error.0.as_str()))
                 }
-            }
-            // Handle error when reached end of expression but no 'end' in sight.
-            else if i == expressions.len()-1
-            {
-                push_error!(error_manager, 
-                    ParseError::new(line_start+i,
-                        format!("Scope starting at '{}' was not closed with an 'end' statement.", &expressions[line_start+scope_start].split_once(")").unwrap().1).as_str()));
-            }
-        }
-        else
-        {            
-            // Scope change (if/loop).
-            if expression_type.is_scope()
+                continue;
+            },
+            ExpressionType::Struct =>
             {
-                scope_start = i;
-                scope_expression = expression_type.clone();
-                inside_scope = true;
+                if context.nested
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, "Structs can only be declared outside of other scopes."));
+                }
 
-                expression_order.push(ExpressionListType::Internal);
-                order_pushed_flag = true;
+                let header = parse_scope(exp, &expression_type);
+                let (fields, end_span) = parse_struct_fields(lines, index, error_manager);
 
-                if i == expressions.len()-1
+                match header
                 {
-                    push_error!(error_manager, 
-                        ParseError::new(line_start+i+1,
-                            format!("Scope starting at '{}' was not closed with an 'end' statement.", &expressions[line_start+scope_start].split_once(")").unwrap().1).as_str()));
+                    Ok(ScopeHeader::Struct(name)) =>
+                    {
+                        statements.push(Statement::Struct(Rc::new(StructDef { name, fields, span: exp_span.to(&end_span) })));
+                    },
+                    Ok(_) => (),
+                    Err(error) => push_error!(error_manager, ParseError::new(error.1, error.0.as_str()))
                 }
-            }
+                continue;
+            },
             // Return operation.
-            else if expression_type == ExpressionType::Return
+            ExpressionType::Return =>
             {
-                if in_function
+                if context.in_function
                 {
-                    match operation_parser::parse_operation(exp.trim_start_matches("return"))
+                    if exp.len() == 1
                     {
-                        Ok(operation) =>
-                        {
-                            single_op.push(("return".to_string(), vec!(), operation, line_start+i));
-                            expression_order.push(ExpressionListType::Single);
-                            order_pushed_flag = true;
-                            operation_index += 1;
-                        }
-                        Err(error) =>
-                        {
-                            parse_error!(error_manager, line_start+i+1, error.as_str());
-                        }
+                        statements.push(Statement::Return { value: None, span: exp_span });
+                    }
+                    else if let Some(operation) = parse_operation(&exp[1..], error_manager)
+                    {
+                        statements.push(Statement::Return { value: Some(operation), span: exp_span });
                     }
                 }
                 else
                 {
-                    push_error!(error_manager, ParseError::new(line_start+i+1, "Return statement unexpected outside function definition."));
+                    push_error!(error_manager, ParseError::new(exp_span, "Return statement unexpected outside function definition."));
                 }
-            }
+            },
+            ExpressionType::Raise =>
+            {
+                if !context.in_function
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, "Raise statement unexpected outside function definition."));
+                }
+                else if exp.len() == 1
+                {
+                    push_error!(error_manager, ParseError::new(exp_span, "Expected an error message after 'raise'. (Eg. raise \"Asset not found\")"));
+                }
+                else if let Some(operation) = parse_operation(&exp[1..], error_manager)
+                {
+                    statements.push(Statement::Raise { value: operation, span: exp_span });
+                }
+            },
             // Variable assignment.
-            else if expression_type == ExpressionType::Assignment
+            ExpressionType::Assignment =>
             {
                 match parse_var(exp)
                 {
                     Ok(result) => 
                     {
-                        match operation_parser::parse_operation(&result.2)
+                        let indexes: Option<Vec<Operation>> = result.3.iter().map(|x| parse_operation(x, error_manager)).collect();
+
+                        if let (Some(operation), Some(indexes)) = (parse_operation(&result.2, error_manager), indexes)
                         {
-                            Ok(operation) =>
+                            statements.push(Statement::Assign
                             {
-                                single_op.push((result.1, result.0, operation, line_start+i+1));
-                                expression_order.push(ExpressionListType::Single);
-                                order_pushed_flag = true;
-                                operation_index += 1;
-                            }
-                            Err(error) => {push_error!(error_manager, ParseError::new(line_start+i+1, error.as_str()));}
+                                name: result.1,
+                                modifiers: result.0.iter().map(|x| VariableModifier::from(x.as_str())).collect(),
+                                value: operation,
+                                indexes,
+                                span: exp_span
+                            });
                         }
                     },
-                    Err(error) => {push_error!(error_manager, ParseError::new(line_start+i+1, error.as_str()));}
+                    Err(error) => {push_error!(error_manager, ParseError::new(exp_span, error.as_str()));}
                 }
-            }
+            },
             // Function call.
-            else if expression_type == ExpressionType::Call
+            ExpressionType::Call =>
             {
-                if in_function
+                if context.in_function
                 {
                     match call_parser::parse_call(exp)
                     {
                         Ok(result) => 
                         {
-                            let mut operations: Vec<Vec<Token>> = Vec::new();
+                            let arguments: Vec<&[LexToken]> = result.1.iter().map(|x| x.as_slice()).collect();
 
-                            for statement in result.1
+                            match operation_parser::parse_arguments(&arguments)
                             {
-                                match operation_parser::parse_operation(&statement)
-                                {
-                                    Ok(operation) => operations.push(operation),
-                                    Err(error) => {push_error!(error_manager, ParseError::new(line_start+i+1, error.as_str()));}
-                                }
+                                Ok(arguments) => statements.push(Statement::Call { name: result.0, arguments, span: exp_span }),
+                                Err(error) => { parse_error!(error_manager, error.1, error.0.as_str()); }
                             }
-
-                            multi_ops.push((result.0, operations, line_start+i+1));
-                            expression_order.push(ExpressionListType::Multi);
-                            order_pushed_flag = true;
-
-                            operation_index += 1;
                         },
-                        Err(error) => {push_error!(error_manager, ParseError::new(line_start+i+1, error.as_str()));}
+                        Err(error) => {push_error!(error_manager, ParseError::new(exp_span, error.as_str()));}
                     }
                 }
                 else
                 {
-                    push_error!(error_manager, ParseError::new(line_start+i+1, "Unexpected function call in script. Did you mean to call it inside a function?"));
+                    push_error!(error_manager, ParseError::new(exp_span, "Unexpected function call in script. Did you mean to call it inside a function?"));
                 }
-            }
+            },
             // loop control functions
-            else if expression_type == ExpressionType::Break
+            ExpressionType::Break =>
             {
-                if let ExpressionType::Loop = scope_expression
+                if context.in_loop
                 {
-                    single_op.push(("break".to_string(), vec![], vec![], line_start+i+1));
-                    expression_order.push(ExpressionListType::Single);
-                    order_pushed_flag = true;
-
-                    operation_index += 1;
+                    statements.push(Statement::Break(exp_span));
                 }
                 else
                 {
-                    push_error!(error_manager, ParseError::new(line_start+i+1, "Break statement used outside loop."));
+                    push_error!(error_manager, ParseError::new(exp_span, "Break statement used outside loop."));
                 }
-            }
-            else if expression_type == ExpressionType::Continue
+            },
+            ExpressionType::Continue =>
             {
-                if let ExpressionType::Loop = scope_expression
+                if context.in_loop
                 {
-                    single_op.push(("continue".to_string(), vec![], vec![], line_start+i+1));
-                    expression_order.push(ExpressionListType::Single);
-                    order_pushed_flag = true;
-
-                    operation_index += 1;
+                    statements.push(Statement::Continue(exp_span));
                 }
                 else
                 {
-                    push_error!(error_manager, ParseError::new(line_start+i+1, "Continue statement used outside loop."));
+                    push_error!(error_manager, ParseError::new(exp_span, "Continue statement used outside loop."));
                 }
-            }
+            },
             // Importing external functions
-            else if expression_type == ExpressionType::Library
+            ExpressionType::Library =>
             {
-                if !in_function
+                if !context.nested
                 {
-                    let library = exp.trim_start_matches(match exp
-                    {
-                        _ if exp.starts_with("use") => "use",
-                        _ if exp.starts_with("using") => "using",
-                        _ if exp.starts_with("import") => "import",
-                        _ if exp.starts_with("include") => "include",
-                        _ => ""
-                    });
-                    
-                    includes.insert(library.to_string(), line_start+i+1);
-                    expression_order.push(ExpressionListType::Library);
-                    order_pushed_flag = true;
+                    let library: String = exp[1..].iter().map(|x| x.to_string()).collect();
+
+                    statements.push(Statement::Import { library, span: exp_span });
                 }
                 else
                 {
-                    push_error!(error_manager, ParseError::new(line_start+i+1, "Library includes are not allowed within a scope."));
+                    push_error!(error_manager, ParseError::new(exp_span, "Library includes are not allowed within a scope."));
                 }
+            },
+            ExpressionType::None => ()
+        }
+
+        *index += 1;
+    }
+
+    (statements, None)
+}
+
+// Parses an if statement along with all the elif and else branches chained to it.
+// The index is left on the line after the 'end'.
+fn parse_if(lines: &[Vec<LexToken>], index: &mut usize, context: ScopeContext, error_manager: &mut ErrorManager) -> Option<Statement>
+{
+    let start_span = lexer::tokens_span(&lines[*index]);
+    let mut branches: Vec<Branch> = Vec::new();
+
+    let mut header_type = ExpressionType::If;
+    let mut failed = false;
+
+    loop
+    {
+        let header_line = *index;
+        let header_span = lexer::tokens_span(&lines[header_line]);
+
+        *index += 1;
+        let (body, closed_by) = parse_block(lines, index, ScopeContext { nested: true, ..context }, error_manager);
+
+        // Misplaced catches and cases were already reported.
+        let header = match header_type
+        {
+            ExpressionType::Catch | ExpressionType::Case => Ok(ScopeHeader::Else),
+            _ => parse_scope(&lines[header_line], &header_type)
+        };
+
+        match header
+        {
+            Ok(ScopeHeader::Condition(condition)) => branches.push(Branch { condition: Some(condition), body, span: header_span }),
+            Ok(_) => branches.push(Branch { condition: None, body, span: header_span }),
+            Err(error) =>
+            {
+                push_error!(error_manager, ParseError::new(error.1, error.0.as_str()));
+                failed = true;
             }
-            else if expression_type == ExpressionType::End
+        }
+
+        match closed_by
+        {
+            Some(ExpressionType::End) =>
+            {
+                let end_span = lexer::tokens_span(&lines[*index]);
+                *index += 1;
+
+                return if failed { None } else { Some(Statement::If { branches, span: start_span.to(&end_span) }) };
+            },
+            Some(next_type @ ExpressionType::Catch) | Some(next_type @ ExpressionType::Case) =>
+            {
+                push_error!(error_manager, ParseError::new(lexer::tokens_span(&lines[*index]), misplaced_branch(&next_type)));
+                failed = true;
+                header_type = next_type;
+            },
+            Some(next_type) =>
             {
-                push_error!(error_manager, ParseError::new(line_start+i+1, "Too many 'end' statements are present. Are you missing a ':' after a function decleration?"));
-                order_pushed_flag = true;
+                if header_type == ExpressionType::Else
+                {
+                    push_error!(error_manager, ParseError::new(lexer::tokens_span(&lines[*index]), "Else must be the last branch of an if statement."));
+                    failed = true;
+                }
+
+                header_type = next_type;
+            },
+            None =>
+            {
+                push_error!(error_manager, 
+                    ParseError::new(start_span,
+                        format!("Scope starting at '{}' was not closed with an 'end' statement.", lexer::tokens_to_string(&lines[header_line])).as_str()));
+                return None;
             }
         }
+    }
+}
+
+// Parses a match statement along with its cases. Each case is a branch, like the elifs of an if statement.
+// The index is left on the line after the 'end'.
+fn parse_match(lines: &[Vec<LexToken>], index: &mut usize, context: ScopeContext, error_manager: &mut ErrorManager) -> Option<Statement>
+{
+    let start = *index;
+    let start_span = lexer::tokens_span(&lines[start]);
+    let mut failed = false;
+
+    let subject = match parse_scope(&lines[start], &ExpressionType::Match)
+    {
+        Ok(ScopeHeader::Match(subject)) => subject,
+        Ok(_) => vec![],
+        Err(error) =>
+        {
+            push_error!(error_manager, ParseError::new(error.1, error.0.as_str()));
+            failed = true;
+            vec![]
+        }
+    };
+
+    *index += 1;
+    let (before_cases, mut closed_by) = parse_block(lines, index, ScopeContext { nested: true, ..context }, error_manager);
 
-        // Handle expression order flagging for empty lines.
-        if order_pushed_flag { order_pushed_flag = false; }
-        else { expression_order.push(ExpressionListType::Null); }
+    if !before_cases.is_empty()
+    {
+        push_error!(error_manager, ParseError::new(before_cases[0].span(), "Expected a 'case' before any statements of a match."));
+        failed = true;
     }
 
-    ExpressionList
+    let mut cases: Vec<Case> = Vec::new();
+
+    let end_span = loop
     {
-        scope_info: (None, None),
-        size: operation_index,
-        line_start,
-
-        expression_order,
-        single_op,
-        multi_ops,
-        internal_expressions,
-        includes
+        let mut misplaced = false;
+
+        match closed_by
+        {
+            Some(ExpressionType::Case) => (),
+            Some(ExpressionType::End) =>
+            {
+                let end_span = lexer::tokens_span(&lines[*index]);
+                *index += 1;
+                break end_span;
+            },
+            Some(other) =>
+            {
+                push_error!(error_manager, ParseError::new(lexer::tokens_span(&lines[*index]), misplaced_branch(&other)));
+                failed = true;
+                misplaced = true;
+            },
+            None =>
+            {
+                push_error!(error_manager,
+                    ParseError::new(start_span,
+                        format!("Scope starting at '{}' was not closed with an 'end' statement.", lexer::tokens_to_string(&lines[start])).as_str()));
+                return None;
+            }
+        }
+
+        let header_line = *index;
+        let header_span = lexer::tokens_span(&lines[header_line]);
+
+        *index += 1;
+        let (body, next) = parse_block(lines, index, ScopeContext { nested: true, ..context }, error_manager);
+
+        match parse_scope(&lines[header_line], &ExpressionType::Case)
+        {
+            _ if misplaced => (),
+            Ok(ScopeHeader::Case(pattern, guard)) => cases.push(Case { pattern, guard, body, span: header_span }),
+            Ok(_) => (),
+            Err(error) =>
+            {
+                push_error!(error_manager, ParseError::new(error.1, error.0.as_str()));
+                failed = true;
+            }
+        }
+
+        closed_by = next;
+    };
+
+    if cases.is_empty() && !failed
+    {
+        push_error!(error_manager, ParseError::new(start_span, "Match statements need at least one 'case'."));
+        return None;
     }
+
+    check_cases(&cases, start_span, error_manager);
+
+    if failed { None } else { Some(Statement::Match { subject, cases, span: start_span.to(&end_span) }) }
 }
 
-pub fn get_expression_type(string: &str) -> Result<ExpressionType, String>
+// Warns about cases that can't be reached, and matches on a bool that miss true or false.
+// (Bools are the only values with a known set of cases. Anything else needs a 'case _:' to be exhaustive.)
+fn check_cases(cases: &[Case], span: Span, error_manager: &mut ErrorManager)
 {
-    let mut buffer = String::new();
-    let mut started_call_or_function = false;
+    let catch_all = cases.iter().position(|x| x.guard.is_none() && x.pattern.is_catch_all());
 
-    if string.is_empty()
+    if let Some(unreachable) = catch_all.and_then(|i| cases.get(i+1))
     {
-        return Ok(ExpressionType::None);
+        push_error!(error_manager, ParseWarning::new(unreachable.span, "Case can't be reached, as a case before it matches everything."));
     }
 
-    match string.to_lowercase().as_str()
+    let bools: Vec<bool> = cases.iter().filter(|x| x.guard.is_none()).filter_map(|x| match x.pattern
     {
-        "break" => {return Ok(ExpressionType::Break);}
-        "continue" => {return Ok(ExpressionType::Continue);}
-        "end" => {return Ok(ExpressionType::End);}
-        _ => ()
-    };
+        Pattern::Literal(Token::Bool(b)) => Some(b),
+        _ => None
+    }).collect();
+
+    if catch_all.is_some() || bools.is_empty()
+    {
+        return;
+    }
 
-    // Comments
-    if string.starts_with("//") || string.starts_with("#")
+    for missing in [true, false].iter().filter(|x| !bools.contains(x))
     {
-        return Ok(ExpressionType::Comment);
+        push_error!(error_manager, ParseWarning::new(span, format!("Match isn't exhaustive, as '{}' isn't handled. Add 'case {}:' or 'case _:'.", missing, missing).as_str()));
     }
+}
 
-    let mut peekable = string.chars().peekable();
-    while let Some(c) = peekable.next()
+// Error for an elif, else, catch or case that isn't part of the scope it continues.
+fn misplaced_branch(expression_type: &ExpressionType) -> &'static str
+{
+    match expression_type
     {
-        if !started_call_or_function
+        ExpressionType::Catch => "Catch can only be used after a try statement.",
+        ExpressionType::Case => "Case can only be used inside a match statement.",
+        _ => "Elif and else can only be used after an if statement."
+    }
+}
+
+// Parses a try statement, along with the catch that handles its errors.
+// The index is left on the line after the 'end'.
+fn parse_try(lines: &[Vec<LexToken>], index: &mut usize, context: ScopeContext, error_manager: &mut ErrorManager) -> Option<Statement>
+{
+    let start = *index;
+    let start_span = lexer::tokens_span(&lines[start]);
+    let mut failed = false;
+
+    if let Err(error) = parse_scope(&lines[start], &ExpressionType::Try)
+    {
+        push_error!(error_manager, ParseError::new(error.1, error.0.as_str()));
+        failed = true;
+    }
+
+    *index += 1;
+    let (body, closed_by) = parse_block(lines, index, ScopeContext { nested: true, ..context }, error_manager);
+
+    match closed_by
+    {
+        Some(ExpressionType::Catch) => (),
+        Some(ExpressionType::End) =>
         {
-            match c
+            push_error!(error_manager, ParseError::new(lexer::tokens_span(&lines[*index]), "Try statements need a 'catch' before their 'end'. (Eg. catch err:)"));
+            *index += 1;
+            return None;
+        },
+        Some(closed_by) =>
+        {
+            // Parsed as the catch, to carry on to the 'end'.
+            push_error!(error_manager, ParseError::new(lexer::tokens_span(&lines[*index]), misplaced_branch(&closed_by)));
+            failed = true;
+        },
+        None =>
+        {
+            push_error!(error_manager,
+                ParseError::new(start_span,
+                    format!("Scope starting at '{}' was not closed with an 'end' statement.", lexer::tokens_to_string(&lines[start])).as_str()));
+            return None;
+        }
+    }
+
+    let variable = match parse_scope(&lines[*index], &ExpressionType::Catch)
+    {
+        Ok(ScopeHeader::Catch(variable)) => variable,
+        Ok(_) => None,
+        Err(error) =>
+        {
+            push_error!(error_manager, ParseError::new(error.1, error.0.as_str()));
+            failed = true;
+            None
+        }
+    };
+
+    let (handler, end_span) = parse_scope_body(lines, index, ScopeContext { nested: true, ..context }, error_manager);
+
+    if failed { None } else { Some(Statement::Try { body, variable, handler, span: start_span.to(&end_span) }) }
+}
+
+// Parses the body of a loop or function, starting at the scope's first line.
+// Returns the body and the span of the 'end'. The index is left on the line after the 'end'.
+fn parse_scope_body(lines: &[Vec<LexToken>], index: &mut usize, context: ScopeContext, error_manager: &mut ErrorManager) -> (Vec<Statement>, Span)
+{
+    let start = *index;
+    let mut body: Vec<Statement> = Vec::new();
+
+    *index += 1;
+
+    loop
+    {
+        let (mut statements, closed_by) = parse_block(lines, index, context, error_manager);
+        body.append(&mut statements);
+
+        match closed_by
+        {
+            Some(ExpressionType::End) =>
+            {
+                let end_span = lexer::tokens_span(&lines[*index]);
+                *index += 1;
+
+                return (body, end_span);
+            },
+            Some(closed_by) =>
+            {
+                push_error!(error_manager, ParseError::new(lexer::tokens_span(&lines[*index]), misplaced_branch(&closed_by)));
+                *index += 1;
+            },
+            None =>
             {
-                c if c.is_alphanumeric() || c == '.' || c == '_' || c == '!' => buffer.push(c),
-                '=' => return Ok(ExpressionType::Assignment),
-                '(' => started_call_or_function = true,
-                c if utility::operations_contains(c) => (),
-                '"' | '\'' | ')' => (),
-                _ => return Err(format!("Failed to recognize character: '{}'", c))
+                let start_span = lexer::tokens_span(&lines[start]);
+
+                push_error!(error_manager, 
+                    ParseError::new(start_span,
+                        format!("Scope starting at '{}' was not closed with an 'end' statement.", lexer::tokens_to_string(&lines[start])).as_str()));
+
+                return (body, start_span);
             }
         }
-        // Check for the end of a function creation.
-        else
+    }
+}
+
+// Parses the fields of a struct, starting at the struct's first line.
+// Each line is a field name, with an optional default value. (Eg. x = 0) Fields without one default to Null.
+// Returns the fields and the span of the 'end'. The index is left on the line after the 'end'.
+fn parse_struct_fields(lines: &[Vec<LexToken>], index: &mut usize, error_manager: &mut ErrorManager) -> (Vec<(String, Operation)>, Span)
+{
+    let start = *index;
+    let mut fields: Vec<(String, Operation)> = Vec::new();
+
+    *index += 1;
+
+    while *index < lines.len()
+    {
+        let line = &lines[*index];
+        let line_span = lexer::tokens_span(line);
+        *index += 1;
+
+        let (name, default) = match line.as_slice()
         {
-            if let ':' = c
+            [end] if end.is_keyword("end") => { return (fields, line_span); }
+            [name] if name.identifier().is_some() => (name.to_string(), Some(vec![Token::Null])),
+            [name, assign, value @ ..] if name.identifier().is_some() && assign.is_symbol("=") && !value.is_empty() =>
+            {
+                (name.to_string(), parse_operation(value, error_manager))
+            },
+            _ =>
             {
-                return Ok(ExpressionType::Function);
+                push_error!(error_manager, ParseError::new(line_span, "Struct fields need a name, with an optional default value. (Eg. x = 0)"));
+                continue;
             }
+        };
+
+        if fields.iter().any(|x| x.0 == name)
+        {
+            push_error!(error_manager, ParseError::new(line_span, format!("Struct field '{}' is declared more than once.", name).as_str()));
+        }
+        else if let Some(default) = default
+        {
+            fields.push((name, default));
         }
+    }
+
+    let start_span = lexer::tokens_span(&lines[start]);
 
-        // Buffer check for the start of an expression type.
-        // Later parsing will check for errors in missing colons or arguments.
-        match buffer.to_lowercase().as_str()
+    push_error!(error_manager,
+        ParseError::new(start_span,
+            format!("Scope starting at '{}' was not closed with an 'end' statement.", lexer::tokens_to_string(&lines[start])).as_str()));
+
+    (fields, start_span)
+}
+
+// Parses an operation, pushing an error at the failing token if it couldn't be parsed.
+fn parse_operation(tokens: &[LexToken], error_manager: &mut ErrorManager) -> Option<Operation>
+{
+    match operation_parser::parse_operation_tokens(tokens)
+    {
+        Ok(operation) => Some(operation),
+        Err(error) =>
+        {
+            parse_error!(error_manager, error.1, error.0.as_str());
+            None
+        }
+    }
+}
+
+pub fn get_expression_type(tokens: &[LexToken]) -> Result<ExpressionType, String>
+{
+    let first = match tokens.first()
+    {
+        Some(first) => first,
+        None => { return Ok(ExpressionType::None); }
+    };
+
+    if tokens.len() == 1
+    {
+        if first.is_keyword("break") { return Ok(ExpressionType::Break); }
+        if first.is_keyword("continue") { return Ok(ExpressionType::Continue); }
+        if first.is_keyword("end") { return Ok(ExpressionType::End); }
+    }
+
+    // Keywords at the start of an expression.
+    // Later parsing will check for errors in missing colons or arguments.
+    if let Some(keyword) = first.identifier()
+    {
+        match keyword.to_lowercase().as_str()
         {
             "loop" => return Ok(ExpressionType::Loop),
+            "struct" if tokens.len() > 1 => return Ok(ExpressionType::Struct),
             "if" => return Ok(ExpressionType::If),
             "elif"|"elseif" => return Ok(ExpressionType::Elif),
-            "else" => {if let Some('i') = peekable.peek() {} else { return Ok(ExpressionType::Else)}},
+            "else" => return Ok(if tokens.len() > 1 && tokens[1].is_keyword("if") { ExpressionType::Elif } else { ExpressionType::Else }),
             "return" => return Ok(ExpressionType::Return),
+            "match" if tokens.len() > 1 && tokens[tokens.len()-1].is_symbol(":") => return Ok(ExpressionType::Match),
+            "case" if tokens.len() > 1 && tokens[tokens.len()-1].is_symbol(":") => return Ok(ExpressionType::Case),
+            "try" => return Ok(ExpressionType::Try),
+            "catch" => return Ok(ExpressionType::Catch),
+            "raise" => return Ok(ExpressionType::Raise),
             "use"|"import"|"include"|"using" => return Ok(ExpressionType::Library),
             _ => ()
         }
     }
-
-    // Found a call but not a function.
-    // (Char search closed out before finding a colon.)
-    if started_call_or_function
+    else
     {
-        return Ok(ExpressionType::Call);
+        return Err(format!("Failed to recognize '{}' at the start of an expression.", first));
     }
 
-    // check for additional ++ or --
-    if string.ends_with("++") || string.ends_with("--")
+    let mut i = 0;
+    while i < tokens.len()
     {
-        return Ok(ExpressionType::Assignment);
+        let token = &tokens[i];
+        i += 1;
+
+        match &token.kind
+        {
+            LexKind::Identifier(_) | LexKind::Number(_) => (),
+            // Keys or indexes being assigned. (Eg. m["hp"] = 3)
+            LexKind::Symbol(s) if s == "[" =>
+            {
+                i = match lexer::find_closing(tokens, i-1)
+                {
+                    Some(close) => close + 1,
+                    None => { return Err("Too few brackets/parenthises found. Make sure to enclose your expressions correctly.".to_string()); }
+                };
+            },
+            LexKind::Symbol(s) if s == "." || s == "!" => (),
+            LexKind::Symbol(s) if s == "=" || s == "+=" || s == "-=" || s == "*=" || s == "/=" => return Ok(ExpressionType::Assignment),
+            // check for additional ++ or --
+            LexKind::Symbol(s) if (s == "++" || s == "--") && i == tokens.len() => return Ok(ExpressionType::Assignment),
+            LexKind::Symbol(s) if s == "(" =>
+            {
+                // Check for the end of a function creation.
+                if tokens[tokens.len()-1].is_symbol(":")
+                {
+                    return Ok(ExpressionType::Function);
+                }
+
+                // Found a call but not a function.
+                return Ok(ExpressionType::Call);
+            },
+            _ => return Err(format!("Failed to recognize '{}' in expression.", token))
+        }
     }
 
     Err("Unkown expression.".to_string())
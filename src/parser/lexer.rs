@@ -0,0 +1,643 @@
+use std::fmt::{self, Display};
+
+use crate::external::format;
+use crate::types::Span;
+use crate::types::error::*;
+use crate::utility;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexKind
+{
+    // Names, keywords and boolean values.
+    Identifier(String),
+    // Unparsed numeric text. (Converted into Int or Float by the operation parser.)
+    Number(String),
+    StringLiteral(String),
+    // Template of an f-string, with the tokens of each expression between its braces. (Eg. f"HP: {hp}")
+    FormatString(String, Vec<Vec<LexToken>>),
+    CharLiteral(String),
+    // Operators and punctuation.
+    Symbol(String),
+    // End of a statement. (New line or ';')
+    Newline,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexToken
+{
+    pub kind: LexKind,
+    pub span: Span,
+}
+
+impl LexToken
+{
+    pub fn is_symbol(&self, symbol: &str) -> bool
+    {
+        matches!(&self.kind, LexKind::Symbol(s) if s == symbol)
+    }
+
+    // Keywords are matched without case, the same as the rest of the language.
+    pub fn is_keyword(&self, keyword: &str) -> bool
+    {
+        matches!(&self.kind, LexKind::Identifier(s) if s.to_lowercase() == keyword)
+    }
+
+    pub fn identifier(&self) -> Option<&str>
+    {
+        if let LexKind::Identifier(s) = &self.kind { Some(s.as_str()) } else { None }
+    }
+}
+
+// Converts a token back into source text.
+impl Display for LexKind
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            LexKind::Identifier(s) | LexKind::Number(s) | LexKind::Symbol(s) => write!(f, "{}", s),
+            LexKind::StringLiteral(s) => write!(f, "\"{}\"", s),
+            LexKind::FormatString(template, expressions) =>
+            {
+                // Put each expression back in front of its spec.
+                let mut expressions = expressions.iter();
+                let mut text = String::new();
+
+                let mut chars = template.chars().peekable();
+                while let Some(c) = chars.next()
+                {
+                    text.push(c);
+                    match c
+                    {
+                        '{' | '}' if chars.peek() == Some(&c) => { chars.next(); text.push(c); },
+                        '{' => { text.push_str(&expressions.next().map(|x| tokens_to_string(x)).unwrap_or_default()); },
+                        _ => {}
+                    }
+                }
+
+                write!(f, "f\"{}\"", text)
+            },
+            LexKind::CharLiteral(s) => write!(f, "'{}'", s),
+            LexKind::Newline => writeln!(f),
+        }
+    }
+}
+
+impl Display for LexToken
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.kind)
+    }
+}
+
+// Symbols made of two characters. Checked before single character symbols.
+const DOUBLE_SYMBOLS: [&str; 14] = [
+    "+=", "-=", "*=", "/=", "++", "--",
+    "&&", "||", "<=", ">=", "==", "!=",
+    "..", "=>"
+];
+
+const SINGLE_SYMBOLS: [char; 10] = ['(', ')', '[', ']', '{', '}', ',', '.', ':', '!'];
+
+// Splits the source of a script into tokens, keeping track of where each token came from.
+// Whitespace and comments are dropped, new lines and ';' become Newline tokens.
+pub fn tokenize(source: &str, error_manager: &mut ErrorManager) -> Vec<LexToken>
+{
+    let mut tokens: Vec<LexToken> = Vec::new();
+
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    lex_chars(&chars, 0, (1, 0), &mut tokens, error_manager);
+
+    tokens
+}
+
+// Lexes from the index until the end of the characters, starting at the given line.
+// Also used for the expressions of f-strings, so their tokens keep where they are in the source.
+fn lex_chars(chars: &[(usize, char)], mut i: usize, (mut line, mut line_start): (usize, usize), tokens: &mut Vec<LexToken>, error_manager: &mut ErrorManager)
+{
+    let offset_at = |index: usize| if index < chars.len() { chars[index].0 } else { source_end(chars) };
+    let text = |start: usize, end: usize| chars[start..end].iter().map(|x| x.1).collect::<String>();
+
+    while i < chars.len()
+    {
+        let (offset, c) = chars[i];
+        let column = i - line_start + 1;
+
+        match c
+        {
+            '\n' | ';' =>
+            {
+                tokens.push(LexToken { kind: LexKind::Newline, span: Span::new(offset, offset+1, line, column) });
+
+                if c == '\n'
+                {
+                    line += 1;
+                    line_start = i+1;
+                }
+                i += 1;
+            },
+            // Allow multiple lines using '\'
+            '\\' if next_line_start(chars, i+1).is_some() =>
+            {
+                i = next_line_start(chars, i+1).unwrap();
+                line += 1;
+                line_start = i;
+            },
+            c if c.is_whitespace() => { i += 1; },
+            // Raw strings keep backslashes as they are. (Eg. r"C:\path")
+            'r' if matches!(chars.get(i+1), Some((_, '"'))) =>
+            {
+                let start = i;
+                i = lex_string(chars, i+1, StringKind::Raw, (start, line, line_start), tokens, error_manager);
+                count_lines(chars, start, i, &mut line, &mut line_start);
+            },
+            // Format strings. (Eg. f"HP: {hp}/{max_hp:.1}")
+            'f' if matches!(chars.get(i+1), Some((_, '"'))) =>
+            {
+                let start = i;
+                i = lex_string(chars, i+1, StringKind::Format, (start, line, line_start), tokens, error_manager);
+                count_lines(chars, start, i, &mut line, &mut line_start);
+            },
+            // Comments run until the end of the line.
+            '#' => { i = skip_comment(chars, i); },
+            '/' if matches!(chars.get(i+1), Some((_, '/'))) => { i = skip_comment(chars, i); },
+            c if c.is_alphabetic() || c == '_' =>
+            {
+                let start = i;
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') { i += 1; }
+
+                tokens.push(LexToken
+                {
+                    kind: LexKind::Identifier(text(start, i)),
+                    span: Span::new(offset, offset_at(i), line, column)
+                });
+            },
+            c if c.is_ascii_digit() =>
+            {
+                let start = i;
+                while i < chars.len() && chars[i].1.is_ascii_digit() { i += 1; }
+
+                // Numbers directly after an accessor are indexes, so they can't have a decimal point.
+                // (Eg. pos.0.1)
+                let after_accessor = matches!(tokens.last(), Some(token) if token.is_symbol("."));
+
+                if !after_accessor && i+1 < chars.len() && chars[i].1 == '.' && chars[i+1].1.is_ascii_digit()
+                {
+                    i += 1;
+                    while i < chars.len() && chars[i].1.is_ascii_digit() { i += 1; }
+                }
+
+                tokens.push(LexToken
+                {
+                    kind: LexKind::Number(text(start, i)),
+                    span: Span::new(offset, offset_at(i), line, column)
+                });
+            },
+            '"' =>
+            {
+                let start = i;
+                i = lex_string(chars, i, StringKind::Plain, (start, line, line_start), tokens, error_manager);
+                count_lines(chars, start, i, &mut line, &mut line_start);
+            },
+            '\'' =>
+            {
+                i += 1;
+                let mut value = String::new();
+
+                while i < chars.len() && chars[i].1 != '\'' && chars[i].1 != '\n'
+                {
+                    i = read_char(chars, i, false, &mut value, error_manager, (line, column));
+                }
+
+                if i >= chars.len() || chars[i].1 != '\''
+                {
+                    push_error!(error_manager, ParseError::new(Span::new(offset, offset_at(i), line, column), "Char was not enclosed."));
+                    continue;
+                }
+                i += 1;
+
+                tokens.push(LexToken
+                {
+                    kind: LexKind::CharLiteral(value),
+                    span: Span::new(offset, offset_at(i), line, column)
+                });
+            },
+            _ =>
+            {
+                let double: String = chars[i..(i+2).min(chars.len())].iter().map(|x| x.1).collect();
+
+                let symbol = if DOUBLE_SYMBOLS.contains(&double.as_str())
+                {
+                    i += 2;
+                    double
+                }
+                else if SINGLE_SYMBOLS.contains(&c) || c == '=' || utility::operations_contains(c)
+                {
+                    i += 1;
+                    c.to_string()
+                }
+                else
+                {
+                    push_error!(error_manager, ParseError::new(Span::new(offset, offset_at(i+1), line, column),
+                        format!("Failed to recognize character: '{}'", c).as_str()));
+                    i += 1;
+                    continue;
+                };
+
+                tokens.push(LexToken
+                {
+                    kind: LexKind::Symbol(symbol),
+                    span: Span::new(offset, offset_at(i), line, column)
+                });
+            }
+        }
+    }
+}
+
+// How the text between a string's quotes is read.
+#[derive(Clone, Copy, PartialEq)]
+enum StringKind
+{
+    Plain,
+    // Backslashes are kept as they are.
+    Raw,
+    // Expressions between braces are lexed into their own tokens.
+    Format,
+}
+
+// Reads a string starting at its opening quote, returning the index after it.
+// Strings opened with three quotes run until three closing quotes and can span lines.
+// A line break directly after the opening quotes is left out, so the text can start on its own line.
+fn lex_string(chars: &[(usize, char)], mut i: usize, kind: StringKind, (start, line, line_start): (usize, usize, usize),
+              tokens: &mut Vec<LexToken>, error_manager: &mut ErrorManager) -> usize
+{
+    let offset_at = |index: usize| if index < chars.len() { chars[index].0 } else { source_end(chars) };
+    let (offset, column) = (chars[start].0, start - line_start + 1);
+
+    let multi_line = is_quotes(chars, i, 3);
+    let quote_count = if multi_line { 3 } else { 1 };
+    i += quote_count;
+
+    if multi_line
+    {
+        if matches!(chars.get(i), Some((_, '\r'))) { i += 1; }
+        if matches!(chars.get(i), Some((_, '\n'))) { i += 1; }
+    }
+
+    let mut value = String::new();
+    let mut expressions: Vec<Vec<LexToken>> = Vec::new();
+
+    while i < chars.len() && !is_quotes(chars, i, quote_count) && (multi_line || chars[i].1 != '\n')
+    {
+        i = match chars[i].1
+        {
+            '{' | '}' if kind == StringKind::Format =>
+                lex_format_part(chars, i, multi_line, (line, line_start), &mut value, &mut expressions, error_manager),
+            _ => read_char(chars, i, kind == StringKind::Raw, &mut value, error_manager, (line, column))
+        };
+    }
+
+    if !is_quotes(chars, i, quote_count)
+    {
+        push_error!(error_manager, ParseError::new(Span::new(offset, offset_at(i), line, column), "String was not enclosed."));
+        return i;
+    }
+    i += quote_count;
+
+    let kind = match kind
+    {
+        StringKind::Format => LexKind::FormatString(value, expressions),
+        _ => LexKind::StringLiteral(value)
+    };
+
+    tokens.push(LexToken
+    {
+        kind,
+        span: Span::new(offset, offset_at(i), line, column)
+    });
+
+    i
+}
+
+// Reads a brace in an f-string. Doubled braces are left in the template as they are.
+// The expression inside a placeholder is lexed where it is, and only its spec is kept in the template.
+// (Eg. {hp:>3} becomes {:>3})
+fn lex_format_part(chars: &[(usize, char)], i: usize, multi_line: bool, (mut line, mut line_start): (usize, usize),
+                   template: &mut String, expressions: &mut Vec<Vec<LexToken>>, error_manager: &mut ErrorManager) -> usize
+{
+    let c = chars[i].1;
+
+    count_lines(chars, line_start, i, &mut line, &mut line_start);
+    let column = i - line_start + 1;
+
+    if matches!(chars.get(i+1), Some((_, next)) if *next == c)
+    {
+        template.push(c);
+        template.push(c);
+        return i + 2;
+    }
+
+    if c == '}'
+    {
+        push_error!(error_manager, ParseError::new(Span::new(chars[i].0, chars[i].0 + 1, line, column),
+            "Unmatched '}' in format string. Use '}}' for a brace."));
+        return i + 1;
+    }
+
+    let (close, colon) = match find_format_close(chars, i, multi_line)
+    {
+        Some(found) => found,
+        None =>
+        {
+            push_error!(error_manager, ParseError::new(Span::new(chars[i].0, chars[i].0 + 1, line, column), "Unclosed '{' in format string."));
+            return i + 1;
+        }
+    };
+    let placeholder_span = Span::new(chars[i].0, chars[close].0 + 1, line, column);
+
+    let mut expression: Vec<LexToken> = Vec::new();
+    lex_chars(&chars[..colon.unwrap_or(close)], i+1, (line, line_start), &mut expression, error_manager);
+    expression.retain(|x| x.kind != LexKind::Newline);
+
+    if expression.is_empty()
+    {
+        push_error!(error_manager, ParseError::new(placeholder_span, "Expected an expression between '{' and '}' in format string."));
+    }
+
+    let spec: String = colon.map(|x| chars[x+1..close].iter().map(|x| x.1).collect()).unwrap_or_default();
+    if let Err(error) = format::parse_spec(&spec)
+    {
+        push_error!(error_manager, ParseError::new(placeholder_span, error.as_str()));
+    }
+
+    template.push('{');
+    if colon.is_some()
+    {
+        template.push(':');
+        template.push_str(&spec);
+    }
+    template.push('}');
+
+    expressions.push(expression);
+    close + 1
+}
+
+// Finds the brace closing an expression in an f-string, along with the ':' starting its spec.
+// Brackets and quotes inside the expression are skipped over, stopping at the end of the string.
+fn find_format_close(chars: &[(usize, char)], start: usize, multi_line: bool) -> Option<(usize, Option<usize>)>
+{
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut colon = None;
+
+    for (i, (_, c)) in chars.iter().enumerate().skip(start + 1)
+    {
+        match (*c, quote)
+        {
+            ('\n', _) if !multi_line => { return None; },
+            (c, Some(q)) if c == q => { quote = None; },
+            (_, Some(_)) => {},
+            ('"', None) if !multi_line || is_quotes(chars, i, 3) => { return None; },
+            ('"' | '\'', None) => { quote = Some(*c); },
+            ('(' | '[' | '{', None) => { depth += 1; },
+            ('}', None) if depth == 0 => { return Some((i, colon)); },
+            (')' | ']' | '}', None) => { depth -= 1; },
+            (':', None) if depth == 0 && colon.is_none() => { colon = Some(i); },
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn is_quotes(chars: &[(usize, char)], index: usize, count: usize) -> bool
+{
+    (index..index+count).all(|x| matches!(chars.get(x), Some((_, '"'))))
+}
+
+// The byte offset just after the last character.
+fn source_end(chars: &[(usize, char)]) -> usize
+{
+    chars.last().map_or(0, |x| x.0 + x.1.len_utf8())
+}
+
+// Reads a character of a string or char literal, along with its escape sequence.
+// (\n, \t, \r, \0, \\, \", \' and \u{...}) A backslash at the end of a line joins it with the next.
+fn read_char(chars: &[(usize, char)], i: usize, raw: bool, value: &mut String, error_manager: &mut ErrorManager, (line, column): (usize, usize)) -> usize
+{
+    let c = chars[i].1;
+
+    if c != '\\' || raw || i+1 >= chars.len()
+    {
+        value.push(c);
+        return i + 1;
+    }
+
+    let escaped = match chars[i+1].1
+    {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        '\\' => '\\',
+        '"' => '"',
+        '\'' => '\'',
+        'u' => { return read_unicode(chars, i, value, error_manager, (line, column)); },
+        '\n' => { return i + 2; },
+        other =>
+        {
+            push_error!(error_manager, ParseError::new(Span::new(chars[i].0, chars[i+1].0 + other.len_utf8(), line, column),
+                format!("Unknown escape sequence: '\\{}'", other).as_str()));
+            other
+        }
+    };
+
+    value.push(escaped);
+    i + 2
+}
+
+// Reads a unicode escape, such as \u{1F600}, from the backslash.
+fn read_unicode(chars: &[(usize, char)], i: usize, value: &mut String, error_manager: &mut ErrorManager, (line, column): (usize, usize)) -> usize
+{
+    let close = chars.iter().skip(i).take(12).position(|x| x.1 == '}' || x.1 == '\n').map(|x| x + i);
+
+    let escaped = match close
+    {
+        Some(close) if chars[close].1 == '}' && matches!(chars.get(i+2), Some((_, '{'))) =>
+        {
+            let digits: String = chars[i+3..close].iter().map(|x| x.1).collect();
+            u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32).map(|x| (x, close + 1))
+        },
+        _ => None
+    };
+
+    match escaped
+    {
+        Some((escaped, next)) =>
+        {
+            value.push(escaped);
+            next
+        },
+        None =>
+        {
+            push_error!(error_manager, ParseError::new(Span::new(chars[i].0, chars[i+1].0 + 1, line, column),
+                "Invalid unicode escape. Expected a hex code point such as '\\u{1F600}'."));
+            i + 2
+        }
+    }
+}
+
+// Moves the line count past any line breaks in the characters between the indexes.
+fn count_lines(chars: &[(usize, char)], start: usize, end: usize, line: &mut usize, line_start: &mut usize)
+{
+    for (i, _) in chars.iter().enumerate().take(end).skip(start).filter(|(_, x)| x.1 == '\n')
+    {
+        *line += 1;
+        *line_start = i+1;
+    }
+}
+
+// Splits tokens into the statements they make up. Empty statements are dropped.
+pub fn split_lines(tokens: Vec<LexToken>) -> Vec<Vec<LexToken>>
+{
+    let mut lines: Vec<Vec<LexToken>> = Vec::new();
+    let mut current: Vec<LexToken> = Vec::new();
+
+    for token in tokens
+    {
+        if let LexKind::Newline = token.kind
+        {
+            if !current.is_empty()
+            {
+                lines.push(current);
+                current = Vec::new();
+            }
+        }
+        else
+        {
+            current.push(token);
+        }
+    }
+
+    if !current.is_empty()
+    {
+        lines.push(current);
+    }
+
+    lines
+}
+
+// The span covering all of the passed tokens.
+pub fn tokens_span(tokens: &[LexToken]) -> Span
+{
+    match (tokens.first(), tokens.last())
+    {
+        (Some(first), Some(last)) => first.span.to(&last.span),
+        _ => Span::default()
+    }
+}
+
+// Converts tokens back into (whitespace separated) source text.
+pub fn tokens_to_string(tokens: &[LexToken]) -> String
+{
+    tokens.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+// Splits tokens by a symbol, ignoring any found inside brackets.
+pub fn split_tokens<'a>(tokens: &'a [LexToken], symbol: &str) -> Result<Vec<&'a [LexToken]>, (String, Span)>
+{
+    let mut result: Vec<&[LexToken]> = Vec::new();
+
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, token) in tokens.iter().enumerate()
+    {
+        if is_open_bracket(token)
+        {
+            depth += 1;
+        }
+        else if is_close_bracket(token)
+        {
+            depth -= 1;
+
+            // If depth is ever less than 0, there is a bracket in excess.
+            if depth < 0
+            {
+                return Err((format!("Excess '{}' found. Make sure to properly enclose your statements.", token), token.span));
+            }
+        }
+        else if depth == 0 && token.is_symbol(symbol)
+        {
+            result.push(&tokens[start..i]);
+            start = i+1;
+        }
+    }
+
+    // Too few brackets found.
+    if depth > 0
+    {
+        return Err(("Too few brackets/parenthises found. Make sure to enclose your expressions correctly.".to_string(), tokens_span(tokens)));
+    }
+
+    if !tokens.is_empty()
+    {
+        result.push(&tokens[start..]);
+    }
+
+    Ok(result)
+}
+
+// Finds the index of the bracket closing the one at the start index.
+pub fn find_closing(tokens: &[LexToken], start: usize) -> Option<usize>
+{
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(start)
+    {
+        if is_open_bracket(token)
+        {
+            depth += 1;
+        }
+        else if is_close_bracket(token)
+        {
+            depth -= 1;
+
+            if depth == 0
+            {
+                return Some(i);
+            }
+        }
+    }
+
+    None
+}
+
+pub fn is_open_bracket(token: &LexToken) -> bool
+{
+    token.is_symbol("(") || token.is_symbol("[") || token.is_symbol("{")
+}
+
+pub fn is_close_bracket(token: &LexToken) -> bool
+{
+    token.is_symbol(")") || token.is_symbol("]") || token.is_symbol("}")
+}
+
+fn skip_comment(chars: &[(usize, char)], mut i: usize) -> usize
+{
+    while i < chars.len() && chars[i].1 != '\n' { i += 1; }
+    i
+}
+
+// Returns the index after a line break if only whitespace is between the index and the break.
+fn next_line_start(chars: &[(usize, char)], mut i: usize) -> Option<usize>
+{
+    while i < chars.len() && chars[i].1 != '\n'
+    {
+        if !chars[i].1.is_whitespace() { return None; }
+        i += 1;
+    }
+
+    if i < chars.len() { Some(i+1) } else { None }
+}
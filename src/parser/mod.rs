@@ -1,13 +1,17 @@
 mod expression_parser;
 mod variable_parser;
 pub mod operation_parser;
+pub mod lexer;
 
 use std::fs;
+use std::io::Read;
 use std::fmt::Write;
-use crate::parser::expression_parser::parse_expressions;
+use std::rc::Rc;
+use crate::parser::expression_parser::{parse_statements, get_expression_type};
+use crate::parser::lexer::{LexToken, LexKind};
 
 use super::script_type::ScriptType;
-use super::types::Parser;
+use super::types::{Parser, Statement, FunctionDef, StructDef};
 use super::types::error::*;
 
 #[derive(PartialEq, Debug, Clone)]
@@ -22,11 +26,16 @@ pub enum ExpressionType
     Elif,
     Else,
     Loop,
+    Struct,
     Break,
     Continue,
     Library,
-    Comment,
     End,
+    Try,
+    Catch,
+    Raise,
+    Match,
+    Case,
 }
 
 impl ExpressionType
@@ -38,6 +47,9 @@ impl ExpressionType
             ExpressionType::Function => true,
             ExpressionType::If => true,
             ExpressionType::Loop => true,
+            ExpressionType::Struct => true,
+            ExpressionType::Try => true,
+            ExpressionType::Match => true,
             _ => false
         }
     }
@@ -51,95 +63,112 @@ impl Parser
         error_manager: &mut ErrorManager
     ) -> Result<Parser, String>
     {
-        let mut contents: String = match fs::read_to_string(&file_path)
+        let contents: String = match fs::read_to_string(file_path)
         {
             Ok(string) => string,
             Err(_) => { return Err(format!("Error reading from file: {}", &file_path)); }
         };
 
-        if contents.is_empty()
+        Parser::parse_str(&contents, file_path, error_manager)
+    }
+
+    // Parses a script from any reader (Eg. an asset pack entry).
+    // The name is only used to identify the script in errors.
+    pub fn parse_reader<R: Read>(
+        mut reader: R,
+        name: &str,
+        error_manager: &mut ErrorManager
+    ) -> Result<Parser, String>
+    {
+        let mut contents = String::new();
+
+        if let Err(error) = reader.read_to_string(&mut contents)
+        {
+            return Err(format!("Error reading from {}: {}", name, error));
+        }
+
+        Parser::parse_str(&contents, name, error_manager)
+    }
+
+    // Parses a script that is already in memory.
+    // The name is a virtual file name that is used to identify the script in errors.
+    pub fn parse_str(
+        source: &str,
+        name: &str,
+        error_manager: &mut ErrorManager
+    ) -> Result<Parser, String>
+    {
+        if source.is_empty()
         {
             let mut s = String::new();
-            write!(&mut s, "Contents of {} were empty.", file_path).ok();
+            write!(&mut s, "Contents of {} were empty.", name).ok();
             return Err(s);
         }
 
-        // Allow multiple lines using '\'
-        contents = contents.replace("\\\r\n", "");
+        // Errors are collected separately so they can be tagged with the script name.
+        let mut script_errors = ErrorManager::new();
 
-        let lines: Vec<String> = 
-            Parser::handle_content_replace(&contents, error_manager);
+        let tokens = lexer::tokenize(source, &mut script_errors);
+        let mut lines = lexer::split_lines(tokens);
 
-        // Determining Script Type.
-        // let first_line = &lines[0][2..].trim_end_matches(";");
-        // let script_type = ScriptType::from_string(first_line);
+        let script_type = parse_script_type(&mut lines, &mut script_errors);
 
-        // if let ScriptType::None = script_type
-        // {
-        //     return  Err(format!("Unexpected script type: {}", first_line));
-        // }
+        // Parse global statements.
+        let statements = parse_statements(&lines, &mut script_errors);
+        variable_parser::check_constant_assignments(&statements, &mut script_errors);
 
-        // Parse global expressions.
-        let global_expressions = parse_expressions(&lines[0..].to_vec(), 0, error_manager, &ExpressionType::None, false);
+        error_manager.merge_script(script_errors, name);
 
         Ok(Parser
         {
-            script_type: ScriptType::None,
-            global_expressions,
+            name: name.to_string(),
+            script_type,
+            statements,
         })
     }
 
-    // Parses the content by removing empty spaces and placing semi colons at the end of lines.
-    fn handle_content_replace(string: &str, error_manager: &mut ErrorManager) -> Vec<String>
+    // Finds a struct declared in the script by name.
+    pub fn find_struct(&self, name: &str) -> Option<Rc<StructDef>>
+    {
+        self.statements.iter().find_map(|x|
+            match x
+            {
+                Statement::Struct(definition) if definition.name == name => Some(definition.clone()),
+                _ => None
+            })
+    }
+
+    // Finds a function defined in the script by name.
+    pub fn find_function(&self, name: &str) -> Option<Rc<FunctionDef>>
     {
-        let mut new_string: Vec<String> = Vec::new();
+        self.statements.iter().find_map(|x|
+            match x
+            {
+                Statement::Function(function) if function.name == name => Some(function.clone()),
+                _ => None
+            })
+    }
+}
 
-        let mut string_finished = false;
+// Determines the script type from a header on the first line. (Eg. System)
+// The header is removed so it isn't parsed as a statement. Scripts without one have no type.
+fn parse_script_type(lines: &mut Vec<Vec<LexToken>>, error_manager: &mut ErrorManager) -> ScriptType
+{
+    let header = match lines.first().map(|x| x.as_slice())
+    {
+        // A lone name isn't a statement, so it can only be a header.
+        Some([LexToken { kind: LexKind::Identifier(name), .. }]) if get_expression_type(&lines[0]).is_err() => name.clone(),
+        _ => { return ScriptType::None; }
+    };
 
-        for (index, line) in string.lines().enumerate()
-        {
-            // Remove all empty spaces except when in string literal.
-            let mut new_line = String::new();
-            let mut in_string_literal = false;
+    let script_type = ScriptType::from_string(&header);
 
-            for c in line.chars()
-            {
-                // Start new line line after semi-colons.
-                if c == ';'
-                {
-                    new_string.push(format!("{}){}", index+1, new_line));
-                    string_finished = true;
-
-                    new_line = String::new();
-
-                    if in_string_literal
-                    {
-                        push_error!(error_manager, ParseError::new(index, "String was not enclosed."));
-                    }
-                    in_string_literal = false;
-                    continue;
-                }
-
-                string_finished = false;
-
-                // Don't include white space unless in string literal.
-                if (!c.is_whitespace()) || in_string_literal
-                {
-                    new_line.push(c);
-                }
-
-                if c == '"' || c == '\''
-                {
-                    in_string_literal = !in_string_literal;
-                }
-            }
-
-            if !string_finished
-            {
-                new_string.push(format!("{}){}", index+1, new_line));
-            }
-        }
-    
-        new_string
+    if let ScriptType::None = script_type
+    {
+        push_error!(error_manager, ScriptTypeError::new(header));
     }
+
+    lines.remove(0);
+
+    script_type
 }
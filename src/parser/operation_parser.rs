@@ -1,483 +1,443 @@
 use std::collections::{VecDeque, HashMap};
+use std::rc::Rc;
+use linked_hash_map::LinkedHashMap;
 
-use crate::types::Token;
+use crate::types::{Token, Span, Statement, FunctionDef, Parameter, Pattern, Function};
+use crate::types::error::ErrorManager;
 use crate::utility;
 
-// Used internally for parsing tokens from an operation.
-// (Without the values associated with Types::Token)
-#[derive(PartialEq, Clone, Debug)]
-enum ParseTokenType
+use super::lexer::{self, LexKind, LexToken};
+
+// Parses an operation from source text.
+// Used for text that was not tokenized with the rest of the script.
+pub fn parse_operation(string: &str) -> Result<Vec<Token>, String>
 {
-    None,
-    Value,
-    StringLiteral,
-    CharLiteral,
-    Collection,
-
-    Operator,
-    Parenth,
-    //Function Call
-    Call,
-    Accessor
-}
+    let mut error_manager = ErrorManager::new();
+    let tokens = lexer::tokenize(string, &mut error_manager);
+
+    if let Some(error) = error_manager.errors.pop_front()
+    {
+        return Err(error.to_string());
+    }
+
+    let tokens: Vec<LexToken> = tokens.into_iter().filter(|x| x.kind != LexKind::Newline).collect();
 
-use ParseTokenType as PTT;
+    parse_operation_tokens(&tokens).map_err(|error| error.0)
+}
 
 // Hybrid polish notation/ast tree. Internal operations (Expressed in parentheses)
 // are put into a recursive calculation.
-pub fn parse_operation<'a>(string: & str) -> Result<Vec<Token>, String>
+// Errors include the span of the token that could not be parsed.
+pub fn parse_operation_tokens(tokens: &[LexToken]) -> Result<Vec<Token>, (String, Span)>
 {
-    let mut last_token_type = ParseTokenType::None;
-    let mut token_start: usize = 0;
-    let mut token_end: usize = 0;
-    
-    let mut inner_parenth_count: i8 = 0;
-    let mut inner_collection_count: i8 = 0;
+    let mut operation: Vec<Token> = Vec::new();
+    let mut last_was_value = false;
 
-    // optional char is for the char operation leading to the next option.
-    let mut tokens: Vec<Token> = Vec::new();
-    let tokens_ptr = &mut tokens;
-
-    // Holds the token attached to an accessor.
-    let mut current_accessor_token = Token::Null;
-
-    // Allows for skipping parsing of the next character in the loop.
-    // Currently used by string literals if they have an accessor emmediately after.
-    let mut skip_once = false;
-
-    // Resurive check for parentheses
-    for (i, c) in string.chars().enumerate()
+    let mut i = 0;
+    while i < tokens.len()
     {
-        if skip_once
-        {
-            skip_once = false;
-            continue;
-        }
+        let token = &tokens[i];
 
-        let current_char_type =
+        match &token.kind
         {
-            if c.is_alphanumeric() || c == '_' { ParseTokenType::Value }
-            else if c == '"' { ParseTokenType::StringLiteral }
-            else if c == '\'' { ParseTokenType::CharLiteral }
-            else if utility::operations_contains(c) { ParseTokenType::Operator }
-            else if c == '(' || c == ')' { ParseTokenType::Parenth }
-            else if c == '[' || c == ']' { ParseTokenType::Collection }
-            else if c == '.' { ParseTokenType::Accessor }
-            else { ParseTokenType::None }
-        };
-
-        if last_token_type != ParseTokenType::None
-        {
-            // Determine if the for loop should continue to the next character.
-            // Current is still apart of the last type.
-            if i != string.len()-1
+            // Operators where a value is expected apply to the value after them. (Eg. -x, !done)
+            LexKind::Symbol(symbol) if !last_was_value && (symbol == "-" || symbol == "+" || symbol == "!") =>
             {
-                // Enum values not present in match are skipped.
-                // Follow the "a encounters b" pattern.
-                let skip_current = match (&last_token_type, &current_char_type)
+                match tokens.get(i+1)
                 {
-                    (PTT::Value, PTT::StringLiteral) |
-                    (PTT::Value, PTT::CharLiteral) =>
-                        {return Err("Unexpected literal after value type.".to_string());},
-                    (PTT::Value, PTT::Collection) =>
-                        {return Err("Unexpected collection after value type.".to_string());},
-                    (PTT::Value, PTT::Operator) => false,
-                    (PTT::Value, PTT::Parenth) => false,
-                    (PTT::Value, PTT::Accessor) =>
-                        if string[token_start..token_end].chars().all(|c| c.is_numeric()) { true } else { false },
-
-                    (PTT::Operator, PTT::Value) => false,
-                    (PTT::Operator, PTT::StringLiteral) => false,
-                    (PTT::Operator, PTT::CharLiteral) => false,
-                    (PTT::Operator, PTT::Collection) => false,
-                    (PTT::Operator, PTT::Parenth) => false,
-                    
-                    (PTT::StringLiteral, PTT::StringLiteral) => false,
-                    (PTT::CharLiteral, PTT::CharLiteral) => false,
-
-                    (PTT::Collection, PTT::Collection) => false,
-
-                    (PTT::Parenth, PTT::Parenth) => false,
-
-                    (PTT::Call, PTT::Parenth) => false,
-
-                    (PTT::Accessor, PTT::Accessor) => false,
-                    (PTT::Accessor, PTT::Collection) => false,
-                    (PTT::Accessor, PTT::Operator) => false,
-                    (PTT::Accessor, PTT::Value) => false,
-                    (PTT::Accessor, PTT::Parenth) => false,
-
-                    _ => true
-                };
-
-                if skip_current
-                {
-                    token_end = i+1;
-                    continue;
+                    // Negative literals don't need to be run. (Unless something is accessed on them, or they're raised to a power.)
+                    Some(LexToken { kind: LexKind::Number(number), .. })
+                        if symbol == "-" && !matches!(tokens.get(i+2), Some(next) if next.is_symbol(".") || next.is_symbol("[") || next.is_symbol("^")) =>
+                    {
+                        operation.push(parse_token_value(&format!("-{}", number), false, false));
+                        last_was_value = true;
+                        i += 2;
+                    },
+                    Some(_) =>
+                    {
+                        let operator = if symbol == "!" { symbol.clone() } else { format!("u{}", symbol) };
+                        operation.push(Token::Operator(operator));
+                        i += 1;
+                    },
+                    None => { return Err((format!("Expected a value after '{}'.", symbol), token.span)); }
                 }
-            }
-            // The following else ifs' handle the last token value base on what kind of change happened.
-
-            // Finish off value or string literal.
-            let is_literal = last_token_type == ParseTokenType::StringLiteral;
-            let is_char = last_token_type == ParseTokenType::CharLiteral;
-            let used_accessor = current_char_type == ParseTokenType::Accessor;
-
-            if (last_token_type == ParseTokenType::Value && current_char_type != ParseTokenType::Value) ||
-               is_literal || is_char
+            },
+            LexKind::Symbol(symbol) if utility::OPERATIONS.contains(&symbol.as_str()) =>
+            {
+                operation.push(Token::Operator(symbol.clone()));
+                last_was_value = false;
+                i += 1;
+            },
+            _ =>
             {
-                // If current is parenth and this is not a string literal, it's the start of a call.
-                if current_char_type == ParseTokenType::Parenth &&
-                    last_token_type != ParseTokenType::StringLiteral &&
-                    last_token_type != ParseTokenType::CharLiteral &&
-                    last_token_type != ParseTokenType::Collection
+                if last_was_value
                 {
-                    // Start call but don't reset token_start.
-                    last_token_type = ParseTokenType::Call;
+                    return Err((format!("Expected an operator before '{}'.", token), token.span));
                 }
-                // Otherwise it's just a value.
-                else
-                {
-                    let value = &string[token_start..token_end];
-                    // Make sure if char, it is only one character.
-                    if is_char
-                    {
-                        if value.len() < 1
-                        {
-                            return Err(format!("Tried to create a char type with no characters."));
-                        }
-                        else if value.len() > 1
-                        {
-                            return Err(format!("Too many characters to create a valid char type."));
-                        }
-                    }
 
-                    let token = parse_token_value(&string[token_start..token_end], is_literal, is_char);
-                    // Check for accessor on current char or after in the case of a string literal.
-                    if (used_accessor || (is_literal && i+1 <string.len() && &string[i+1..i+2] == ".")) && !is_char 
-                    {
-                        current_accessor_token = token;
-                        
-                        last_token_type = ParseTokenType::Accessor;
-                        token_start = i+1;
-                        token_end = i+1;
+                let (value, next) = parse_value(tokens, i)?;
 
-                        if is_literal || is_char
-                        {
-                            token_start += 1;
-                            token_end += 1;
-                            skip_once = true;
-                        }
-                    }
-                    else
-                    {
-                        tokens_ptr.push(token);
+                operation.push(value);
+                last_was_value = true;
+                i = next;
+            }
+        }
+    }
 
-                        last_token_type = ParseTokenType::None;
-                        token_start = i;
-                    }
+    // Handle operation order and populating.
+    Ok(handle_populating_operation(operation))
+}
 
-                    if is_literal || is_char || used_accessor
-                    {
-                        continue;
-                    }
-                }
-            }
-            // Finish off operator.
-            else if last_token_type == ParseTokenType::Operator && current_char_type != ParseTokenType::Operator
-            {
-                let found_operator = &string[token_start..token_end];
-                if utility::OPERATIONS.iter().any(|x| x == &found_operator)
-                {
-                    tokens_ptr.push(Token::Operator(found_operator.to_string()));
-                }
-                else
-                {
-                    return Err(format!("{} Operation was not recognized.", found_operator));
-                }
+// Parses a single value starting at the index, along with any accessors after it.
+// Returns the value and the index of the token after it.
+fn parse_value(tokens: &[LexToken], start: usize) -> Result<(Token, usize), (String, Span)>
+{
+    let token = &tokens[start];
+    let mut i = start + 1;
 
-                last_token_type = ParseTokenType::None;
-                token_start = i;
-            }
-            // Finish off collection.
-            else if last_token_type == ParseTokenType::Collection
+    let mut value = match &token.kind
+    {
+        // Lambdas take the rest of the operation as their body.
+        LexKind::Identifier(name) if name == "fn" && i < tokens.len() && tokens[i].is_symbol("(") =>
+        {
+            i = tokens.len();
+            parse_lambda(&tokens[start..])?
+        },
+        LexKind::Identifier(name) if i < tokens.len() && tokens[i].is_symbol("(") =>
+        {
+            let (call, next) = parse_call_value(tokens, start, name)?;
+            i = next;
+            call
+        },
+        LexKind::Identifier(value) | LexKind::Number(value) => parse_token_value(value, false, false),
+        LexKind::StringLiteral(value) => parse_token_value(value, true, false),
+        LexKind::FormatString(template, expressions) => parse_format_string(template, expressions, token.span)?,
+        LexKind::CharLiteral(value) =>
+        {
+            // Make sure if char, it is only one character.
+            match value.chars().count()
             {
-                if c == '['
-                {
-                    inner_collection_count += 1;
-                }
-                else if c == ']'
-                {
-                    if inner_collection_count == 0
-                    {
-                        match utility::split_by(&string[token_start..token_end], ',')
-                        {
-                            Ok(result) =>
-                            {
-                                let mut collection_operations: Vec<Token> = Vec::new();
-                                for collection_operation in result.iter()
-                                {
-                                    let operation = parse_operation(&collection_operation);
-
-                                    match operation
-                                    {
-                                        Ok(mut op) =>
-                                        {
-                                            match op.len()
-                                            {
-                                                0 => collection_operations.push(Token::Null),
-                                                // Pop and Unwrap allowed because of our knowledge of a
-                                                // single element present.
-                                                1 => collection_operations.push(op.pop().unwrap()),
-                                                _ => collection_operations.push(Token::Operation(op))
-                                            }
-                                        },
-                                        Err(error) =>
-                                        {
-                                            return Err(error);
-                                        }
-                                    }
-                                }
-                                 
-                                let token = Token::Collection(collection_operations);
-                                // Check if using an accessor after this call.
-                                if string.len() > i+1 && &string[i+1..i+2] == "."
-                                {
-                                    current_accessor_token = token;
-                                    
-                                    last_token_type = ParseTokenType::None;
-                                    token_start = i+2;
-                                    token_end = i+3;
-                                }
-                                else
-                                {
-                                    tokens_ptr.push(token);
-            
-                                    last_token_type = ParseTokenType::None;
-                                    token_start = i;
-                                }
-                            }
-                            Err(error) =>
-                            {
-                                return Err(error);
-                            }
-                        }
-                       continue;
-                    }
-                    else
-                    {
-                        inner_collection_count -= 1;
-                    }
-                }
+                0 => { return Err(("Tried to create a char type with no characters.".to_string(), token.span)); }
+                1 => parse_token_value(value, false, true),
+                _ => { return Err(("Too many characters to create a valid char type.".to_string(), token.span)); }
             }
-            // Handle an internal operation using recursion on the current function.
-            else if last_token_type == ParseTokenType::Parenth
+        },
+        // Handle an internal operation using recursion on the current function.
+        LexKind::Symbol(symbol) if symbol == "(" =>
+        {
+            let close = find_closing(tokens, start)?;
+            i = close + 1;
+
+            let inner = &tokens[start+1..close];
+            Token::Operation(parse_operation_tokens(inner)?, lexer::tokens_span(inner))
+        },
+        LexKind::Symbol(symbol) if symbol == "[" =>
+        {
+            let close = find_closing(tokens, start)?;
+            i = close + 1;
+
+            let mut collection_operations: Vec<Token> = Vec::new();
+
+            for item in lexer::split_tokens(&tokens[start+1..close], ",")?
             {
-                // Found an inner-inner operation.
-                if c == '('
-                {
-                    inner_parenth_count += 1;
-                }
-                else if c == ')'
+                let mut op = parse_operation_tokens(item)?;
+
+                match op.len()
                 {
-                    if inner_parenth_count == 0
-                    {
-                        match parse_operation(&string[token_start..token_end])
-                        {
-                            Ok(op) =>
-                            {
-                                let token = Token::Operation(op);
-                                
-                                tokens_ptr.push(token);
-
-                                last_token_type = ParseTokenType::None;
-                                token_start = i;
-                            },
-                            Err(error) =>
-                            {
-                                return Err(error);
-                            }
-                        }
-                    }
-                    else
-                    {
-                        inner_parenth_count -= 1;
-                    }
+                    0 => collection_operations.push(Token::Null),
+                    // Pop and Unwrap allowed because of our knowledge of a
+                    // single element present.
+                    1 => collection_operations.push(op.pop().unwrap()),
+                    _ => collection_operations.push(Token::Operation(op, lexer::tokens_span(item)))
                 }
             }
-            else if last_token_type == ParseTokenType::Call
-            {
-                // Found an inner-call operation.
-                if c == '('
-                {
-                    inner_parenth_count += 1;
-                }
-                else if c == ')'
-                {
-                    if inner_parenth_count == 0
-                    {
-                        // Split call into function and arguments.
-                        let call = &string[token_start..i];
 
-                        if let Some(result) = call.split_once("(")
-                        {
-                            let mut token = Token::Call(result.0.to_string(), result.1[..result.1.len()].to_string());
-
-                            // Handle accessor.
-                            match &current_accessor_token
-                            {
-                                Token::Null => (),
-                                _ => { token = handle_accessor(current_accessor_token.clone(), Box::new(token)); }
-                            }
-
-                            // Check if using an accessor after this call.
-                            if string.len() >= i+2 && &string[i+1..i+2] == "."
-                            {
-                                current_accessor_token = token;
-                                
-                                last_token_type = ParseTokenType::None;
-                                token_start = i+2;
-                                token_end = i+3;
-                            }
-                            else
-                            {
-                                tokens_ptr.push(token);
-        
-                                last_token_type = ParseTokenType::None;
-                                token_start = i;
-                            }
-                        }
-                    }
-                    else
-                    {
-                        inner_parenth_count -= 1;
-                    }
-                }
+            Token::Collection(collection_operations)
+        },
+        LexKind::Symbol(symbol) if symbol == "{" =>
+        {
+            let close = find_closing(tokens, start)?;
+            i = close + 1;
+
+            parse_map(&tokens[start+1..close])?
+        },
+        LexKind::Symbol(symbol) if symbol == ")" || symbol == "]" || symbol == "}" =>
+        {
+            return Err((format!("Excess '{}' found. Make sure to properly enclose your statements.", symbol), token.span));
+        },
+        _ =>
+        {
+            return Err((format!("Unexpected '{}' found in operation.", token), token.span));
+        }
+    };
+
+    // Accessors can follow any value.
+    while i < tokens.len() && (tokens[i].is_symbol(".") || tokens[i].is_symbol("["))
+    {
+        // Index by the value of an operation. (Eg. m["hp"], c[i + 1])
+        if tokens[i].is_symbol("[")
+        {
+            let close = find_closing(tokens, i)?;
+
+            if close == i+1
+            {
+                return Err(("Expected a value between '[' and ']'.".to_string(), tokens[i].span));
             }
-            else if last_token_type == ParseTokenType::Accessor
+
+            let index = &tokens[i+1..close];
+            value = Token::Accessor(Box::new(value), Box::new(Token::Operation(parse_operation_tokens(index)?, lexer::tokens_span(index))));
+            i = close + 1;
+            continue;
+        }
+
+        let accessor = match tokens.get(i+1)
+        {
+            Some(next) => next,
+            None => { return Err(("Expected a value after '.'.".to_string(), tokens[i].span)); }
+        };
+
+        let accessor_value = match &accessor.kind
+        {
+            LexKind::Identifier(name) if i+2 < tokens.len() && tokens[i+2].is_symbol("(") =>
+            {
+                let (call, next) = parse_call_value(tokens, i+1, name)?;
+                i = next;
+                call
+            },
+            LexKind::Identifier(name) | LexKind::Number(name) =>
             {
-                let mut was_last = false;
+                i += 2;
+                parse_token_value(name, false, false)
+            },
+            _ => { return Err((format!("Unexpected '{}' after accessor.", accessor), accessor.span)); }
+        };
 
-                // Load accessor with previously grabbed token into the tokens_ptr.
-                if i == string.len()-1
-                {
-                    token_end += 1;
-                    was_last = true;
-                }
+        value = handle_accessor(value, Box::new(accessor_value));
+    }
 
-                let token = handle_accessor(current_accessor_token.clone(), Box::new(parse_token_value(&string[token_start..token_end], is_literal, is_char)));
+    Ok((value, i))
+}
 
-                if current_char_type == ParseTokenType::Accessor
-                {
-                    // Continue to be an accessor, just recursive with the last accessor.
-                    current_accessor_token = token;
-                    token_start = i+1;
-                    token_end = i+1;
+// Parses a call starting at the name of the function.
+// Arguments are parsed now so they only need to be run when the call happens.
+fn parse_call_value(tokens: &[LexToken], start: usize, name: &str) -> Result<(Token, usize), (String, Span)>
+{
+    let close = find_closing(tokens, start+1)?;
 
-                    continue;
-                }
-                // Just continue through when the current is just another value.
-                else if current_char_type == ParseTokenType::Value
-                {
-                    token_end = i+1;
-                }
-                else if current_char_type == ParseTokenType::Parenth
+    let arguments = parse_arguments(&lexer::split_tokens(&tokens[start+2..close], ",")?)?;
+
+    Ok((Token::Call(name.to_string(), arguments, lexer::tokens_span(&tokens[start..=close])), close+1))
+}
+
+// Parses the arguments of a call. Named arguments are kept with their operation, and need to come last.
+// Arguments of more than a value are kept as an operation, so errors in them point at the argument.
+// (Eg. spawn("orc", count=3))
+pub fn parse_arguments(arguments: &[&[LexToken]]) -> Result<Vec<Vec<Token>>, (String, Span)>
+{
+    let mut result: Vec<Vec<Token>> = Vec::new();
+
+    for argument in arguments
+    {
+        match argument
+        {
+            [name, assign, value @ ..] if name.identifier().is_some() && assign.is_symbol("=") =>
+            {
+                if value.is_empty()
                 {
-                    last_token_type = ParseTokenType::Call;
+                    return Err((format!("Expected a value after '{}='.", name), assign.span));
                 }
-                else
-                {
-                    tokens_ptr.push(token);
-                    current_accessor_token = Token::Null;
-    
-                    last_token_type = ParseTokenType::None;
-                    token_start = i;
 
-                    if was_last
-                    {
-                        continue;
-                    }
-                }
+                result.push(vec![Token::Named(name.to_string(), Box::new(Token::Operation(parse_operation_tokens(value)?, lexer::tokens_span(value))))]);
+            },
+            _ if matches!(result.last().map(|x| x.as_slice()), Some([Token::Named(_, _)])) =>
+            {
+                return Err(("Arguments without a name can't come after named arguments.".to_string(), lexer::tokens_span(argument)));
+            },
+            _ =>
+            {
+                let operation = parse_operation_tokens(argument)?;
+                result.push(if operation.len() > 1 { vec![Token::Operation(operation, lexer::tokens_span(argument))] } else { operation });
             }
         }
+    }
+
+    Ok(result)
+}
+
+// Parses the parameters of a function, or lambda.
+// Parameters with a default value need to come after the ones without, and a variadic parameter last.
+// (Eg. spawn(kind, count=1, *tags))
+pub fn parse_parameters(parameters: &[LexToken]) -> Result<Vec<Parameter>, (String, Span)>
+{
+    let mut result: Vec<Parameter> = Vec::new();
+
+    if parameters.is_empty()
+    {
+        return Ok(result);
+    }
+
+    for tokens in lexer::split_tokens(parameters, ",")?
+    {
+        let span = lexer::tokens_span(tokens);
 
-        if last_token_type == ParseTokenType::None || i == string.len()-1
+        let parameter = match tokens
         {
-            if current_char_type == ParseTokenType::Value
+            [name] if name.identifier().is_some() => Parameter { name: name.to_string(), default: None, variadic: false },
+            [star, name] if star.is_symbol("*") && name.identifier().is_some() => Parameter { name: name.to_string(), default: None, variadic: true },
+            [name, assign, value @ ..] if name.identifier().is_some() && assign.is_symbol("=") && !value.is_empty() =>
             {
-                last_token_type = ParseTokenType::Value;
+                Parameter { name: name.to_string(), default: Some(parse_operation_tokens(value)?), variadic: false }
+            },
+            _ => { return Err(("Parameters need to be a name, with an optional default value, or '*' before the last to collect the rest. (Eg. f(a, b=1, *rest))".to_string(), span)); }
+        };
 
-                // This might be the last value.
-                if i == string.len()-1
-                {
-                    let mut token = parse_token_value(&string[token_start..i+1], false, false);
+        if result.iter().any(|x| x.name == parameter.name)
+        {
+            return Err((format!("Parameter '{}' is declared more than once.", parameter.name), span));
+        }
+        if matches!(result.last(), Some(last) if last.variadic)
+        {
+            return Err(("The variadic parameter needs to be the last.".to_string(), span));
+        }
+        if parameter.default.is_none() && !parameter.variadic && result.iter().any(|x| x.default.is_some())
+        {
+            return Err((format!("Parameter '{}' needs a default value, as it comes after one with a default.", parameter.name), span));
+        }
 
-                    if let Token::Null = current_accessor_token {}
-                    else
-                    {
-                        token = handle_accessor(current_accessor_token.clone(), Box::new(token));
-                    }
-                    tokens_ptr.push(token);
-                }
+        result.push(parameter);
+    }
 
-                token_start = i;
-                token_end = i+1;
-            }
-            else if current_char_type == ParseTokenType::Collection
-            {
-                last_token_type = ParseTokenType::Collection;
+    Ok(result)
+}
 
-                token_start = i+1;
-                token_end = i+2;
-            }
-            else if current_char_type == ParseTokenType::StringLiteral
-            {
-                last_token_type = ParseTokenType::StringLiteral;
+// Parses the pattern of a match case. (Eg. "idle", -1, [x, 0], n or _)
+pub fn parse_pattern(tokens: &[LexToken]) -> Result<Pattern, (String, Span)>
+{
+    let span = lexer::tokens_span(tokens);
 
-                token_start = i+1;
-                token_end = i+2;
-            }
-            else if current_char_type == ParseTokenType::CharLiteral
-            {
-                last_token_type = ParseTokenType::CharLiteral;
+    if matches!(tokens.first(), Some(first) if first.is_symbol("[")) && find_closing(tokens, 0)? == tokens.len()-1
+    {
+        let mut items: Vec<Pattern> = Vec::new();
 
-                token_start = i+1;
-                token_end = i+2;
-            }
-            else if current_char_type == ParseTokenType::Operator
-            {
-                last_token_type = ParseTokenType::Operator;
-                token_start = i;
-                token_end = i+1;
-            }
-            else if c == '('
-            {
-                last_token_type = ParseTokenType::Parenth;
-                token_start = i+1;
-                token_end = i+2;
-            }
-            else if current_char_type == ParseTokenType::Accessor
+        if tokens.len() > 2
+        {
+            for item in lexer::split_tokens(&tokens[1..tokens.len()-1], ",")?
             {
-                last_token_type = ParseTokenType::Accessor;
-                token_start = i+1;
-                token_end = i+1;
+                items.push(parse_pattern(item)?);
             }
         }
+
+        return Ok(Pattern::Collection(items));
     }
 
-    // If still in parenthises, there is an error.
-    if inner_parenth_count > 0
+    let (negate, value) = match tokens
+    {
+        [minus, value] if minus.is_symbol("-") => (true, std::slice::from_ref(value)),
+        _ => (false, tokens)
+    };
+
+    match parse_operation_tokens(value)?.as_slice()
     {
-        return Err("Parenthesis were not closed during operation.".to_string());
+        [Token::Var(name)] if !negate && name == "_" => Ok(Pattern::Wildcard),
+        [Token::Var(name)] if !negate && !name.contains('.') => Ok(Pattern::Binding(name.clone())),
+        [Token::Int(i)] => Ok(Pattern::Literal(Token::Int(if negate { -i } else { *i }))),
+        [Token::Float(f)] => Ok(Pattern::Literal(Token::Float(if negate { -f } else { *f }))),
+        [literal @ (Token::String(_) | Token::Char(_) | Token::Bool(_))] if !negate => Ok(Pattern::Literal(literal.clone())),
+        _ => Err(("Case patterns need to be a value, a name, '_', or a collection of patterns. (Eg. case [x, 0]:)".to_string(), span))
     }
-    else if inner_parenth_count < 0
+}
+
+// Parses an anonymous function, starting at 'fn'. (Eg. fn(a, b) => a + b)
+fn parse_lambda(tokens: &[LexToken]) -> Result<Token, (String, Span)>
+{
+    let span = lexer::tokens_span(tokens);
+    let close = find_closing(tokens, 1)?;
+
+    let parameters = parse_parameters(&tokens[2..close])?;
+
+    match tokens.get(close+1)
     {
-        return Err("Too many closing parenthesis found.".to_string());
+        Some(arrow) if arrow.is_symbol("=>") => (),
+        _ => { return Err(("Expected '=>' after the lambda's parameters.".to_string(), span)); }
+    }
+
+    let body = &tokens[close+2..];
+    if body.is_empty()
+    {
+        return Err(("Expected an expression after '=>'.".to_string(), span));
+    }
+
+    let definition = FunctionDef
+    {
+        name: "fn".to_string(),
+        parameters,
+        body: vec![Statement::Return { value: Some(parse_operation_tokens(body)?), span: lexer::tokens_span(body) }],
+        span
+    };
+
+    Ok(Token::Function(Function::Lambda { definition: Rc::new(definition), captures: None }))
+}
+
+// Turns an f-string into a template, with the expressions between braces as its values.
+// (Eg. f"HP: {hp}/{max_hp:.1}" runs like format("HP: {}/{:.1}", hp, max_hp))
+fn parse_format_string(template: &str, expressions: &[Vec<LexToken>], span: Span) -> Result<Token, (String, Span)>
+{
+    // Without expressions it's a plain string, once the braces are unescaped.
+    if expressions.is_empty()
+    {
+        return Ok(Token::String(template.replace("{{", "{").replace("}}", "}")));
+    }
+
+    let arguments = expressions.iter()
+        .map(|x| parse_operation_tokens(x).map_err(|(error, span)| (format!("In format string: {}", error), span)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Token::Format(template.to_string(), arguments, span))
+}
+
+// Parses the inside of a map literal. (Eg. "hp": 10, "name": "orc")
+fn parse_map(tokens: &[LexToken]) -> Result<Token, (String, Span)>
+{
+    let mut map: LinkedHashMap<String, Token> = LinkedHashMap::new();
+
+    for item in lexer::split_tokens(tokens, ",")?
+    {
+        // Allow a trailing comma.
+        if item.is_empty()
+        {
+            continue;
+        }
+
+        match lexer::split_tokens(item, ":")?.as_slice()
+        {
+            [[key], value] if !value.is_empty() =>
+            {
+                let key = match &key.kind
+                {
+                    LexKind::StringLiteral(key) => key.clone(),
+                    _ => { return Err(("Map keys need to be strings.".to_string(), key.span)); }
+                };
+
+                let mut op = parse_operation_tokens(value)?;
+
+                // Single values don't need to be an operation.
+                let value = if op.len() == 1 { op.pop().unwrap() } else { Token::Operation(op, lexer::tokens_span(value)) };
+                map.insert(key, value);
+            },
+            _ => { return Err(("Expected a map entry. (Eg. \"key\": value)".to_string(), lexer::tokens_span(item))); }
+        }
+    }
+
+    Ok(Token::Map(map))
+}
+
+fn find_closing(tokens: &[LexToken], start: usize) -> Result<usize, (String, Span)>
+{
+    match lexer::find_closing(tokens, start)
+    {
+        Some(close) => Ok(close),
+        None => Err(("Parenthesis were not closed during operation.".to_string(), tokens[start].span))
     }
-    
-    // Handle operation order and populating.
-    Ok(handle_populating_operation(tokens))
-    
 }
 
 fn handle_accessor(prev_token: Token, new_token: Box<Token>) -> Token
@@ -493,7 +453,7 @@ fn handle_accessor(prev_token: Token, new_token: Box<Token>) -> Token
 }
 
 // Allows for the conversion from string to different types.
-fn parse_token_value<'a>(value: &'a str, literal: bool, is_char: bool) -> Token
+fn parse_token_value(value: &str, literal: bool, is_char: bool) -> Token
 {
     if literal
     {
@@ -505,11 +465,11 @@ fn parse_token_value<'a>(value: &'a str, literal: bool, is_char: bool) -> Token
         return Token::Char(value.chars().next().unwrap());
     }
 
-    if let Ok(result) = value.parse::<i32>()
+    if let Ok(result) = value.parse::<i64>()
     {
         Token::Int(result)
     }
-    else if let Ok(result) = value.parse::<f32>()
+    else if let Ok(result) = value.parse::<f64>()
     {
         Token::Float(result)
     }
@@ -545,6 +505,13 @@ fn handle_populating_operation(mut tokens: Vec<Token>) -> Vec<Token>
     {
         if let Token::Operator(op) = &map[&index]
         {
+            // Unary operators have nothing before them to apply to yet.
+            if utility::is_unary(op)
+            {
+                stack.push(index);
+                continue;
+            }
+
             if let Some(i) = stack.last().copied()
             {
                 if let Token::Operator(mut stack_top) = map[&i].clone()
@@ -593,7 +560,19 @@ fn handle_populating_operation(mut tokens: Vec<Token>) -> Vec<Token>
 // Check if operator worth is greater than or equal to another.
 // Equal to allows for processing left most operators first that share the same value as another.
 // (Eg. x*2/7: x*2 should go first, than divide that by 7.)
+// Right associative operators process the right most first instead. (Eg. 2^3^2 is 2^(3^2).)
+// A power binds tighter than the unary operator before it. (Eg. -x^2 is -(x^2).)
 pub fn operator_a_gte_b(a: &str, b: &str) -> bool
 {
+    if utility::is_unary(a) && b == "^"
+    {
+        return false;
+    }
+
+    if utility::is_right_associative(b)
+    {
+        return utility::get_operator_worth(a) > utility::get_operator_worth(b)
+    }
+
     return utility::get_operator_worth(a) >= utility::get_operator_worth(b)
 }
@@ -1,74 +1,186 @@
 use super::ExpressionType;
+use super::operation_parser::{parse_operation_tokens, parse_parameters, parse_pattern};
+use crate::parser::lexer::{self, LexToken};
+use crate::types::{Span, Operation, Parameter, Pattern, LoopKind};
 
-pub fn parse_scope(exp: &str, expression_type: &ExpressionType) -> Result<(Option<String>, Option<String>), String>
+// Parsed information from the first line of a scope.
+pub enum ScopeHeader
 {
-    if exp.chars().last().unwrap() != ':'
+    Loop(LoopKind),
+    // If and elif.
+    Condition(Operation),
+    Else,
+    // Function name and parameters.
+    Function(String, Vec<Parameter>),
+    // Struct type name.
+    Struct(String),
+    Try,
+    // Name given to the caught error.
+    Catch(Option<String>),
+    // Value being matched.
+    Match(Operation),
+    // Pattern, with the guard after an 'if'.
+    Case(Pattern, Option<Operation>),
+}
+
+pub fn parse_scope(exp: &[LexToken], expression_type: &ExpressionType) -> Result<ScopeHeader, (String, Span)>
+{
+    let span = lexer::tokens_span(exp);
+
+    if exp.is_empty() || !exp[exp.len()-1].is_symbol(":")
     {
-        return Err(format!("{:?} expressions need to end with a ':'", expression_type));
+        return Err((format!("{:?} expressions need to end with a ':'", expression_type), span));
     }
 
     match expression_type
     {
-        // Loop
-        &ExpressionType::Loop =>
+        ExpressionType::Loop =>
         {
-            let result = exp.trim_start_matches("loop").trim_end_matches(":");
-
-            Ok(handle_scope_result(vec!["loop", result]))
+            Ok(ScopeHeader::Loop(parse_loop(&exp[1..exp.len()-1], span)?))
         },
-        // If
-        &ExpressionType::If =>
+        ExpressionType::If =>
         {
-            let result = exp.trim_start_matches("if").trim_end_matches(":");
-
-            Ok(handle_scope_result(vec!["if", result]))
+            parse_condition(&exp[1..exp.len()-1], span)
         },
-        // Elif
-        &ExpressionType::Elif =>
+        ExpressionType::Elif =>
         {
-            let result = exp.trim_start_matches("elif").trim_start_matches("elseif").trim_end_matches(":");
+            // Allow both "elif" and "else if".
+            let keyword_length = if exp.len() > 1 && exp[1].is_keyword("if") { 2 } else { 1 };
 
-            Ok(handle_scope_result(vec!["elif", result]))
+            parse_condition(&exp[keyword_length.min(exp.len()-1)..exp.len()-1], span)
         },
-        // Else
-        &ExpressionType::Else =>
+        ExpressionType::Else =>
         {
-            Ok(handle_scope_result(vec!["else", ""]))
+            if exp.len() > 2
+            {
+                return Err(("Else expressions can't have a condition. Did you mean to use 'elif'?".to_string(), span));
+            }
+
+            Ok(ScopeHeader::Else)
         },
-        // Function
-        &ExpressionType::Function =>
+        ExpressionType::Function =>
         {
-            let result_op = exp.split_once('(');
+            let open = exp.iter().position(|x| x.is_symbol("("));
 
-            if let Some(result) = result_op
+            if let (Some(open), Some(name)) = (open, exp[0].identifier())
             {
-                Ok(handle_scope_result(vec![result.0, result.1.trim_end_matches("):")]))
+                match lexer::find_closing(exp, open)
+                {
+                    Some(close) if close == exp.len()-2 && open == 1 =>
+                    {
+                        Ok(ScopeHeader::Function(name.to_string(), parse_parameters(&exp[open+1..close])?))
+                    },
+                    _ => Err(("Failed to parse function.".to_string(), span))
+                }
             }
             else
             {
-                Err("Failed to parse function.".to_string())
+                Err(("Failed to parse function.".to_string(), span))
             }
         }
-        _ => Ok((None, None))
+        ExpressionType::Struct =>
+        {
+            match &exp[1..exp.len()-1]
+            {
+                [name] if name.identifier().is_some() => Ok(ScopeHeader::Struct(name.to_string())),
+                _ => Err(("Structs need a single name. (Eg. struct Vec2:)".to_string(), span))
+            }
+        },
+        ExpressionType::Try =>
+        {
+            if exp.len() > 2
+            {
+                return Err(("Try expressions don't take a value. (Eg. try:)".to_string(), span));
+            }
+
+            Ok(ScopeHeader::Try)
+        },
+        ExpressionType::Catch =>
+        {
+            match &exp[1..exp.len()-1]
+            {
+                [] => Ok(ScopeHeader::Catch(None)),
+                [name] if name.identifier().is_some() => Ok(ScopeHeader::Catch(Some(name.to_string()))),
+                _ => Err(("Catch takes a single name for the error. (Eg. catch err:)".to_string(), span))
+            }
+        },
+        ExpressionType::Match =>
+        {
+            match &exp[1..exp.len()-1]
+            {
+                [] => Err(("Expected a value after 'match'. (Eg. match state:)".to_string(), span)),
+                subject => Ok(ScopeHeader::Match(parse_operation_tokens(subject)?))
+            }
+        },
+        ExpressionType::Case =>
+        {
+            let header = &exp[1..exp.len()-1];
+
+            let (pattern, guard) = match header.iter().position(|x| x.is_keyword("if"))
+            {
+                Some(i) if i+1 == header.len() => { return Err(("Expected a condition after 'if'.".to_string(), span)); }
+                Some(i) => (&header[..i], Some(parse_operation_tokens(&header[i+1..])?)),
+                None => (header, None)
+            };
+
+            if pattern.is_empty()
+            {
+                return Err(("Expected a pattern after 'case'. (Eg. case \"idle\":)".to_string(), span));
+            }
+
+            Ok(ScopeHeader::Case(parse_pattern(pattern)?, guard))
+        },
+        _ => Err((format!("{:?} is not a scope.", expression_type), span))
     }
 }
 
-fn handle_scope_result(result: Vec<&str>) -> (Option<String>, Option<String>)
+fn parse_condition(condition: &[LexToken], span: Span) -> Result<ScopeHeader, (String, Span)>
 {
-    if result.len() >= 1 && !result[0].is_empty()
+    if condition.is_empty()
     {
-        let scope = Some(result[0].to_string());
-        let mut arguments: Option<String> = None;
+        return Err(("Expected a condition before the ':'".to_string(), span));
+    }
 
-        if result.len() == 2 && !result[1].is_empty()
+    Ok(ScopeHeader::Condition(parse_operation_tokens(condition)?))
+}
+
+// Parses what comes between 'loop' and the ':'.
+// Either nothing, 'while condition', or '(for) name in values' where values is a range or collection.
+fn parse_loop(header: &[LexToken], span: Span) -> Result<LoopKind, (String, Span)>
+{
+    if header.is_empty()
+    {
+        return Ok(LoopKind::Infinite);
+    }
+
+    if header[0].is_keyword("while")
+    {
+        if header.len() == 1
         {
-            arguments = Some(result[1].to_string());
+            return Err(("Expected a condition after 'while'.".to_string(), span));
         }
 
-        (scope, arguments)
+        return Ok(LoopKind::While(parse_operation_tokens(&header[1..])?));
     }
-    else
+
+    let header = if header[0].is_keyword("for") { &header[1..] } else { header };
+
+    let (name, values) = match header
     {
-        return (None, None);
+        [name, keyword, values @ ..] if keyword.is_keyword("in") && name.identifier().is_some() && !values.is_empty() =>
+        {
+            (name.to_string(), values)
+        },
+        _ => { return Err(("Expected 'while', or a name followed by 'in' after 'loop'.".to_string(), span)); }
+    };
+
+    match lexer::split_tokens(values, "..")?.as_slice()
+    {
+        [collection] => Ok(LoopKind::Each { variable: name, collection: parse_operation_tokens(collection)? }),
+        [start, end] if !start.is_empty() && !end.is_empty() =>
+        {
+            Ok(LoopKind::Range { variable: name, start: parse_operation_tokens(start)?, end: parse_operation_tokens(end)? })
+        },
+        _ => Err(("Ranges need a start and an end. (Eg. 0..10)".to_string(), lexer::tokens_span(values)))
     }
 }
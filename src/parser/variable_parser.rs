@@ -1,57 +1,234 @@
-pub fn parse_var(line: &str) -> Result<(Vec<String>, String, String), String>
+use crate::parser::lexer::{self, LexKind, LexToken};
+use crate::types::{Statement, LoopKind, Pattern, VariableModifier, error::*};
+
+// Modifiers, variable name, tokens of the value, and the tokens of each key or index after the name.
+pub type ParsedVar = (Vec<String>, String, Vec<LexToken>, Vec<Vec<LexToken>>);
+
+// Parses a variable assignment into its parts. (Eg. const!grid[x][y] = 0)
+pub fn parse_var(line: &[LexToken]) -> Result<ParsedVar, String>
 {
-    let mut var: (Vec<String>, String, String) = (vec![], "".to_string(), "".to_string());
+    let mut var: ParsedVar = (vec![], "".to_string(), vec![], vec![]);
 
-    let mut identifier_split: Vec<String> = line.split("!").map(|x| x.to_string()).collect();
+    let assign_index = line.iter().position(|x|
+        matches!(&x.kind, LexKind::Symbol(s) if s == "=" || s == "+=" || s == "-=" || s == "*=" || s == "/="));
 
-    if identifier_split.len() == 0
+    let target_end = match assign_index
     {
-        return Err("Failed to parse variable assignement. Expected a single '='.".to_string());
-    }
+        Some(index) => index,
+        // Handle ++ and --
+        None if line.len() > 1 && (line[line.len()-1].is_symbol("++") || line[line.len()-1].is_symbol("--")) => line.len()-1,
+        None => { return Err("Failed to parse variable assignment. Expected a single '='.".to_string()); }
+    };
 
-    let var_unsplit = identifier_split.pop().unwrap();
-    let split: Vec<&str> = var_unsplit.split("=").collect();
+    let index_start = line[..target_end].iter().position(|x| x.is_symbol("[")).unwrap_or(target_end);
+    var.3 = parse_indexes(&line[index_start..target_end])?;
 
+    // Modifiers are separated from the name by '!'.
+    let mut identifier_split: Vec<String> = line[..index_start]
+        .split(|x| x.is_symbol("!"))
+        .map(|x| x.iter().map(|t| t.to_string()).collect::<String>())
+        .collect();
+
+    var.1 = identifier_split.pop().unwrap_or_default();
     var.0 = identifier_split;
 
-    if split.len() !=2
+    if var.1.is_empty() || var.0.iter().any(|x| x.is_empty())
     {
-        // Handle ++ and --
-        if line.ends_with("++")
+        return Err("Failed to parse variable assignment. Expected a variable name.".to_string());
+    }
+
+    let operator = &line[target_end];
+    // The variable being assigned, for use in assignment operatives. (Kept as tokens for fields, like pos.x)
+    let target_start = line[..index_start].iter().rposition(|x| x.is_symbol("!")).map_or(0, |x| x + 1);
+    let target = &line[target_start..target_end];
+    let symbol = |s: &str| LexToken { kind: LexKind::Symbol(s.to_string()), span: operator.span };
+
+    match &operator.kind
+    {
+        LexKind::Symbol(s) if s == "++" || s == "--" =>
         {
-            let temp = line.trim_end_matches("++");
-            var.1 = temp.to_string();
-            var.2 = format!("{}+{}", temp, 1);
-        }
-        else if line.ends_with("--")
+            var.2 = target.to_vec();
+            var.2.push(symbol(&s[..1]));
+            var.2.push(LexToken { kind: LexKind::Number("1".to_string()), span: operator.span });
+        },
+        LexKind::Symbol(s) if s == "=" =>
         {
-            let temp = line.trim_end_matches("--");
-            var.1 = temp.to_string();
-            var.2 = format!("{}-{}", temp, 1);
+            var.2 = line[target_end+1..].to_vec();
+
+            // Named arguments and defaults can use '=' inside brackets. (Eg. x = spawn("orc", count=3))
+            if lexer::split_tokens(&var.2, "=").map_or(true, |x| x.len() > 1)
+            {
+                return Err("Failed to parse variable assignment. Expected a single '='.".to_string());
+            }
+        },
+        // Assignment operatives apply to the whole value. (Eg. x *= a + b is x * (a + b))
+        LexKind::Symbol(s) =>
+        {
+            var.2 = target.to_vec();
+            var.2.push(symbol(&s[..1]));
+            var.2.push(symbol("("));
+            var.2.extend_from_slice(&line[target_end+1..]);
+            var.2.push(symbol(")"));
+        },
+        _ => ()
+    }
+
+    Ok(var)
+}
+
+// Splits the brackets after an assigned name into the tokens inside each. (Eg. [x][y + 1])
+fn parse_indexes(tokens: &[LexToken]) -> Result<Vec<Vec<LexToken>>, String>
+{
+    let mut indexes: Vec<Vec<LexToken>> = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len()
+    {
+        let close = match lexer::find_closing(tokens, i)
+        {
+            Some(close) if tokens[i].is_symbol("[") => close,
+            _ => { return Err("Failed to parse variable assignment. Only indexes can follow an index. (Eg. grid[x][y] = 0)".to_string()); }
+        };
+
+        if close == i + 1
+        {
+            return Err("Expected a key or index between '[' and ']'.".to_string());
         }
-        else
+
+        indexes.push(tokens[i+1..close].to_vec());
+        i = close + 1;
+    }
+
+    Ok(indexes)
+}
+
+// Reports assignments to constants that are known before running.
+// (Global constants, and constants defined earlier in the same function.)
+pub fn check_constant_assignments(statements: &[Statement], error_manager: &mut ErrorManager)
+{
+    let mut globals: Vec<(&str, bool)> = Vec::new();
+
+    for statement in statements
+    {
+        if let Statement::Assign { name, modifiers, span, .. } = statement
         {
-            return Err("Failed to parse variable assignment. Expected a single '='.".to_string());
+            // Fields (Eg. pos.x) belong to the variable they're in.
+            let variable = variable_name(name);
+
+            if globals.iter().any(|x| x.0 == variable && x.1)
+            {
+                push_error!(error_manager, ParseError::new(*span, format!("Cannot assign to '{}', as it's a constant.", variable).as_str()));
+            }
+            else if variable == name
+            {
+                globals.push((name, modifiers.contains(&VariableModifier::Const)));
+            }
         }
     }
-    else
+
+    for statement in statements
     {
-        var.1 = split[0].to_string();
-        var.2 = split[1].to_string();
+        if let Statement::Function(function) = statement
+        {
+            // Parameters hide globals by the same name.
+            let mut scopes: Vec<Vec<(&str, bool)>> = vec![function.parameters.iter().map(|x| (x.name.as_str(), false)).collect()];
+            check_scope(&function.body, &globals, &mut scopes, error_manager);
+        }
+    }
+}
 
-        // Check for assignment operatives.
-        let mut operator: Option<char> = None;
-        if var.1.ends_with('+') { operator = Some('+'); }
-        else if var.1.ends_with('-') { operator = Some('-'); }
-        else if var.1.ends_with('*') { operator = Some('*'); }
-        else if var.1.ends_with('/') { operator = Some('/'); }
+fn check_scope<'a>(statements: &'a [Statement], globals: &[(&str, bool)], scopes: &mut Vec<Vec<(&'a str, bool)>>, error_manager: &mut ErrorManager)
+{
+    scopes.push(Vec::new());
 
-        if let Some(operator) = operator
+    for statement in statements
+    {
+        match statement
         {
-            var.1 = var.1.trim_end_matches(operator).to_string();
-            var.2 = format!("{}{}{}", var.1, operator, var.2);
+            Statement::Assign { name, modifiers, span, .. } =>
+            {
+                let variable = variable_name(name);
+
+                let is_const = match scopes.iter().rev().find_map(|scope| scope.iter().find(|x| x.0 == variable))
+                {
+                    Some(local) => Some(local.1),
+                    None => globals.iter().rev().find(|x| x.0 == variable).map(|x| x.1)
+                };
+
+                match is_const
+                {
+                    Some(true) => { push_error!(error_manager, ParseError::new(*span, format!("Cannot assign to '{}', as it's a constant.", variable).as_str())); }
+                    Some(false) => (),
+                    None if variable != name => (),
+                    // Assigning an unknown name defines it in the current scope.
+                    None =>
+                    {
+                        if let Some(scope) = scopes.last_mut()
+                        {
+                            scope.push((name, modifiers.contains(&VariableModifier::Const)));
+                        }
+                    }
+                }
+            },
+            Statement::If { branches, .. } =>
+            {
+                for branch in branches
+                {
+                    check_scope(&branch.body, globals, scopes, error_manager);
+                }
+            },
+            Statement::Loop { kind, body, .. } =>
+            {
+                let variable = match kind
+                {
+                    LoopKind::Range { variable, .. } | LoopKind::Each { variable, .. } => vec![(variable.as_str(), false)],
+                    _ => vec![]
+                };
+
+                scopes.push(variable);
+                check_scope(body, globals, scopes, error_manager);
+                scopes.pop();
+            },
+            Statement::Match { cases, .. } =>
+            {
+                for case in cases
+                {
+                    let mut names: Vec<&str> = Vec::new();
+                    pattern_names(&case.pattern, &mut names);
+
+                    scopes.push(names.into_iter().map(|x| (x, false)).collect());
+                    check_scope(&case.body, globals, scopes, error_manager);
+                    scopes.pop();
+                }
+            },
+            Statement::Try { body, variable, handler, .. } =>
+            {
+                check_scope(body, globals, scopes, error_manager);
+
+                scopes.push(variable.iter().map(|x| (x.as_str(), false)).collect());
+                check_scope(handler, globals, scopes, error_manager);
+                scopes.pop();
+            },
+            _ => ()
         }
     }
 
-    Ok(var)
+    scopes.pop();
+}
+
+// Names a pattern gives values to.
+fn pattern_names<'a>(pattern: &'a Pattern, names: &mut Vec<&'a str>)
+{
+    match pattern
+    {
+        Pattern::Binding(name) => names.push(name),
+        Pattern::Collection(items) => items.iter().for_each(|x| pattern_names(x, names)),
+        _ => ()
+    }
+}
+
+// Variable a field belongs to. (Eg. pos in pos.x)
+fn variable_name(name: &str) -> &str
+{
+    name.split('.').next().unwrap_or(name)
 }
@@ -0,0 +1,517 @@
+use std::collections::HashMap;
+
+use crate::types::{Token, Span, Statement, LoopKind, FunctionDef, VarMap, Instruction, Chunk};
+use crate::utility;
+
+use super::operation_runner::call_accessor;
+
+struct LoopState
+{
+    start: usize,
+    // Scope depth of the loop body, cleared by break and continue.
+    scope_depth: usize,
+    // Jumps to patch with the end of the loop.
+    breaks: Vec<usize>,
+}
+
+struct Compiler<'a>
+{
+    chunk: Chunk,
+    // Globals at compile time. Assigning a name that isn't one of these (or a local) is decided when it runs,
+    // as the host can register the global after compiling.
+    globals: &'a VarMap,
+    names: HashMap<String, usize>,
+    // Local slots defined in each nested scope.
+    scopes: Vec<Vec<usize>>,
+    loops: Vec<LoopState>,
+    span: Span,
+}
+
+// Compiles a parsed function into bytecode.
+// Returns an error for anything the vm can't run, in which case the function is walked as before.
+pub fn compile_function(function: &FunctionDef, globals: &VarMap) -> Result<Chunk, String>
+{
+    let mut compiler = Compiler
+    {
+        chunk: Chunk
+        {
+            name: function.name.clone(),
+            parameters: function.parameters.clone(),
+            locals: Vec::new(),
+            constants: Vec::new(),
+            instructions: Vec::new(),
+            spans: Vec::new(),
+        },
+        globals,
+        names: HashMap::new(),
+        scopes: vec![vec![]],
+        loops: Vec::new(),
+        span: function.span,
+    };
+
+    // Parameters take the first slots.
+    for parameter in &function.parameters
+    {
+        compiler.define_local(&parameter.name);
+    }
+
+    compiler.compile_block(&function.body)?;
+
+    Ok(compiler.chunk)
+}
+
+impl<'a> Compiler<'a>
+{
+    fn emit(&mut self, instruction: Instruction) -> usize
+    {
+        self.chunk.instructions.push(instruction);
+        self.chunk.spans.push(self.span);
+
+        self.chunk.instructions.len() - 1
+    }
+
+    // Points a jump at the next instruction.
+    fn patch_jump(&mut self, index: usize)
+    {
+        let target = self.chunk.instructions.len();
+
+        match &mut self.chunk.instructions[index]
+        {
+            Instruction::Jump(to) | Instruction::JumpIfNotTrue(to) | Instruction::Next(to) => { *to = target; }
+            _ => ()
+        }
+    }
+
+    fn constant(&mut self, token: Token) -> usize
+    {
+        self.chunk.constants.push(token);
+        self.chunk.constants.len() - 1
+    }
+
+    // Names share a single constant each.
+    fn name(&mut self, name: &str) -> usize
+    {
+        if let Some(index) = self.names.get(name)
+        {
+            return *index;
+        }
+
+        let index = self.constant(Token::String(name.to_string()));
+        self.names.insert(name.to_string(), index);
+
+        index
+    }
+
+    fn resolve_local(&self, name: &str) -> Option<usize>
+    {
+        self.scopes.iter().rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|slot| self.chunk.locals[**slot] == name)
+            .copied()
+    }
+
+    fn define_local(&mut self, name: &str) -> usize
+    {
+        self.chunk.locals.push(name.to_string());
+        let slot = self.chunk.locals.len() - 1;
+
+        if let Some(scope) = self.scopes.last_mut()
+        {
+            scope.push(slot);
+        }
+
+        slot
+    }
+
+    // Clears the locals of every scope from the depth onwards.
+    // Doesn't remove them from the compiler, as code after a jump still belongs to the scope.
+    fn clear_scopes_from(&mut self, depth: usize)
+    {
+        let slots: Vec<usize> = self.scopes[depth..].iter().flatten().copied().collect();
+
+        for slot in slots
+        {
+            self.emit(Instruction::ClearLocal(slot));
+        }
+    }
+
+    fn compile_block(&mut self, statements: &[Statement]) -> Result<(), String>
+    {
+        self.scopes.push(Vec::new());
+
+        for statement in statements
+        {
+            self.compile_statement(statement)?;
+        }
+
+        self.clear_scopes_from(self.scopes.len() - 1);
+        self.scopes.pop();
+
+        Ok(())
+    }
+
+    fn compile_statement(&mut self, statement: &Statement) -> Result<(), String>
+    {
+        self.span = statement.span();
+
+        match statement
+        {
+            Statement::Assign { name, modifiers, value, indexes, .. } =>
+            {
+                if !modifiers.is_empty()
+                {
+                    return Err(format!("Modifiers on '{}' aren't supported by the compiler.", name));
+                }
+                if name.contains('.')
+                {
+                    return Err(format!("Assigning the field '{}' isn't supported by the compiler.", name));
+                }
+                if !indexes.is_empty()
+                {
+                    return Err(format!("Assigning an index of '{}' isn't supported by the compiler.", name));
+                }
+
+                self.compile_operation(value)?;
+
+                if let Some(slot) = self.resolve_local(name)
+                {
+                    self.emit(Instruction::StoreLocalOrGlobal(slot));
+                }
+                else if self.globals.contains_key(name)
+                {
+                    let index = self.name(name);
+                    self.emit(Instruction::StoreGlobal(index));
+                }
+                else
+                {
+                    let slot = self.define_local(name);
+                    self.emit(Instruction::StoreLocalOrGlobal(slot));
+                }
+            },
+            Statement::Call { name, arguments, .. } =>
+            {
+                // Calls on a variable are left to the operation runner's accessors. (Eg. player.move(1, 0))
+                if let Some((variable, _)) = name.split_once('.')
+                {
+                    if self.resolve_local(variable).is_some() || self.globals.contains_key(variable)
+                    {
+                        let index = self.constant(call_accessor(name, arguments, self.span));
+                        self.emit(Instruction::Evaluate(index));
+                        self.emit(Instruction::Pop);
+                        return Ok(());
+                    }
+                }
+
+                for argument in arguments
+                {
+                    self.compile_operation(argument)?;
+                }
+
+                let index = self.name(name);
+                self.emit(Instruction::Call(index, arguments.len()));
+                self.emit(Instruction::Pop);
+            },
+            Statement::If { branches, .. } =>
+            {
+                let mut end_jumps: Vec<usize> = Vec::new();
+
+                for branch in branches
+                {
+                    self.span = branch.span;
+
+                    let skip_jump = match &branch.condition
+                    {
+                        Some(condition) =>
+                        {
+                            self.compile_operation(condition)?;
+                            Some(self.emit(Instruction::JumpIfNotTrue(0)))
+                        },
+                        None => None
+                    };
+
+                    self.compile_block(&branch.body)?;
+                    end_jumps.push(self.emit(Instruction::Jump(0)));
+
+                    if let Some(jump) = skip_jump
+                    {
+                        self.patch_jump(jump);
+                    }
+                }
+
+                for jump in end_jumps
+                {
+                    self.patch_jump(jump);
+                }
+            },
+            Statement::Loop { kind, body, .. } =>
+            {
+                let (variable, iterates) = match kind
+                {
+                    LoopKind::Infinite | LoopKind::While(_) => (None, false),
+                    LoopKind::Range { variable, start, end } =>
+                    {
+                        self.compile_operation(start)?;
+                        self.compile_operation(end)?;
+                        self.emit(Instruction::PushRange);
+                        (Some(variable), true)
+                    },
+                    LoopKind::Each { variable, collection } =>
+                    {
+                        self.compile_operation(collection)?;
+                        self.emit(Instruction::PushItems);
+                        (Some(variable), true)
+                    }
+                };
+
+                let start = self.chunk.instructions.len();
+                let mut exit_jump: Option<usize> = None;
+
+                if let LoopKind::While(condition) = kind
+                {
+                    self.compile_operation(condition)?;
+                    exit_jump = Some(self.emit(Instruction::JumpIfNotTrue(0)));
+                }
+                else if iterates
+                {
+                    exit_jump = Some(self.emit(Instruction::Next(0)));
+                }
+
+                // The loop variable is scoped around the body, so break and continue clear it as well.
+                self.loops.push(LoopState { start, scope_depth: self.scopes.len(), breaks: Vec::new() });
+                self.scopes.push(Vec::new());
+
+                if let Some(variable) = variable
+                {
+                    let slot = self.define_local(variable);
+                    self.emit(Instruction::StoreLocal(slot));
+                }
+
+                self.compile_block(body)?;
+                self.clear_scopes_from(self.scopes.len() - 1);
+                self.scopes.pop();
+                self.emit(Instruction::Jump(start));
+
+                if let Some(jump) = exit_jump
+                {
+                    self.patch_jump(jump);
+                }
+
+                if let Some(state) = self.loops.pop()
+                {
+                    for jump in state.breaks
+                    {
+                        self.patch_jump(jump);
+                    }
+                }
+
+                if iterates
+                {
+                    self.emit(Instruction::PopIterator);
+                }
+            },
+            Statement::Return { value, .. } =>
+            {
+                match value
+                {
+                    Some(value) =>
+                    {
+                        self.compile_operation(value)?;
+                        self.emit(Instruction::Return);
+                    },
+                    None => { self.emit(Instruction::ReturnNull); }
+                }
+            },
+            Statement::Raise { value, .. } =>
+            {
+                self.compile_operation(value)?;
+                self.emit(Instruction::Raise);
+            },
+            Statement::Try { .. } =>
+            {
+                return Err("Try statements aren't supported by the compiler.".to_string());
+            },
+            Statement::Match { .. } =>
+            {
+                return Err("Match statements aren't supported by the compiler.".to_string());
+            },
+            Statement::Break(_) | Statement::Continue(_) =>
+            {
+                let (start, depth) = match self.loops.last()
+                {
+                    Some(state) => (state.start, state.scope_depth),
+                    None => { return Err("Loop control found outside of a loop.".to_string()); }
+                };
+
+                self.clear_scopes_from(depth);
+
+                if let Statement::Break(_) = statement
+                {
+                    let jump = self.emit(Instruction::Jump(0));
+                    if let Some(state) = self.loops.last_mut()
+                    {
+                        state.breaks.push(jump);
+                    }
+                }
+                else
+                {
+                    self.emit(Instruction::Jump(start));
+                }
+            },
+            // Only allowed globally, which is handled on setup.
+            Statement::Function(_) | Statement::Struct(_) | Statement::Import { .. } => ()
+        }
+
+        Ok(())
+    }
+
+    // Compiles the reverse polish operation, leaving its result on the stack.
+    fn compile_operation(&mut self, operation: &[Token]) -> Result<(), String>
+    {
+        // An operation of only a missing variable results in the variable itself.
+        if let [Token::Var(name)] = operation
+        {
+            if let Some(slot) = self.resolve_local(name)
+            {
+                self.emit(Instruction::LoadLocalOrVar(slot));
+            }
+            else
+            {
+                let index = self.name(name);
+                self.emit(Instruction::LoadGlobalOrVar(index));
+            }
+            return Ok(());
+        }
+
+        let mut depth = 0;
+
+        for token in operation.iter().rev()
+        {
+            if let Token::Operator(operator) = token
+            {
+                if let Some(index) = utility::UNARY_OPERATIONS.iter().position(|x| x == operator)
+                {
+                    if depth < 1
+                    {
+                        return Err(format!("Operator '{}' can't be compiled.", operator));
+                    }
+
+                    self.emit(Instruction::Unary(index));
+                    continue;
+                }
+
+                let index = match utility::OPERATIONS.iter().position(|x| x == operator)
+                {
+                    Some(index) if depth >= 2 => index,
+                    _ => { return Err(format!("Operator '{}' can't be compiled.", operator)); }
+                };
+
+                self.emit(Instruction::Operator(index));
+                depth -= 1;
+            }
+            else
+            {
+                self.compile_value(token)?;
+                depth += 1;
+            }
+        }
+
+        if depth != 1
+        {
+            return Err("Failed to parse operation. Try breaking down the statement into steps.".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn compile_value(&mut self, token: &Token) -> Result<(), String>
+    {
+        match token
+        {
+            Token::Var(name) =>
+            {
+                if let Some(slot) = self.resolve_local(name)
+                {
+                    self.emit(Instruction::LoadLocal(slot));
+                }
+                else
+                {
+                    let index = self.name(name);
+                    self.emit(Instruction::LoadGlobal(index));
+                }
+            },
+            // Instructions inside a call or nested operation use its span, so errors point at it.
+            Token::Call(name, arguments, span) =>
+            {
+                let outer = std::mem::replace(&mut self.span, *span);
+
+                for argument in arguments
+                {
+                    self.compile_operation(argument)?;
+                }
+
+                let index = self.name(name);
+                self.emit(Instruction::Call(index, arguments.len()));
+                self.span = outer;
+            },
+            Token::Format(template, values, span) =>
+            {
+                let outer = std::mem::replace(&mut self.span, *span);
+
+                for value in values
+                {
+                    self.compile_operation(value)?;
+                }
+
+                let index = self.constant(Token::String(template.clone()));
+                self.emit(Instruction::Format(index, values.len()));
+                self.span = outer;
+            },
+            Token::Operation(operation, span) =>
+            {
+                let outer = std::mem::replace(&mut self.span, *span);
+                self.compile_operation(operation)?;
+                self.span = outer;
+            }
+            Token::Collection(items) if items.iter().all(|x| is_constant(x) || matches!(x, Token::Var(_))) =>
+            {
+                for item in items
+                {
+                    // Items that fail to find a variable keep the variable token.
+                    self.compile_operation(std::slice::from_ref(item))?;
+                }
+
+                self.emit(Instruction::Collection(items.len()));
+            },
+            // Items that fail to run keep their token, which is left to the operation runner.
+            // Lambdas are run to capture the locals they use.
+            Token::Accessor(_, _) | Token::Collection(_) | Token::Map(_) | Token::Function(_) | Token::Named(_, _) if !is_constant(token) =>
+            {
+                let index = self.constant(token.clone());
+                self.emit(Instruction::Evaluate(index));
+            },
+            Token::Operator(_) | Token::Break | Token::Continue =>
+            {
+                return Err(format!("Unexpected token '{:?}' in operation.", token));
+            },
+            _ =>
+            {
+                let index = self.constant(token.clone());
+                self.emit(Instruction::Constant(index));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Values that don't need to be run.
+fn is_constant(token: &Token) -> bool
+{
+    match token
+    {
+        Token::Null | Token::Int(_) | Token::Float(_) | Token::Bool(_) | Token::String(_) | Token::Char(_) => true,
+        Token::Collection(items) => items.iter().all(is_constant),
+        Token::Map(map) => map.values().all(is_constant),
+        _ => false
+    }
+}
@@ -0,0 +1,58 @@
+use crate::types::{Token, VarMap};
+
+use super::operation_runner::LocalVars;
+
+// Variables of a running function, from the function frame (parameters) to the innermost block.
+// Globals aren't copied in, they stay in Runner::vars and are checked after every frame.
+#[derive(Debug, Default)]
+pub struct Environment
+{
+    frames: Vec<VarMap>,
+    // Function being run, for errors caught in it.
+    pub function_name: String,
+}
+
+impl Environment
+{
+    pub fn new(function_name: &str, function_frame: VarMap) -> Environment
+    {
+        Environment { frames: vec![function_frame], function_name: function_name.to_string() }
+    }
+
+    pub fn push_frame(&mut self)
+    {
+        self.frames.push(VarMap::new());
+    }
+
+    // Variables defined in the frame are dropped with it.
+    pub fn pop_frame(&mut self)
+    {
+        self.frames.pop();
+    }
+
+    // Innermost frame that defines the variable.
+    pub fn frame_of(&mut self, name: &str) -> Option<&mut VarMap>
+    {
+        self.frames.iter_mut().rev().find(|frame| frame.contains_key(name))
+    }
+
+    // Frame new variables are defined in.
+    pub fn current_frame(&mut self) -> &mut VarMap
+    {
+        if self.frames.is_empty()
+        {
+            self.push_frame();
+        }
+
+        // Unwrap allowed, as a frame was just made if there were none.
+        self.frames.last_mut().unwrap()
+    }
+}
+
+impl LocalVars for Environment
+{
+    fn get_local(&self, name: &str) -> Option<&Token>
+    {
+        self.frames.iter().rev().find_map(|frame| frame.get(name)).map(|x| &x.0)
+    }
+}
@@ -0,0 +1,154 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use crate::types::{Runner, Token, Span, Statement, ScriptProvider, VariableModifier};
+use crate::types::error::*;
+
+impl Runner
+{
+    pub fn set_script_provider(&mut self, provider: Rc<dyn ScriptProvider>) -> &mut Self
+    {
+        self.script_provider = Some(provider);
+
+        self
+    }
+
+    // Loads every script referenced by an external variable.
+    // Scripts are otherwise loaded when first used, so this only reports missing scripts and cycles early.
+    pub fn link_external_scripts(&mut self, error_manager: &mut ErrorManager) -> &mut Self
+    {
+        let statements = self.parser.statements.clone();
+
+        for (name, span) in statements.iter().filter_map(|x| if let Statement::Assign { name, span, .. } = x { Some((name, span)) } else { None })
+        {
+            let path = match self.external_path(name)
+            {
+                Some(path) => path.to_string(),
+                None => { continue; }
+            };
+
+            if let Err(error) = self.external_script(&path)
+            {
+                let mut runtime_error = RuntimeError::new(*span, None, error.as_str());
+                runtime_error.set_script_name(&self.parser.name);
+
+                push_error!(error_manager, runtime_error);
+            }
+        }
+
+        self
+    }
+
+    // Path of the script a global external variable refers to.
+    pub(crate) fn external_path(&self, name: &str) -> Option<&str>
+    {
+        match self.vars.get(name)
+        {
+            Some((Token::String(path), _, modifiers)) if modifiers.contains(&VariableModifier::External) => Some(path.as_str()),
+            _ => None
+        }
+    }
+
+    // Splits a call like other.take_damage into the path of the external script and the function name.
+    pub(crate) fn external_function<'a>(&self, call_name: &'a str) -> Option<(String, &'a str)>
+    {
+        let (name, function_name) = call_name.split_once('.')?;
+
+        self.external_path(name).map(|path| (path.to_string(), function_name))
+    }
+
+    fn external_paths(&self) -> Vec<String>
+    {
+        let mut paths: Vec<String> = self.vars.keys().filter_map(|x| self.external_path(x)).map(|x| x.to_string()).collect();
+        paths.sort();
+        paths.dedup();
+
+        paths
+    }
+
+    // Finds a script by path, loading it through the provider the first time.
+    fn external_script(&mut self, path: &str) -> Result<Rc<RefCell<Runner>>, String>
+    {
+        if let Some(script) = self.external_scripts.get(path)
+        {
+            return Ok(script.clone());
+        }
+
+        let script = self.load_script(path)?;
+
+        let mut chain = vec![self.parser.name.clone(), path.to_string()];
+        self.check_cycle(&script, &mut chain)?;
+
+        self.external_scripts.insert(path.to_string(), script.clone());
+
+        Ok(script)
+    }
+
+    fn load_script(&self, path: &str) -> Result<Rc<RefCell<Runner>>, String>
+    {
+        match &self.script_provider
+        {
+            Some(provider) => provider.get_script(path),
+            None => Err(format!("No script provider was set to load '{}'.", path))
+        }
+    }
+
+    // Follows the external references of a script, failing if any lead back to a script in the chain.
+    // A script that can't be borrowed is already running, so it's somewhere up the chain as well.
+    fn check_cycle(&self, script: &Rc<RefCell<Runner>>, chain: &mut Vec<String>) -> Result<(), String>
+    {
+        let paths = match script.try_borrow()
+        {
+            Ok(script) => script.external_paths().into_iter()
+                .map(|x| (script.external_scripts.get(&x).cloned(), x))
+                .collect::<Vec<_>>(),
+            Err(_) => { return Err(format!("Cyclic external reference: {}.", chain.join(" -> "))); }
+        };
+
+        for (loaded, path) in paths
+        {
+            let is_cycle = chain.contains(&path);
+            chain.push(path.clone());
+
+            if is_cycle
+            {
+                return Err(format!("Cyclic external reference: {}.", chain.join(" -> ")));
+            }
+
+            let next = match loaded
+            {
+                Some(next) => next,
+                None => self.load_script(&path)?
+            };
+
+            self.check_cycle(&next, chain)?;
+            chain.pop();
+        }
+
+        Ok(())
+    }
+
+    // Reads a public variable of the script an external variable refers to. (Eg. other.hp)
+    pub(crate) fn get_external_variable(&mut self, path: &str, name: &str) -> Result<Token, String>
+    {
+        let script = self.external_script(path)?;
+        let script = script.try_borrow().map_err(|_| format!("Cyclic external reference: '{}' is already running.", path))?;
+
+        match script.vars.get(name)
+        {
+            Some((value, _, modifiers)) if modifiers.contains(&VariableModifier::Public) => Ok(value.clone()),
+            Some(_) => Err(format!("Variable '{}' of '{}' isn't public.", name, path)),
+            None => Err(format!("Could not find a variable by the name '{}' in '{}'.", name, path))
+        }
+    }
+
+    // Calls a function of the script an external variable refers to. (Eg. other.take_damage(3))
+    pub(crate) fn call_external_script(&mut self, path: &str, function_name: &str, args: Vec<Token>, span: Span) -> Result<Option<Token>, ScriptError>
+    {
+        let script = self.external_script(path).map_err(|x| ScriptError::new(x, span))?;
+        let mut script = script.try_borrow_mut().map_err(|_| (format!("Cyclic external reference: '{}' is already running.", path), span))?;
+
+        // Errors are located in this script, as the other's lines mean nothing here.
+        script.call(function_name, args, span).map_err(|x| ScriptError::new(format!("{} (In '{}')", x.message, path), span))
+    }
+}
@@ -0,0 +1,163 @@
+use std::rc::Rc;
+
+use crate::types::{Runner, Token, Span, Statement, FunctionDef, Function, VarMap};
+use crate::types::error::*;
+
+use super::environment::Environment;
+use super::operation_runner::LocalVars;
+use super::check_unnamed;
+use super::try_catch::name_error;
+
+impl Runner
+{
+    // Anything that can be called by the name. Used to turn the name into a function value. (Eg. f = print)
+    pub(crate) fn has_function(&self, name: &str) -> bool
+    {
+        self.external_functions.contains_key(name) || self.context_functions.contains_key(name) || self.callback_functions.contains_key(name)
+            || self.parser.find_function(name).is_some() || self.parser.find_struct(name).is_some()
+    }
+
+    // Calls a function value, such as a callback the script gave to the host.
+    pub fn call_value(&mut self, function: &Function, args: Vec<Token>, error_manager: &mut ErrorManager) -> Option<Token>
+    {
+        match self.call_function_value(function, args, Span::default())
+        {
+            Ok(result) => result,
+            Err(error) =>
+            {
+                let mut runtime_error = error.to_runtime_error(&function.to_string());
+                runtime_error.set_script_name(&self.parser.name);
+
+                push_error!(error_manager, runtime_error);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn call_function_value(&mut self, function: &Function, args: Vec<Token>, span: Span) -> Result<Option<Token>, ScriptError>
+    {
+        match function
+        {
+            Function::Named(name) => self.call(name, args, span),
+            Function::Lambda { definition, captures } =>
+            {
+                let args = self.bind_arguments(&definition.name, &definition.parameters, args, span)?;
+                let result = self.call_lambda(definition, captures.as_ref().map_or(&[], |x| x.as_slice()), args);
+                name_error(&definition.name, result)
+            },
+            Function::Host(function) =>
+            {
+                check_unnamed("<host fn>", &args).map_err(|error| ScriptError::new(error, span))?;
+                function(args).map_err(|error| ScriptError::new(error, span))
+            }
+        }
+    }
+
+    // Calls by name, unless a local holds a function by that name. (Eg. a callback parameter)
+    pub(crate) fn call_with_locals(&mut self, name: &str, args: Vec<Token>, span: Span, vars: &dyn LocalVars) -> Result<Option<Token>, ScriptError>
+    {
+        match vars.get_local(name)
+        {
+            Some(Token::Function(function)) => self.call_function_value(&function.clone(), args, span),
+            _ => self.call(name, args, span)
+        }
+    }
+
+    fn call_lambda(&mut self, definition: &FunctionDef, captures: &[(String, Token)], arguments: Vec<Token>) -> Result<Option<Token>, ScriptError>
+    {
+        // Parameters hide captured variables of the same name.
+        let mut frame: VarMap = captures.iter().map(|(name, value)| (name.clone(), (value.clone(), false, vec![]))).collect();
+        frame.extend(definition.parameters.iter().map(|x| x.name.clone()).zip(arguments.into_iter().map(|x| (x, false, vec![]))));
+
+        match self.handle_scope(&definition.body, &mut Environment::new(&definition.name, frame))?
+        {
+            Some(Token::Null) | Some(Token::Break) | Some(Token::Continue) => Ok(None),
+            result => Ok(result)
+        }
+    }
+}
+
+// Gives a lambda the values of the locals it uses, at the time it's made.
+// Globals aren't captured, as they can still be found when it's called.
+pub(crate) fn capture(definition: &Rc<FunctionDef>, vars: &dyn LocalVars) -> Token
+{
+    let mut names: Vec<String> = Vec::new();
+    statement_names(&definition.body, &mut names);
+
+    let mut captures: Vec<(String, Token)> = Vec::new();
+
+    for name in names.into_iter().filter(|x| !definition.parameters.iter().any(|parameter| &parameter.name == x))
+    {
+        if let Some(value) = vars.get_local(&name).filter(|_| !captures.iter().any(|x| x.0 == name))
+        {
+            captures.push((name, value.clone()));
+        }
+    }
+
+    Token::Function(Function::Lambda { definition: definition.clone(), captures: Some(Rc::new(captures)) })
+}
+
+fn statement_names(statements: &[Statement], names: &mut Vec<String>)
+{
+    for statement in statements
+    {
+        if let Statement::Return { value: Some(value), .. } = statement
+        {
+            operation_names(value, names);
+        }
+    }
+}
+
+// Variable and function names an operation uses. Dotted names count as their base variable.
+fn operation_names(tokens: &[Token], names: &mut Vec<String>)
+{
+    for token in tokens
+    {
+        match token
+        {
+            Token::Var(name) | Token::Call(name, _, _) =>
+            {
+                names.push(name.split('.').next().unwrap_or(name).to_string());
+
+                if let Token::Call(_, args, _) = token
+                {
+                    args.iter().for_each(|x| operation_names(x, names));
+                }
+            },
+            Token::Operation(operation, _) => operation_names(operation, names),
+            Token::Format(_, values, _) => values.iter().for_each(|x| operation_names(x, names)),
+            Token::Named(_, value) => operation_names(std::slice::from_ref(value), names),
+            Token::Collection(items) => operation_names(items, names),
+            Token::Map(map) => map.values().for_each(|x| operation_names(std::slice::from_ref(x), names)),
+            Token::Accessor(prev, accessor) =>
+            {
+                operation_names(std::slice::from_ref(prev), names);
+                accessor_names(accessor, names);
+            },
+            // Nested lambdas need their values captured here first.
+            Token::Function(Function::Lambda { definition, .. }) =>
+            {
+                let mut inner: Vec<String> = Vec::new();
+                statement_names(&definition.body, &mut inner);
+                names.extend(inner.into_iter().filter(|x| !definition.parameters.iter().any(|parameter| &parameter.name == x)));
+            },
+            _ => ()
+        }
+    }
+}
+
+// Names after a '.' are fields, so only arguments and indexes are looked at.
+fn accessor_names(accessor: &Token, names: &mut Vec<String>)
+{
+    match accessor
+    {
+        Token::Call(_, args, _) => args.iter().for_each(|x| operation_names(x, names)),
+        Token::Operation(operation, _) => operation_names(operation, names),
+        Token::Accessor(first, rest) =>
+        {
+            accessor_names(first, names);
+            accessor_names(rest, names);
+        },
+        _ => ()
+    }
+}
@@ -1,111 +1,107 @@
-use crate::runner::VariableModifier;
-use crate::types::{Runner, Token, ExpressionList, ExpressionListType, VarMap};
+use crate::types::VariableModifier;
+use crate::types::{Runner, Token, Span, Statement, LoopKind, VarMap, Operation};
+use crate::types::error::ScriptError;
 
-use super::operation_runner::run_operation;
-use crate::parser::operation_parser::parse_operation;
+use super::environment::Environment;
+use super::operation_runner::{run_operation, call_accessor, LocalVars};
+use super::try_catch::raise;
 
 impl Runner
 {
     // Resursive function to handle calling order in internal scopes.
     // Originally called by call_internal for a parsed function.
-    pub fn handle_scope(&mut self, function: &ExpressionList, vars: &mut VarMap, is_loop: bool) -> Result<Option<Token>, (String, usize)>
+    // Loop controls are returned as Token::Break or Token::Continue so the loop they're in can handle them.
+    // Variables defined in the scope get their own frame, which is dropped at the end of it.
+    pub fn handle_scope(&mut self, statements: &[Statement], env: &mut Environment) -> Result<Option<Token>, ScriptError>
     {
-        let mut return_result: Result<Option<Token>, (String, usize)> = Ok(None);
-        
-        let mut local_var_refs: Vec<&str> = Vec::new();
+        env.push_frame();
+        let return_result = self.handle_statements(statements, env);
+        env.pop_frame();
 
-        let mut previous_if_failed = false;
-
-        // Follow through and run every expression.
-        
-        let mut single_index: usize = 0;
-        let mut multi_index: usize = 0;
-        let mut internal_index: usize = 0;
+        return_result
+    }
 
-        let mut i: usize = 0;
+    fn handle_statements(&mut self, statements: &[Statement], env: &mut Environment) -> Result<Option<Token>, ScriptError>
+    {
+        let mut return_result: Result<Option<Token>, ScriptError> = Ok(None);
 
-        for expression_type in &function.expression_order
+        // Follow through and run every statement.
+        for statement in statements
         {
-            match expression_type
+            match statement
             {
-                // Return, Assignement, loop controls.
-                ExpressionListType::Single =>
+                Statement::Return { value, span } =>
                 {
-                    let expression = &function.single_op[single_index];
-                    let operation = run_operation(self, &expression.2, &vars);
-
-                    single_index += 1;
-
-                    match expression.0.as_str()
+                    match value
                     {
-                        "return" =>
+                        Some(value) =>
                         {
-                            match operation
+                            match run_operation(self, value, *span, env)
                             {
-                                Ok(result) => { return_result = Ok(result); }
-                                Err(error) => { return Err((error, function.line_start+i+1)); }
+                                // Nothing to return still ends the function.
+                                Ok(result) => { return_result = Ok(Some(result.unwrap_or(Token::Null))); }
+                                Err(error) => { return Err(error); }
                             }
                         },
-                        "break" =>
+                        None => { return_result = Ok(Some(Token::Null)); }
+                    }
+                },
+                // Break completely out of outer scope loop.
+                Statement::Break(_) => { return_result = Ok(Some(Token::Break)); },
+                // Skip the rest of the loop body. Which continues in the outer scope loop.
+                Statement::Continue(_) => { return_result = Ok(Some(Token::Continue)); },
+                Statement::Assign { name, modifiers, value, indexes, span } =>
+                {
+                    match run_operation(self, value, *span, env)
+                    {
+                        // Keys or indexes of a variable. (Eg. m["hp"] = 3)
+                        Ok(Some(result)) if !indexes.is_empty() =>
+                        {
+                            let path = self.assign_path(name, indexes, *span, env)?;
+                            self.change_field(env, variable_of(name), &path, result).map_err(|error| ScriptError::new(error, *span))?;
+                        },
+                        Ok(Some(result)) =>
                         {
-                            if is_loop
+                            // Check if any of the parent scopes or the globals contains this var.
+                            if env.get_local(name).is_some() || self.vars.contains_key(name)
                             {
-                                // Break completely out of outer scope loop.
-                                return_result = Ok(Some(Token::Break));
-                                break;
+                                self.change_variable(env, name, result).map_err(|error| ScriptError::new(error, *span))?;
                             }
-                        }
-                        "continue" =>
-                        {
-                            if is_loop
+                            // Field of an existing variable. (Eg. pos.x = 2)
+                            else if let Some((base, path)) = name.split_once('.').filter(|x| env.get_local(x.0).is_some() || self.vars.contains_key(x.0))
                             {
-                                // Break out of current loop. Which continues in the outer scope loop.
-                                break;
+                                self.change_field(env, base, &path.split('.').collect::<Vec<&str>>(), result).map_err(|error| ScriptError::new(error, *span))?;
                             }
-                        },
-                        string =>
-                        {
-                            match operation
+                            else if let Err(error) = self.handle_variable_modifiers(name, value, result, modifiers.clone(), true, Some(env.current_frame()))
                             {
-                                Ok(Some(result)) =>
-                                {
-                                    // Check if any of the parent hashmap contains this var.
-                                    if vars.contains_key(string)
-                                    {
-                                        self.handle_change_var_entry(vars, string, result);
-                                    }
-                                    else
-                                    {
-                                        let modifier_list = expression.1.iter().map(|x| From::from(x.as_str())).collect::<Vec<VariableModifier>>();
-
-                                        if let Err(error) = self.handle_variable_modifiers(expression, result, modifier_list, true, Some(vars))
-                                        {
-                                            return Err((error, function.line_start+i+1));
-                                        }
-
-                                        local_var_refs.push(string)
-                                    }
-                                },
-                                Ok(None) => (),
-                                Err(error) =>
-                                {
-                                    return Err((error, function.line_start+i+1));
-                                }
+                                return Err(ScriptError::new(error, *span));
                             }
+                        },
+                        Ok(None) => (),
+                        Err(error) =>
+                        {
+                            return Err(error);
                         }
                     }
                 },
                 // Function call.
-                ExpressionListType::Multi =>
+                Statement::Call { name, arguments, span } =>
                 {
-                    let expression = &function.multi_ops[multi_index];
-                    let mut args: Vec<Token> = vec![];
+                    // Calls on a variable, like methods of an object, are run as an accessor. (Eg. player.move(1, 0))
+                    if let Some((variable, _)) = name.split_once('.')
+                    {
+                        if env.get_local(variable).is_some() || self.vars.contains_key(variable)
+                        {
+                            run_operation(self, &[call_accessor(name, arguments, *span)], *span, env)?;
+                            continue;
+                        }
+                    }
 
-                    multi_index += 1;
+                    let mut args: Vec<Token> = vec![];
 
-                    for tokens in expression.1.iter()
+                    for tokens in arguments.iter()
                     {
-                        match run_operation(self, tokens, &vars)
+                        match run_operation(self, tokens, *span, env)
                         {
                             Ok(Some(result)) =>
                             {
@@ -114,186 +110,263 @@ impl Runner
                             Ok(None) => (),
                             Err(error) =>
                             {
-                                return Err((error, function.line_start+i+1));
+                                return Err(error);
                             }
                         }
                     }
 
-                    match self.call(&expression.0, args, function.line_start+i+1)
-                    {
-                        Ok(_) => (),
-                        Err(error) =>
-                        {
-                            return Err(error);
-                        }
-                    }
+                    self.call_with_locals(name, args, *span, env)?;
                 },
-                // Internal scope.
-                ExpressionListType::Internal =>
+                // Only the first branch with a passing condition (or an else) is run.
+                Statement::If { branches, .. } =>
                 {
-                    let function = &function.internal_expressions[internal_index];
-
-                    internal_index += 1;
-
-                    if let Some(scope_name) = &function.0.scope_info.0
+                    for branch in branches
                     {
-                        match scope_name.as_str()
+                        let passed = match &branch.condition
                         {
-                            "if" => 
-                            {
-                                if let Some(if_op) = &function.0.scope_info.1
-                                {
-                                    // Only handle scope if the "if operation" is true.
-                                    match parse_operation(if_op)
-                                    {
-                                        Ok(operation) =>
-                                        {
-                                            match run_operation(self, &operation, &vars)
-                                            {
-                                                Ok(Some(Token::Bool(true))) =>
-                                                {
-                                                    return_result = self.handle_scope(&function.0, vars, is_loop);
-                                                    previous_if_failed = false;
-                                                }
-                                                Err(error) =>
-                                                {
-                                                    return Err((error, function.1));
-                                                }
-                                                _ =>
-                                                {
-                                                    previous_if_failed = true;
-                                                }
-                                            }
-                                        }
-                                        Err(error) => {return Err((error, function.1));}
-                                    }
-                                }
-                            },
-                            // Only run if "if" failed
-                            "elif" =>
-                            {
-                                if previous_if_failed
-                                {
-                                    if let Some(if_op) = &function.0.scope_info.1
-                                    {
-                                        // Only handle scope if the "elif operation" is true.
-                                        match parse_operation(if_op)
-                                        {
-                                            Ok(operation) =>
-                                            {
-                                                match run_operation(self, &operation, &vars)
-                                                {
-                                                    Ok(Some(Token::Bool(true))) =>
-                                                    {
-                                                        return_result = self.handle_scope(&function.0, vars, is_loop);
-                                                        previous_if_failed = false;
-                                                    }
-                                                    Err(error) =>
-                                                    {
-                                                        return Err((error, function.1));
-                                                    }
-                                                    _ =>
-                                                    {
-                                                        previous_if_failed = true;
-                                                    }
-                                                }
-                                            }
-                                            Err(error) => {return Err((error, function.1));}
-                                        }
-                                    }
-                                }
-                            },
-                            // Only run if "if" failed
-                            "else" =>
+                            Some(condition) =>
                             {
-                                if previous_if_failed
+                                match run_operation(self, condition, branch.span, env)
                                 {
-                                    return_result = self.handle_scope(&function.0, vars, is_loop);
+                                    Ok(Some(Token::Bool(true))) => true,
+                                    Err(error) => { return Err(error); }
+                                    _ => false
                                 }
                             },
-                            "loop" =>
-                            {
-                                loop
-                                {
-                                    return_result = self.handle_scope(&function.0, vars, true);
-
-                                    match return_result
-                                    {
-                                        Ok(Some(Token::Break)) =>
-                                        {
-                                            return_result = Ok(None);
-                                            break;
-                                        }
-                                        Ok(Some(_)) =>
-                                        {
-                                            break;
-                                        }
-                                        _ => ()
-                                    }
-                                }
-                            }
-                            _ => return_result = self.handle_scope(&function.0, vars, is_loop)
+                            None => true
+                        };
+
+                        if passed
+                        {
+                            return_result = self.handle_scope(&branch.body, env);
+                            break;
                         }
                     }
-                    else
-                    {
-                        return_result = self.handle_scope(&function.0, vars, is_loop);
-                    }
                 },
-                _ => ()
+                Statement::Loop { kind, body, span } =>
+                {
+                    return_result = self.handle_loop(kind, body, *span, env);
+                },
+                Statement::Match { subject, cases, span } =>
+                {
+                    return_result = self.handle_match(subject, cases, *span, env);
+                },
+                Statement::Try { body, variable, handler, .. } =>
+                {
+                    return_result = self.handle_try(body, variable.as_deref(), handler, env);
+                },
+                Statement::Raise { value, span } =>
+                {
+                    let value = run_operation(self, value, *span, env)?.unwrap_or(Token::Null);
+                    return Err(raise(value, *span));
+                },
+                // Only allowed globally, which is handled on setup.
+                Statement::Function(_) | Statement::Struct(_) | Statement::Import { .. } => ()
             }
 
-            // If found a return statement, break out of the expression loop.
-            if let Ok(Some(_)) = return_result
+            // If found a return statement or loop control, break out of the statement loop.
+            if let Ok(Some(_)) | Err(_) = return_result
             {
                 break;
             }
-
-            i += 1;
-        }
-
-        for local_var in local_var_refs
-        {
-            vars.remove(local_var);
         }
 
         return_result
     }
 
-    fn handle_change_var_entry(&mut self, vars: &mut VarMap, entry_name: &str, operation_result: Token)
+    // Runs the body of a loop until its condition fails, it runs out of values, or it's broken out of.
+    // The loop variable gets a frame around the body, so it only exists inside it.
+    fn handle_loop(&mut self, kind: &LoopKind, body: &[Statement], span: Span, env: &mut Environment) -> Result<Option<Token>, ScriptError>
     {
-        let mut alter_target: Option<String> = None;
-        vars.entry(entry_name.to_string()).and_modify(
-            |x|
+        // Values are found once, before the first run of the body.
+        let (variable, mut range, items) = match kind
+        {
+            LoopKind::Range { variable, start, end } =>
             {
-                // Make sure not external or change has the same type.
-                if !x.1 || Token::variant_equal(&operation_result, &x.0)
+                let start = range_bound(run_operation(self, start, span, env)?).map_err(|error| ScriptError::new(error, span))?;
+                let end = range_bound(run_operation(self, end, span, env)?).map_err(|error| ScriptError::new(error, span))?;
+
+                (Some(variable), start..end, vec![])
+            },
+            LoopKind::Each { variable, collection } =>
+            {
+                let items = match run_operation(self, collection, span, env)?
                 {
-                    // Check alias to affect targeted variable.
-                    if !x.2.contains(&VariableModifier::Alias)
-                    {
-                        x.0 = operation_result.clone();
-                    }
-                    else
+                    Some(value) => loop_items(value).map_err(|error| ScriptError::new(error, span))?,
+                    None => vec![]
+                };
+
+                (Some(variable), 0..0, items)
+            },
+            _ => (None, 0..0, vec![])
+        };
+
+        let mut items = items.into_iter();
+
+        loop
+        {
+            let value = match kind
+            {
+                LoopKind::Infinite => None,
+                LoopKind::While(condition) =>
+                {
+                    match run_operation(self, condition, span, env)
                     {
-                        if let Token::Var(targeted_var) = &x.0
-                        {
-                            alter_target = Some(targeted_var.to_string());
-                        }
+                        Ok(Some(Token::Bool(true))) => None,
+                        Err(error) => { return Err(error); }
+                        _ => { break; }
                     }
-                }
+                },
+                LoopKind::Range { .. } => match range.next() { Some(i) => Some(Token::Int(i)), None => { break; } },
+                LoopKind::Each { .. } => match items.next() { Some(item) => Some(item), None => { break; } }
+            };
 
-                // Add an indicator if an external var has changed.
-                if x.1 { self.var_indexes_changed.push(entry_name.to_string()); }
+            env.push_frame();
+            if let (Some(name), Some(value)) = (variable, value)
+            {
+                env.current_frame().insert(name.to_string(), (value, false, vec![]));
+            }
+
+            let result = self.handle_scope(body, env);
+            env.pop_frame();
+
+            match result?
+            {
+                Some(Token::Break) => { break; }
+                // Continue just starts the next run of the body.
+                Some(Token::Continue) | None => (),
+                result => { return Ok(result); }
             }
-        );
+        }
+
+        Ok(None)
+    }
+
+    // Changes an existing variable in the innermost scope defining it, or the globals.
+    // Aliases pass the change on to the variable they target.
+    fn change_variable(&mut self, env: &mut Environment, name: &str, value: Token) -> Result<(), String>
+    {
+        let target = match env.frame_of(name)
+        {
+            Some(frame) => self.handle_change_var_entry(frame, name, value.clone())?,
+            None => self.change_global_entry(name, value.clone())?
+        };
+
+        match target
+        {
+            Some(target) => self.change_variable(env, &target, value),
+            None => Ok(())
+        }
+    }
+
+    // Changes a field nested in a variable's value, by the path after the variable name. (Eg. x in pos.x)
+    pub(crate) fn change_field<S: AsRef<str>>(&mut self, env: &mut Environment, name: &str, path: &[S], value: Token) -> Result<(), String>
+    {
+        let mut target = run_operation(self, &[Token::Var(name.to_string())], Span::default(), env).map_err(|error| error.message)?.unwrap_or(Token::Null);
+        target.set_field(path, value)?;
+
+        self.change_variable(env, name, target)
+    }
+
+    // Fields, keys and indexes an assignment changes in its variable. (Eg. stats and hp in m.stats["hp"] = 3)
+    pub(crate) fn assign_path(&mut self, name: &str, indexes: &[Operation], span: Span, vars: &dyn LocalVars) -> Result<Vec<String>, ScriptError>
+    {
+        let mut path: Vec<String> = name.split('.').skip(1).map(|x| x.to_string()).collect();
 
-        if let Some(target) = alter_target
+        for index in indexes
         {
-            if vars.contains_key(&target)
+            match run_operation(self, index, span, vars)?
             {
-                self.handle_change_var_entry(vars, &target, operation_result);
+                Some(Token::String(key)) => path.push(key),
+                Some(Token::Int(i)) => path.push(i.to_string()),
+                other => { return Err(ScriptError::new(format!("Keys need to be strings, and indexes Ints. Found: '{}'", other.unwrap_or(Token::Null)), span)); }
             }
         }
+
+        Ok(path)
+    }
+
+    // Changes a global, following aliases to other globals.
+    pub(crate) fn change_global(&mut self, name: &str, value: Token) -> Result<(), String>
+    {
+        match self.change_global_entry(name, value.clone())?
+        {
+            Some(target) => self.change_global(&target, value),
+            None => Ok(())
+        }
     }
+
+    fn change_global_entry(&mut self, name: &str, value: Token) -> Result<Option<String>, String>
+    {
+        // Taken out so the runner can still track changes to external variables.
+        let mut globals = std::mem::take(&mut self.vars);
+        let target = self.handle_change_var_entry(&mut globals, name, value);
+        self.vars = globals;
+
+        target
+    }
+
+    // Returns the variable targeted if the entry is an alias.
+    fn handle_change_var_entry(&mut self, vars: &mut VarMap, entry_name: &str, operation_result: Token) -> Result<Option<String>, String>
+    {
+        let entry = match vars.get_mut(entry_name)
+        {
+            Some(entry) => entry,
+            None => { return Ok(None); }
+        };
+
+        if entry.2.contains(&VariableModifier::Const)
+        {
+            return Err(format!("Cannot assign to '{}', as it's a constant.", entry_name));
+        }
+
+        let mut alter_target: Option<String> = None;
+
+        // Make sure not external or change has the same type.
+        if !entry.1 || Token::variant_equal(&operation_result, &entry.0)
+        {
+            // Check alias to affect targeted variable.
+            if !entry.2.contains(&VariableModifier::Alias)
+            {
+                entry.0 = operation_result;
+            }
+            else if let Token::Var(targeted_var) = &entry.0
+            {
+                alter_target = Some(targeted_var.to_string());
+            }
+        }
+
+        // Add an indicator if an external var has changed.
+        if entry.1 { self.var_indexes_changed.push(entry_name.to_string()); }
+
+        Ok(alter_target)
+    }
+}
+
+// Start or end of a loop range.
+pub(crate) fn range_bound(value: Option<Token>) -> Result<i64, String>
+{
+    match value
+    {
+        Some(Token::Int(i)) => Ok(i),
+        Some(value) => Err(format!("Loop ranges need Int values. Found: '{}'", value)),
+        None => Err("Loop ranges need Int values.".to_string())
+    }
+}
+
+// Values looped over by a 'loop for'. Strings are looped over by character.
+pub(crate) fn loop_items(value: Token) -> Result<Vec<Token>, String>
+{
+    match value
+    {
+        Token::Collection(items) => Ok(items),
+        Token::String(string) => Ok(string.chars().map(Token::Char).collect()),
+        value => Err(format!("Cannot loop over '{}'. Expected a collection or string.", value))
+    }
+}
+
+// Variable a field or key belongs to. (Eg. pos in pos.x)
+pub(crate) fn variable_of(name: &str) -> &str
+{
+    name.split('.').next().unwrap_or(name)
 }
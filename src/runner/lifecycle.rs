@@ -0,0 +1,80 @@
+use crate::types::{Runner, Token, LifecycleHook};
+use crate::types::error::*;
+
+use super::signature;
+
+impl Runner
+{
+    // Finds the lifecycle functions the script defines.
+    // Hooks with the wrong amount of parameters are reported and never called.
+    pub(crate) fn find_hooks(&mut self, error_manager: &mut ErrorManager)
+    {
+        self.hooks.clear();
+
+        for hook in LifecycleHook::ALL.iter().copied()
+        {
+            let function = match self.parser.find_function(hook.function_name())
+            {
+                Some(function) => function,
+                None => { continue; }
+            };
+
+            if !function.accepts(hook.parameter_count())
+            {
+                push_error!(error_manager, RuntimeError::new(function.span, Some(function.name.clone()),
+                    format!("'{}' should take {} parameters, but is {}.", function.name, hook.parameter_count(), signature(&function.name, &function.parameters)).as_str()));
+                continue;
+            }
+
+            self.hooks.push(hook);
+        }
+    }
+
+    pub fn has_hook(&self, hook: LifecycleHook) -> bool
+    {
+        self.hooks.contains(&hook)
+    }
+
+    // Calls a lifecycle function of the script. Does nothing if the script doesn't define it.
+    pub fn call_hook(&mut self, hook: LifecycleHook, args: Vec<Token>, error_manager: &mut ErrorManager) -> Option<Token>
+    {
+        if !self.has_hook(hook)
+        {
+            return None;
+        }
+
+        self.call_function(hook.function_name(), args, error_manager)
+    }
+
+    // on_start()
+    pub fn start(&mut self, error_manager: &mut ErrorManager) -> &mut Self
+    {
+        self.call_hook(LifecycleHook::Start, vec![], error_manager);
+
+        self
+    }
+
+    // on_update(dt)
+    // Delta time is passed in seconds.
+    pub fn update(&mut self, delta_time: f64, error_manager: &mut ErrorManager) -> &mut Self
+    {
+        self.call_hook(LifecycleHook::Update, vec![Token::Float(delta_time)], error_manager);
+
+        self
+    }
+
+    // on_destroy()
+    pub fn destroy(&mut self, error_manager: &mut ErrorManager) -> &mut Self
+    {
+        self.call_hook(LifecycleHook::Destroy, vec![], error_manager);
+
+        self
+    }
+
+    // on_event(name, payload)
+    // Returns whatever the script returns, so it can answer the event.
+    pub fn send_event(&mut self, name: &str, payload: Token, error_manager: &mut ErrorManager) -> Option<Token>
+    {
+        self.call_hook(LifecycleHook::Event, vec![Token::String(name.to_string()), payload], error_manager)
+    }
+}
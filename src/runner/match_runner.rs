@@ -0,0 +1,78 @@
+use crate::types::{Runner, Token, Span, Case, Pattern};
+use crate::types::error::ScriptError;
+
+use super::environment::Environment;
+use super::operation_runner::run_operation;
+
+impl Runner
+{
+    // Runs the first case that matches the subject, which is only run once.
+    // Names given by the pattern exist for the guard and body of the case.
+    pub(crate) fn handle_match(&mut self, subject: &[Token], cases: &[Case], span: Span, env: &mut Environment) -> Result<Option<Token>, ScriptError>
+    {
+        let value = run_operation(self, subject, span, env)?.unwrap_or(Token::Null);
+
+        for case in cases
+        {
+            let mut bindings: Vec<(String, Token)> = Vec::new();
+
+            if !match_pattern(&case.pattern, &value, &mut bindings)
+            {
+                continue;
+            }
+
+            env.push_frame();
+            for (name, value) in bindings
+            {
+                env.current_frame().insert(name, (value, false, vec![]));
+            }
+
+            let passed = match &case.guard
+            {
+                Some(guard) => run_operation(self, guard, case.span, env).map(|result| matches!(result, Some(Token::Bool(true)))),
+                None => Ok(true)
+            };
+
+            let result = match passed
+            {
+                Ok(true) => Some(self.handle_scope(&case.body, env)),
+                Ok(false) => None,
+                Err(error) => Some(Err(error))
+            };
+            env.pop_frame();
+
+            if let Some(result) = result
+            {
+                return result;
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// Whether the value fits the pattern, collecting the values of the names it gives.
+fn match_pattern(pattern: &Pattern, value: &Token, bindings: &mut Vec<(String, Token)>) -> bool
+{
+    match pattern
+    {
+        Pattern::Wildcard => true,
+        Pattern::Binding(name) =>
+        {
+            bindings.push((name.clone(), value.clone()));
+            true
+        },
+        // Values of the same type are compared the same as '=='. Ints and floats can match each other.
+        Pattern::Literal(literal) =>
+        {
+            let numbers = matches!(literal, Token::Int(_) | Token::Float(_)) && matches!(value, Token::Int(_) | Token::Float(_));
+            (numbers || literal.variant_equal(value)) && matches!(literal.compare_eq(value), Some(Token::Bool(true)))
+        },
+        Pattern::Collection(patterns) => match value
+        {
+            Token::Collection(items) if items.len() == patterns.len() =>
+                patterns.iter().zip(items.iter()).all(|(pattern, item)| match_pattern(pattern, item, bindings)),
+            _ => false
+        }
+    }
+}
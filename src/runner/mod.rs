@@ -1,20 +1,33 @@
 pub mod operation_runner;
 pub mod modifier_runner;
 pub mod tester;
+pub mod compiler;
+pub mod environment;
 mod token_impl;
 
 mod internal_function;
+mod external_script;
+mod lifecycle;
+mod function_value;
+mod try_catch;
+mod match_runner;
+mod vm;
 
-use crate::types::VariableModifier;
 use crate::types::ExFnRef;
+use std::any::Any;
+use std::convert::TryFrom;
 use std::collections::HashMap;
+use std::rc::Rc;
 
-use crate::{types::{Runner, Token, RegisteredFunction, RegisteredVariable, VarMap, BoxedCall}, utility, external};
+use crate::{types::{Runner, Token, Span, Statement, FunctionDef, Parameter, StructDef, RegisteredFunction, RegisteredVariable, VarMap, BoxedCall, VariableModifier, IntoDrythonFunction}, external};
 use crate::external::auto;
 use crate::types::Parser;
 use crate::types::error::*;
 
 use self::operation_runner::run_operation;
+use self::internal_function::variable_of;
+use self::environment::Environment;
+use self::try_catch::name_error;
 
 impl Runner
 {
@@ -24,13 +37,23 @@ impl Runner
         {
             parser,
             external_functions: HashMap::new(),
+            context_functions: HashMap::new(),
+            callback_functions: HashMap::new(),
+            context: None,
             vars: HashMap::new(),
             var_indexes_changed: Vec::new(),
+            external_scripts: HashMap::new(),
+            script_provider: None,
+            compiled_functions: HashMap::new(),
+            hooks: Vec::new(),
         }
     }
     
     pub fn run_setup(&mut self, error_manager: &mut ErrorManager) -> &mut Self
     {
+        // Errors are collected separately so they can be tagged with the script name.
+        let mut script_errors = ErrorManager::new();
+
         // Include base external functions and vars.
         let mut functions: Vec<RegisteredFunction> = Vec::new();
         let mut vars: Vec<RegisteredVariable> = Vec::new();
@@ -49,9 +72,11 @@ impl Runner
         }
 
         // Include libs
-        for library in &self.parser.global_expressions.includes
+        let statements = self.parser.statements.clone();
+
+        for (library, span) in statements.iter().filter_map(|x| if let Statement::Import { library, span } = x { Some((library, span)) } else { None })
         {
-            let found_lib = external::get_lib(library.0);
+            let found_lib = external::get_lib(library);
 
             match found_lib
             {
@@ -66,139 +91,331 @@ impl Runner
                     {
                         self.vars.insert(var.0, (var.1, true, vec![]));
                     }
+
+                    self.callback_functions.extend(external::get_lib_callbacks(library));
                 },
                 Err(error) =>
                 {
-                    push_error!(error_manager, RuntimeError::new(*library.1, None, error.as_str()));
+                    push_error!(script_errors, RuntimeError::new(*span, None, error.as_str()));
                 }
             }
         }
 
         // Register all variables.
-        self.parser.global_expressions.single_op.clone().iter().for_each(|x|
+        for statement in statements.iter()
+        {
+            if let Statement::Assign { name, modifiers, value, indexes, span } = statement
             {
-                let operation = run_operation(self, &x.2, &HashMap::new());
+                let operation = run_operation(self, value, *span, &VarMap::new());
 
                 match operation
                 {
                     Ok(Some(result)) =>
                     {
-                        let modifier_list = x.1.iter().map(|x| From::from(x.as_str())).collect::<Vec<VariableModifier>>();
-
-                        if let Err(error) = self.handle_variable_modifiers(x, result, modifier_list, false, None)
+                        let changed = match name.split_once('.')
                         {
-                            push_error!(error_manager,
-                                RuntimeError::new(x.3, None, error.as_str()));
+                            // Keys or indexes of a variable. (Eg. m["hp"] = 3)
+                            _ if !indexes.is_empty() => self.assign_path(name, indexes, *span, &VarMap::new()).map_err(|error| error.message)
+                                .and_then(|path| self.change_field(&mut Environment::default(), variable_of(name), &path, result)),
+                            // Field of an existing variable. (Eg. pos.x = 2)
+                            Some((base, path)) if !self.vars.contains_key(name) && self.vars.contains_key(base) =>
+                                self.change_field(&mut Environment::default(), base, &path.split('.').collect::<Vec<&str>>(), result),
+                            _ => self.handle_variable_modifiers(name, value, result, modifiers.clone(), false, None)
+                        };
+
+                        if let Err(error) = changed
+                        {
+                            push_error!(script_errors,
+                                RuntimeError::new(*span, None, error.as_str()));
                         }
                     }
                     Err(error) =>
                     {
-                        push_error!(error_manager, RuntimeError::new(x.3, None, error.as_str()));
+                        push_error!(script_errors, RuntimeError::new(error.span, None, error.message.as_str()));
                     }
                     _ => ()
                 }
-            });
+            }
+        }
+
+        self.find_hooks(&mut script_errors);
+
+        error_manager.merge_script(script_errors, &self.parser.name);
 
         self
     }
 
+    // Compiles the script's functions to bytecode, which then run on the vm.
+    // Names are still looked up as the function runs, so globals registered after compiling are found the same.
+    // Functions using anything the compiler doesn't support keep running as before.
+    pub fn compile(&mut self) -> &mut Self
+    {
+        for statement in self.parser.statements.iter()
+        {
+            if let Statement::Function(function) = statement
+            {
+                if let Ok(chunk) = compiler::compile_function(function, &self.vars)
+                {
+                    self.compiled_functions.insert(function.name.clone(), Rc::new(chunk));
+                }
+            }
+        }
+
+        self
+    }
+
+    pub fn is_compiled(&self, function_name: &str) -> bool
+    {
+        self.compiled_functions.contains_key(function_name)
+    }
+
     pub fn  call_function(&mut self, function_name: &str, args: Vec<Token>, error_manager: &mut ErrorManager) -> Option<Token>
     {
-        match self.call(function_name, args, 0)
+        match self.call(function_name, args, Span::default())
         {
             Ok(result) => {return result;}
-            Err((error, line_number)) => {push_error!(error_manager, RuntimeError::new(line_number, Some(function_name.to_string()), error.as_str())); return None;}
+            Err(error) =>
+            {
+                let mut runtime_error = error.to_runtime_error(function_name);
+                runtime_error.set_script_name(&self.parser.name);
+
+                push_error!(error_manager, runtime_error);
+                None
+            }
         }
     }
 
-    fn call(&mut self, function_name: &str, args: Vec<Token>, line_number: usize) -> Result<Option<Token>, (String, usize)>
+    // Calls a function with a context that functions registered by register_context_function are given.
+    pub fn call_function_with<T: 'static>(&mut self, function_name: &str, args: Vec<Token>, context: &mut T, error_manager: &mut ErrorManager) -> Option<Token>
+    {
+        let context: &mut dyn Any = context;
+        let previous = self.context.replace(context as *mut dyn Any);
+
+        // Put back when the call ends, even by a panic, so the pointer never outlives the borrow.
+        let guard = ContextGuard { runner: self, previous };
+        guard.runner.call_function(function_name, args, error_manager)
+    }
+
+    fn call(&mut self, function_name: &str, args: Vec<Token>, span: Span) -> Result<Option<Token>, ScriptError>
     {
         if self.external_functions.contains_key(function_name)
         {
+            check_unnamed(function_name, &args).map_err(|error| ScriptError::new(error, span))?;
+
             let function = &self.external_functions[function_name];
             if let Some(call) = &function.1
             {
                 match call(self.external_functions[function_name].0, args)
                 {
                     Ok(result) => Ok(result),
-                    Err(error) => Err((error, 0))
+                    Err(error) => Err(ScriptError::new(error, span))
                 }
             }
             else { Ok(None) }
         }
-        else
+        else if let Some(function) = self.context_functions.get(function_name)
         {
-            let index = self.parser.global_expressions.internal_expressions.iter()
-                .position(|x| 
-                    if let Some(name) = &x.0.scope_info.0
-                    {
-                        if name == function_name
-                        {
-                            true
-                        }
-                        else { false }
-                    } else { false }
-                );
+            check_unnamed(function_name, &args).map_err(|error| ScriptError::new(error, span))?;
 
-            if let Some(index) = index
+            match self.context
             {
-                self.call_internal(&index, args)
+                // Safety: The context is only set while call_function_with holds its mutable borrow.
+                Some(context) => function(unsafe { &mut *context }, args).map_err(|error| ScriptError::new(error, span)),
+                None => Err(ScriptError::new(format!("Function '{}' needs a context. Call it through call_function_with.", function_name), span))
             }
-            else
+        }
+        else if let Some(function) = self.callback_functions.get(function_name).copied()
+        {
+            check_unnamed(function_name, &args).map_err(|error| ScriptError::new(error, span))?;
+
+            // Errors in the functions it calls keep where they happened.
+            let mut inner_error = None;
+            let result = function(&mut |value, args| self.call_function_value(value, args, span).map_err(|error|
             {
-                Err((format!("No function called '{}' exists.", function_name), line_number))
+                let message = error.message.clone();
+                inner_error = Some(error);
+                message
+            }), args);
+
+            match (result, inner_error)
+            {
+                (Err(_), Some(error)) => Err(error),
+                (result, _) => result.map_err(|error| ScriptError::new(error, span))
             }
         }
+        else if let Some(chunk) = self.compiled_functions.get(function_name).cloned()
+        {
+            let args = self.bind_arguments(&chunk.name, &chunk.parameters, args, span)?;
+            let result = self.run_chunk(&chunk, args);
+            name_error(function_name, result)
+        }
+        else if let Some(function) = self.parser.find_function(function_name)
+        {
+            let args = self.bind_arguments(&function.name, &function.parameters, args, span)?;
+            let result = self.call_internal(&function, args);
+            name_error(function_name, result)
+        }
+        // Calling a struct's name builds one.
+        else if let Some(definition) = self.parser.find_struct(function_name)
+        {
+            self.construct_struct(&definition, args, span).map(Some)
+        }
+        // Function of another script through an external variable. (Eg. other.take_damage)
+        else if let Some((path, name)) = self.external_function(function_name)
+        {
+            self.call_external_script(&path, name, args, span)
+        }
+        // Global holding a function value. (Eg. on_hit = fn(x) => x * 2)
+        else if let Some((Token::Function(function), _, _)) = self.vars.get(function_name)
+        {
+            let function = function.clone();
+            self.call_function_value(&function, args, span)
+        }
+        else
+        {
+            Err(ScriptError::new(format!("No function called '{}' exists.", function_name), span))
+        }
     }
 
-    // Called by a function pointer from a registered internal function.
-    fn call_internal(&mut self, expression_index: &usize, mut arguments: Vec<Token>) -> Result<Option<Token>, (String, usize)>
+    // Builds a struct from values for its fields in order, or by name. Fields without a value are given their default.
+    // (Eg. Vec2(1, y=2))
+    fn construct_struct(&mut self, definition: &StructDef, values: Vec<Token>, span: Span) -> Result<Token, ScriptError>
     {
-        let return_result: Option<Token>;
+        let (named, mut values): (Vec<Token>, Vec<Token>) = values.into_iter().partition(|x| matches!(x, Token::Named(_, _)));
 
-        let mut scope_vars: VarMap = HashMap::new();
+        if values.len() > definition.fields.len()
+        {
+            return Err(ScriptError::new(format!("Struct '{}' has {} fields, but recieved {} values.", definition.name, definition.fields.len(), values.len()), span));
+        }
+
+        let defaults = definition.fields[values.len()..].iter()
+            .map(|(_, default)| run_operation(self, default, span, &VarMap::new()).map(|x| x.unwrap_or(Token::Null)))
+            .collect::<Result<Vec<Token>, ScriptError>>()?;
+        let given = definition.fields.len() - defaults.len();
+        values.extend(defaults);
 
-        let mut arg_vars: VarMap = HashMap::new();
+        let mut value = Token::Struct(definition.name.clone(), definition.fields.iter().map(|x| x.0.clone()).zip(values).collect());
 
-        let function = self.parser.global_expressions.internal_expressions[*expression_index].0.clone();
-        if let Some(expected_args) = &function.scope_info.1
+        for field in named
         {
-            match utility::split_by(expected_args.as_str(), ',')
+            if let Token::Named(name, field_value) = field
             {
-                Ok(parsed_arg_names) =>
+                match definition.fields.iter().position(|x| x.0 == name)
                 {
-                    if parsed_arg_names.len() == arguments.len()
-                    {
-                        arguments.reverse();
-                        arg_vars.extend(parsed_arg_names.into_iter()
-                            .map(|x| (x, (arguments.pop().unwrap(), false, vec![])))
-                        );
-                    }
-                    else
+                    Some(i) if i < given => { return Err(ScriptError::new(format!("Field '{}' of struct '{}' was given more than once.", name, definition.name), span)); }
+                    Some(_) => value.set_field(&[name.as_str()], *field_value).map_err(|error| ScriptError::new(error, span))?,
+                    None => { return Err(ScriptError::new(format!("Struct '{}' has no field called '{}'.", definition.name, name), span)); }
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    // Builds a struct declared in the script from the host, by field name. Fields that aren't given keep their default.
+    pub fn build_struct(&mut self, name: &str, fields: Vec<(&str, Token)>) -> Result<Token, String>
+    {
+        let definition = match self.parser.find_struct(name)
+        {
+            Some(definition) => definition,
+            None => { return Err(format!("No struct called '{}' exists.", name)); }
+        };
+
+        let mut value = self.construct_struct(&definition, vec![], Span::default()).map_err(|error| error.message)?;
+
+        for (field, field_value) in fields
+        {
+            value.set_field(&[field], field_value)?;
+        }
+
+        Ok(value)
+    }
+
+    // Runs a function parsed from the script, with arguments already matched to its parameters.
+    fn call_internal(&mut self, function: &FunctionDef, arguments: Vec<Token>) -> Result<Option<Token>, ScriptError>
+    {
+        let arg_vars: VarMap = function.parameters.iter().map(|x| x.name.clone())
+            .zip(arguments.into_iter().map(|x| (x, false, vec![])))
+            .collect();
+
+        // The function only sees its own variables and the globals.
+        let mut env = Environment::new(&function.name, arg_vars);
+
+        match self.handle_scope(&function.body, &mut env)?
+        {
+            // A return without a value, or a loop control that escaped the function.
+            Some(Token::Null) | Some(Token::Break) | Some(Token::Continue) => Ok(None),
+            result => Ok(result)
+        }
+    }
+    
+    // Matches the arguments of a call to the parameters, giving a value for each parameter in order.
+    // Named arguments go to the parameter with that name, missing ones are given their default,
+    // and extra ones are collected by a variadic parameter.
+    // Run before entering the function, so errors belong to the caller and point at the call.
+    pub(crate) fn bind_arguments(&mut self, function_name: &str, parameters: &[Parameter], arguments: Vec<Token>, span: Span) -> Result<Vec<Token>, ScriptError>
+    {
+        let variadic = matches!(parameters.last(), Some(last) if last.variadic);
+
+        // Nothing to match when every parameter is given in order.
+        if !variadic && parameters.len() == arguments.len() && !arguments.iter().any(|x| matches!(x, Token::Named(_, _)))
+        {
+            return Ok(arguments);
+        }
+
+        let recieved = arguments.len();
+        let positional = parameters.len() - variadic as usize;
+        let mut values: Vec<Option<Token>> = vec![None; positional];
+        let mut rest: Vec<Token> = Vec::new();
+        let mut next = 0;
+
+        for argument in arguments
+        {
+            match argument
+            {
+                Token::Named(name, value) => match parameters[..positional].iter().position(|x| x.name == name)
+                {
+                    Some(i) if values[i].is_some() =>
                     {
-                        return Err((format!("Expected {} arguments, but only recieved {}.", parsed_arg_names.len(), arguments.len()), function.line_start));
-                    }
+                        return Err(ScriptError::new(format!("Argument '{}' was given more than once, for {}.", name, signature(function_name, parameters)), span));
+                    },
+                    Some(i) => { values[i] = Some(*value); }
+                    None => { return Err(ScriptError::new(format!("{} has no parameter called '{}'.", signature(function_name, parameters), name), span)); }
+                },
+                value if next < positional =>
+                {
+                    values[next] = Some(value);
+                    next += 1;
+                },
+                value if variadic => rest.push(value),
+                _ =>
+                {
+                    return Err(ScriptError::new(format!("Too many arguments for {}. Expected at most {}, but recieved {}.", signature(function_name, parameters), positional, recieved), span));
                 }
-                Err(error) => {return Err((error, function.line_start));}
             }
         }
-        scope_vars.extend(self.vars.clone());
-        scope_vars.extend(arg_vars);
 
-        match self.handle_scope(&function, &mut scope_vars, false)
+        let mut result: Vec<Token> = Vec::with_capacity(parameters.len());
+
+        for (parameter, value) in parameters.iter().zip(values)
         {
-            Ok(result) =>
+            match (value, &parameter.default)
             {
-                return_result = result;
+                (Some(value), _) => result.push(value),
+                (None, Some(default)) => result.push(run_operation(self, default, span, &VarMap::new())?.unwrap_or(Token::Null)),
+                (None, None) => { return Err(ScriptError::new(format!("Missing argument '{}' for {}.", parameter.name, signature(function_name, parameters)), span)); }
             }
-            Err(error) => {return Err(error);}
         }
 
-        self.vars = scope_vars;
+        if variadic
+        {
+            result.push(Token::Collection(rest));
+        }
 
-        Ok(return_result)
+        Ok(result)
     }
-    
+
+    #[deprecated(note = "Use register_function with a closure that captures its state, or register_context_function.")]
     pub fn register_external_function(&mut self, function_name: &str,
         optional_identifier: Option<*mut dyn ExFnRef>, function: BoxedCall) -> &mut Self
     {
@@ -207,6 +424,42 @@ impl Runner
         self
     }
 
+    // Registers a host function. State is captured by the closure. (Eg. an Rc<RefCell<T>> or Arc<Mutex<T>>)
+    pub fn register_function<F>(&mut self, function_name: &str, function: F) -> &mut Self
+        where F: Fn(Vec<Token>) -> Result<Option<Token>, String> + 'static
+    {
+        self.external_functions.insert(function_name.to_string(), (None, Some(Box::new(move |_, args| function(args)))));
+
+        self
+    }
+
+    // Registers a Rust function or closure, converting the arguments and result to and from tokens.
+    // Wrong argument counts and types become runtime errors naming the function and argument.
+    pub fn bind_function<Args, F>(&mut self, function_name: &str, function: F) -> &mut Self
+        where F: IntoDrythonFunction<Args>
+    {
+        self.external_functions.insert(function_name.to_string(), (None, Some(function.into_function(function_name))));
+
+        self
+    }
+
+    // Registers a host function given the context passed to call_function_with.
+    // Calling it without a context of the same type is a runtime error.
+    pub fn register_context_function<T, F>(&mut self, function_name: &str, function: F) -> &mut Self
+        where T: 'static, F: Fn(&mut T, Vec<Token>) -> Result<Option<Token>, String> + 'static
+    {
+        let name = function_name.to_string();
+
+        self.context_functions.insert(function_name.to_string(), Box::new(move |context, args|
+            match context.downcast_mut::<T>()
+            {
+                Some(context) => function(context, args),
+                None => Err(format!("Function '{}' needs a context of type '{}'.", name, std::any::type_name::<T>()))
+            }));
+
+        self
+    }
+
     pub fn register_library(&mut self, mut additional_library: (Vec<RegisteredFunction>, Vec<RegisteredVariable>)) -> &mut Self
     {
         while let Some(function) = additional_library.0.pop()
@@ -236,15 +489,26 @@ impl Runner
         self
     }
     
-    pub fn update_variable<T>(&mut self, external_var: (&str, &mut T)) -> & mut Self
-        where T: From<Token>
+    // Copies a changed variable back to the host's value. The value is left as it was if the conversion fails.
+    pub fn update_variable<T>(&mut self, external_var: (&str, &mut T)) -> &mut Self
+        where T: TryFrom<Token>, T::Error: std::fmt::Display
+    {
+        let _ = self.try_update_variable(external_var);
+
+        self
+    }
+
+    // Same as update_variable, but fails if the variable can't be converted to the host's type.
+    pub fn try_update_variable<T>(&mut self, external_var: (&str, &mut T)) -> Result<&mut Self, String>
+        where T: TryFrom<Token>, T::Error: std::fmt::Display
     {
         if self.var_indexes_changed.iter().any(|x| x == external_var.0)
         {
-            *external_var.1 = From::from(self.vars[external_var.0].0.clone());
+            *external_var.1 = T::try_from(self.vars[external_var.0].0.clone())
+                .map_err(|error| format!("Variable '{}': {}", external_var.0, error))?;
         }
 
-        self
+        Ok(self)
     }
 
     pub fn update_variable_conversion<T>(&mut self, external_var: (&str, &mut T), conversion_function: fn(&Token) -> T) -> &mut Self
@@ -257,5 +521,78 @@ impl Runner
         self
     }
 
+    // Variables the script marked as public, in the order they were defined. (Eg. pub!speed = 2.5)
+    pub fn public_variables(&self) -> impl Iterator<Item = (&str, &Token)>
+    {
+        self.parser.statements.iter().filter_map(move |statement|
+            match statement
+            {
+                Statement::Assign { name, .. } => match self.vars.get(name)
+                {
+                    Some((value, _, modifiers)) if modifiers.contains(&VariableModifier::Public) => Some((name.as_str(), value)),
+                    _ => None
+                },
+                _ => None
+            })
+    }
+
+    // None if the variable isn't public or doesn't hold a T.
+    pub fn get_public<T>(&self, name: &str) -> Option<T>
+        where T: TryFrom<Token>
+    {
+        match self.vars.get(name)
+        {
+            Some((value, _, modifiers)) if modifiers.contains(&VariableModifier::Public) => T::try_from(value.clone()).ok(),
+            _ => None
+        }
+    }
+
+    // Changes a public variable from the host. The value has to keep the variable's type.
+    pub fn set_public<T>(&mut self, name: &str, value: T) -> Result<(), String>
+        where T: Into<Token>
+    {
+        let value: Token = value.into();
+
+        match self.vars.get_mut(name)
+        {
+            Some((_, _, modifiers)) if !modifiers.contains(&VariableModifier::Public) => Err(format!("Variable '{}' isn't public.", name)),
+            Some((_, _, modifiers)) if modifiers.contains(&VariableModifier::Const) => Err(format!("Cannot assign to '{}', as it's a constant.", name)),
+            Some((current, _, _)) if std::mem::discriminant(current) != std::mem::discriminant(&value) => Err(format!("Expected {} for '{}'. Found: {}", current.type_name(), name, value.type_name())),
+            Some((current, _, _)) => { *current = value; Ok(()) }
+            None => Err(format!("Could not find a variable by the name: {}", name))
+        }
+    }
+
     
 }
+
+// Restores the context a call to call_function_with replaced.
+struct ContextGuard<'a>
+{
+    runner: &'a mut Runner,
+    previous: Option<*mut dyn Any>,
+}
+
+impl Drop for ContextGuard<'_>
+{
+    fn drop(&mut self)
+    {
+        self.runner.context = self.previous;
+    }
+}
+
+// How a function is declared, for errors about its arguments. (Eg. spawn(kind, count=1, *tags))
+pub(crate) fn signature(function_name: &str, parameters: &[Parameter]) -> String
+{
+    format!("{}({})", function_name, parameters.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", "))
+}
+
+// Host functions only take their arguments in order.
+pub(crate) fn check_unnamed(function_name: &str, arguments: &[Token]) -> Result<(), String>
+{
+    match arguments.iter().find_map(|x| if let Token::Named(name, _) = x { Some(name) } else { None })
+    {
+        Some(name) => Err(format!("Function '{}' doesn't take named arguments, but was given '{}'.", function_name, name)),
+        None => Ok(())
+    }
+}
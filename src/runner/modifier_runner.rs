@@ -1,13 +1,14 @@
 use crate::runner::VarMap;
 use crate::runner::Token;
-use crate::runner::VariableModifier;
+use crate::types::VariableModifier;
 use crate::runner::Runner;
-use crate::types::SingleOp;
+use crate::types::Operation;
 
 impl Runner
 {
     pub fn handle_variable_modifiers(&mut self,
-        variable: &SingleOp,
+        name: &str,
+        value: &Operation,
         operation_result: Token,
         modifier_list: Vec<VariableModifier>,
         is_scope: bool,
@@ -17,7 +18,7 @@ impl Runner
         {
             if let VariableModifier::Unkown(m) = modifier
             {
-                return Err(format!("Unkown modifier '{}' on variable '{}'.", m, variable.0));
+                return Err(format!("Unkown modifier '{}' on variable '{}'.", m, name));
             }
             else if is_scope && !modifier.check_scope_allowed()
             {
@@ -25,26 +26,32 @@ impl Runner
             }
         }
 
+        // Globals are defined by assigning them again, which shouldn't replace a constant.
+        if !is_scope && self.vars.get(name).is_some_and(|x| x.2.contains(&VariableModifier::Const))
+        {
+            return Err(format!("Cannot assign to '{}', as it's a constant.", name));
+        }
+
         if modifier_list.contains(&VariableModifier::Alias)
         {
-            match &variable.2[0]
+            match &value[0]
             {
-                Token::Var(_) | Token::Call(_, _) =>
+                Token::Var(_) | Token::Call(_, _, _) =>
                 {
                     if is_scope
                     {
-                        scope_var_map.unwrap().insert(variable.0.to_string(), (variable.2[0].clone(), false, vec![VariableModifier::Alias]));
+                        scope_var_map.unwrap().insert(name.to_string(), (value[0].clone(), false, vec![VariableModifier::Alias]));
                         return Ok(());
                     }
                     else
                     {
-                        self.vars.insert(variable.0.to_string(), (variable.2[0].clone(), false, vec![VariableModifier::Alias]));
+                        self.vars.insert(name.to_string(), (value[0].clone(), false, vec![VariableModifier::Alias]));
                         return Ok(());
                     }
                 }
                 _ =>
                 {
-                    return Err(format!("Alias modifier expects a reference to another variable/function. Found: '{}'", variable.2[0]));
+                    return Err(format!("Alias modifier expects a reference to another variable/function. Found: '{}'", value[0]));
                 }
             }
         }
@@ -52,31 +59,31 @@ impl Runner
         // External script reference.
         if modifier_list.contains(&VariableModifier::External)
         {
-            if let Token::String(script_path) = &operation_result
+            if let Token::String(_) = &operation_result
             {
                 if is_scope
                 {
-                    scope_var_map.unwrap().insert(variable.0.to_string(), (Token::, false, modifier_list));
+                    scope_var_map.unwrap().insert(name.to_string(), (operation_result, false, modifier_list));
                 }
                 else
                 {
-                    self.vars.insert(variable.0.to_string(), (operation_result, false, modifier_list));
+                    self.vars.insert(name.to_string(), (operation_result, false, modifier_list));
                 }
                 return Ok(());
             }
             else
             {
-                return Err(format!("External variables require a string path."));
+                return Err(format!("External variable '{}' requires a string path to a script.", name));
             }
         }
 
         if is_scope
         {
-            scope_var_map.unwrap().insert(variable.0.to_string(), (operation_result, false, modifier_list));
+            scope_var_map.unwrap().insert(name.to_string(), (operation_result, false, modifier_list));
         }
         else
         {
-            self.vars.insert(variable.0.to_string(), (operation_result, false, modifier_list));
+            self.vars.insert(name.to_string(), (operation_result, false, modifier_list));
         }
 
         Ok(())
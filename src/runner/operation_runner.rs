@@ -1,8 +1,38 @@
-use crate::{types::{Token, Runner, VarMap}, parser::operation_parser, utility};
+use std::convert::TryFrom;
+
+use linked_hash_map::LinkedHashMap;
+
+use crate::types::{Token, Span, Runner, VarMap, Object, Function};
+use crate::types::error::ScriptError;
+use crate::utility;
+use crate::external::format;
+
+use super::function_value::capture;
+
+// Variables local to whatever is running the operation.
+// Names that aren't found locally are looked up in the runner's globals.
+pub trait LocalVars
+{
+    fn get_local(&self, name: &str) -> Option<&Token>;
+}
+
+impl LocalVars for VarMap
+{
+    fn get_local(&self, name: &str) -> Option<&Token>
+    {
+        self.get(name).map(|x| &x.0)
+    }
+}
+
+fn find_var(runner: &Runner, vars: &dyn LocalVars, name: &str) -> Option<Token>
+{
+    vars.get_local(name).or_else(|| runner.vars.get(name).map(|x| &x.0)).cloned()
+}
 
 // recursive function that runs the operation from the reverse polish notation.
-pub fn run_operation(runner: &mut Runner, operations: &Vec<Token>,
-    vars: &VarMap) -> Result<Option<Token>, String>
+// Errors point at the innermost operation or call they happened in, which is the span given for this one.
+pub fn run_operation(runner: &mut Runner, operations: &[Token], span: Span,
+    vars: &dyn LocalVars) -> Result<Option<Token>, ScriptError>
 {
     let mut stack: Vec<Token> = vec![];
 
@@ -10,13 +40,24 @@ pub fn run_operation(runner: &mut Runner, operations: &Vec<Token>,
     {
         if let Token::Operator(operator) = &operations[i]
         {
+            if utility::is_unary(operator)
+            {
+                if let Some(unhandled) = stack.pop()
+                {
+                    let value = handle_token_type(runner, unhandled.clone(), span, vars, false)?.unwrap_or(unhandled);
+
+                    stack.push(run_unary_operation(&value, operator).map_err(|error| ScriptError::new(error, span))?);
+                }
+                continue;
+            }
+
             let second = stack.pop();
             let first = stack.pop();
 
             if let (Some(unhandled1), Some(unhandled2)) = (first, second)
             {
-                let handled_1 = handle_token_type(runner, unhandled1.clone(), vars, false);
-                let handled_2 = handle_token_type(runner, unhandled2.clone(), vars, false);
+                let handled_1 = handle_token_type(runner, unhandled1.clone(), span, vars, false);
+                let handled_2 = handle_token_type(runner, unhandled2.clone(), span, vars, false);
 
                 if let Err(error) = handled_1
                 {
@@ -29,20 +70,21 @@ pub fn run_operation(runner: &mut Runner, operations: &Vec<Token>,
 
                 // Run operations based on whether the token has been handled or not.
                 // Unwrap because errors automatically return from this function before.
-                let ran_operation = match (handled_1, handled_2)
+                let (token1, token2) = match (handled_1, handled_2)
                 {
-                    (Ok(Some(token1)), Ok(Some(token2))) => run_operation_by_type(&token1, &token2, operator),
-                    (Ok(None), Ok(Some(token2))) => run_operation_by_type(&unhandled1, &token2, operator),
-                    (Ok(Some(token1)), Ok(None)) => run_operation_by_type(&token1, &unhandled2, operator),
-                    _ => run_operation_by_type(&unhandled1, &unhandled2, operator)
+                    (Ok(Some(token1)), Ok(Some(token2))) => (token1, token2),
+                    (Ok(None), Ok(Some(token2))) => (unhandled1.clone(), token2),
+                    (Ok(Some(token1)), Ok(None)) => (token1, unhandled2.clone()),
+                    _ => (unhandled1.clone(), unhandled2.clone())
                 };
-                match ran_operation
+                match run_operation_by_type(&token1, &token2, operator)
                 {
                     Some(result) =>
                     {
                         stack.push(result);
                     }
-                    None => { return Err(format!("Cannot apply operation '{}' to '{}' and '{}'.", operator, unhandled1, unhandled2)); }
+                    None => { return Err(ScriptError::new(operation_error(&token1, &token2, operator)
+                                    .unwrap_or_else(|| format!("Cannot apply operation '{}' to '{}' and '{}'.", operator, token1, token2)), span)); }
                 }
             }
         }
@@ -56,7 +98,7 @@ pub fn run_operation(runner: &mut Runner, operations: &Vec<Token>,
     if let Some(token) = stack.pop()
     {
         // Secondary handle in case it was a single token and not a full operation.
-        match handle_token_type(runner, token.clone(), vars, false)
+        match handle_token_type(runner, token.clone(), span, vars, false)
         {
             Ok(Some(result)) =>
             {
@@ -78,84 +120,81 @@ pub fn run_operation(runner: &mut Runner, operations: &Vec<Token>,
     }
     else
     {
-            Err("Failed to parse operation. Try breaking down the statement into steps.".to_string())
+            Err(ScriptError::new("Failed to parse operation. Try breaking down the statement into steps.".to_string(), span))
     }
 }
 
-fn handle_token_type(runner: &mut Runner, token: Token, vars: &VarMap, return_original: bool) -> Result<Option<Token>, String>
+// The span is of the operation the token is in, for errors that don't have a closer location.
+pub(crate) fn handle_token_type(runner: &mut Runner, token: Token, span: Span, vars: &dyn LocalVars, return_original: bool) -> Result<Option<Token>, ScriptError>
 {
-    // Check for chain of vars first.
+    // Check for chain of vars first. (Unless the first is a variable, like a map.)
     if let Token::Accessor(p, a) = &token {
-    if let Token::Var(_) = **p { if let Token::Accessor(_, _) = **a {
+    if let Token::Var(name) = &**p { if let Token::Accessor(_, _) = **a {
+    if find_var(runner, vars, name).is_none() {
     if let Some(Ok(Some(result))) = check_var_chain(&token)
     {
-        return handle_token_type(runner, result, vars, false);
+        return handle_token_type(runner, result, span, vars, false);
     }
-    }}}
+    }}}}
 
     match &token
     {
-        Token::Call(name, args) =>
+        Token::Call(name, args, call_span) =>
         {
-            // Parse arguments and run them recursively.
+            // Run the already parsed arguments.
             let mut parsed_args: Vec<Token> = Vec::new();
 
-            match utility::split_by(&args, ',')
+            for arg in args
             {
-                Ok(result) =>
+                match run_operation(runner, arg, *call_span, vars)
                 {
-                    for arg in result
-                    {
-                        match operation_parser::parse_operation(&arg)
-                        {
-                            Ok(operation) =>
-                            {
-                                if let Ok(Some(ran_token)) = 
-                                    run_operation(runner, &operation, vars)
-                                {
-                                    parsed_args.push(ran_token);
-                                }
-                            }
-                            Err(error) => {return Err(error);}
-                        }
-                    }
-                }
-                Err(error) =>
-                {
-                    return Err(error);
+                    Ok(Some(ran_token)) => parsed_args.push(ran_token),
+                    Ok(None) => (),
+                    Err(error) => {return Err(error);}
                 }
             }
             
-            let call_result = runner.call(&name, parsed_args, 0);
+            // Errors keep the span they were given in the function, instead of pointing at the call.
+            let call_result = runner.call_with_locals(name, parsed_args, *call_span, vars);
             
             match call_result
             {
-                Ok(None) =>
-                {
-                    return Ok(Some(token.clone()));
-                }
-                Ok(Some(result)) =>
-                {
-                    return Ok(Some(result));
-                }
-                Err(error) => {return Err(error.0);}
+                Ok(None) => Ok(Some(token.clone())),
+                Ok(Some(result)) => Ok(Some(result)),
+                Err(error) => Err(error)
+            }
+        },
+        Token::Format(template, values, format_span) =>
+        {
+            let mut ran_values: Vec<Token> = Vec::new();
+
+            for value in values
+            {
+                ran_values.push(run_operation(runner, value, *format_span, vars)?.unwrap_or(Token::Null));
             }
+
+            format::format_values(template, &ran_values).map(|x| Some(Token::String(x))).map_err(|error| ScriptError::new(error, *format_span))
         },
-        Token::Operation(op) =>
+        // The value is run here, and the name is left for the function being called.
+        Token::Named(name, value) =>
+        {
+            let value = handle_token_type(runner, *value.clone(), span, vars, true)?.unwrap_or(Token::Null);
+            Ok(Some(Token::Named(name.clone(), Box::new(value))))
+        },
+        Token::Operation(op, op_span) =>
         {
             // Run operation recursively.
-            return run_operation(runner, &op, vars);
+            run_operation(runner, op, *op_span, vars)
         },
         Token::Var(name) =>
         {
             // Deal with any accessors.
-            if vars.contains_key(name)
-            {
-                return check_alias_chain(runner, &vars[name].0, vars);
-            }
-            else
+            match find_var(runner, vars, name)
             {
-                return Err(format!("Could not find a variable by the name: {}", name));
+                Some(value) => check_alias_chain(runner, &value, span, vars),
+                // Functions can be used as values by name. (Eg. apply(double, 3))
+                None if runner.has_function(name) => Ok(Some(Token::Function(Function::Named(name.clone())))),
+                None => Err(ScriptError::new(format!("Could not find a variable by the name: {}", name), span))
             }
         },
         Token::Collection(items) =>
@@ -164,27 +203,77 @@ fn handle_token_type(runner: &mut Runner, token: Token, vars: &VarMap, return_or
 
             for item in items
             {
-                if let Ok(Some(token_result)) = handle_token_type(runner, item.clone(), vars, false)
+                let item = match handle_token_type(runner, item.clone(), span, vars, false)?
                 {
-                    new_items.push(token_result);
-                }
-                else
+                    Some(result) => result,
+                    None => item.clone()
+                };
+
+                new_items.push(item);
+            }
+
+            Ok(Some(Token::Collection(new_items)))
+        },
+        Token::Map(map) =>
+        {
+            let mut new_map: LinkedHashMap<String, Token> = LinkedHashMap::new();
+
+            for (key, value) in map.iter()
+            {
+                let value = match handle_token_type(runner, value.clone(), span, vars, false)?
                 {
-                    new_items.push(item.clone());
-                }
+                    Some(result) => result,
+                    None => value.clone()
+                };
+
+                new_map.insert(key.clone(), value);
             }
 
-            return Ok(Some(Token::Collection(new_items)));
+            Ok(Some(Token::Map(new_map)))
         },
+        // Fields are already run when the struct is built.
+        Token::Struct(_, _) | Token::Object(_) => Ok(Some(token.clone())),
+        Token::Function(Function::Lambda { definition, captures: None }) => Ok(Some(capture(definition, vars))),
+        Token::Function(_) => Ok(Some(token.clone())),
         Token::Accessor(prev_token, accessor) =>
         {
-            match (handle_token_type(runner, *prev_token.clone(), vars, false), handle_token_type(runner, *accessor.clone(), vars, true))
+            // Variables and functions of another script. (Eg. other.hp or other.take_damage(3))
+            if let Token::Var(name) = &**prev_token
+            {
+                if let Some(path) = runner.external_path(name).filter(|_| vars.get_local(name).is_none()).map(|x| x.to_string())
+                {
+                    return access_external(runner, name, &path, accessor, span, vars);
+                }
+            }
+
+            let prev = handle_token_type(runner, *prev_token.clone(), span, vars, false);
+
+            // Maps and structs find their keys by name, instead of looking for a variable.
+            let found = match &prev
+            {
+                Ok(Some(Token::Map(map))) => access_map(runner, map, accessor, span, vars),
+                Ok(Some(Token::Struct(name, fields))) => access_struct(runner, name, fields, accessor, span, vars),
+                Ok(Some(Token::Object(object))) => access_object(runner, object, accessor, span, vars),
+                _ => None
+            };
+
+            if let Some(result) = found
+            {
+                return result;
+            }
+
+            match (prev, handle_token_type(runner, *accessor.clone(), span, vars, true))
             {
                 // Collection.Int -> index
                 (Ok(Some(Token::Collection(collection))), Ok(Some(Token::Int(i)))) =>
                 {
-                    let token = &collection[i as usize];
-                    if let Ok(Some(result)) = handle_token_type(runner, token.clone(), vars, false)
+                    let token = match usize::try_from(i).ok().and_then(|i| collection.get(i))
+                    {
+                        Some(token) => token,
+                        None => { return Err(ScriptError::new("Tried to access a collection index out of range.".to_string(), span)); }
+                    };
+
+                    if let Ok(Some(result)) = handle_token_type(runner, token.clone(), span, vars, false)
                     {
                         Ok(Some(result))
                     }
@@ -196,22 +285,20 @@ fn handle_token_type(runner: &mut Runner, token: Token, vars: &VarMap, return_or
                 // String.String -> variable name
                 (Ok(Some(Token::String(value))), Ok(Some(Token::String(accessor_str)))) =>
                 {
-                    handle_token_type(runner, Token::Var(format!("{}.{}", value, accessor_str.clone())), vars, false)
+                    handle_token_type(runner, Token::Var(format!("{}.{}", value, accessor_str.clone())), span, vars, false)
                 },
                 // String.Call() -> call with longer name
-                (Ok(Some(Token::String(value))), Ok(Some(Token::Call(name, args)))) =>
+                (Ok(Some(Token::String(value))), Ok(Some(Token::Call(name, args, call_span)))) =>
                 {
-                    handle_token_type(runner, Token::Call(format!("{}.{}", value, name), args), vars, false)
+                    handle_token_type(runner, Token::Call(format!("{}.{}", value, name), args, call_span), span, vars, false)
                 },
                 (Ok(Some(Token::String(value))), Ok(Some(Token::Int(i)))) =>
                 {
-                    if (i as usize) < value.len() && i >= 0
+                    // Indexes count characters, not bytes.
+                    match usize::try_from(i).ok().and_then(|i| value.chars().nth(i))
                     {
-                        Ok(Some(Token::String(value.as_str()[(i as usize)..((i+1) as usize)].to_string())))
-                    }
-                    else
-                    {
-                        Err("Tried to access a string index out of range.".to_string())
+                        Some(c) => Ok(Some(Token::String(c.to_string()))),
+                        None => Err(ScriptError::new("Tried to access a string index out of range.".to_string(), span))
                     }
                 },
                 (Err(error), Ok(Some(Token::Var(var2)))) =>
@@ -219,7 +306,7 @@ fn handle_token_type(runner: &mut Runner, token: Token, vars: &VarMap, return_or
                     if let Token::Var(var1) = &**prev_token
                     {
                         let var = Token::Var(format!("{}.{}", var1, var2));
-                        handle_token_type(runner, var, vars, false)
+                        handle_token_type(runner, var, span, vars, false)
                     }
                     else
                     {
@@ -231,11 +318,15 @@ fn handle_token_type(runner: &mut Runner, token: Token, vars: &VarMap, return_or
                 {
                     match &**accessor
                     {
-                        Token::Call(name, args) =>
+                        Token::Call(name, args, call_span) =>
                         {
                             if let Ok(Some(actual_prev)) = &prev
                             {
-                                if let Ok(result) = handle_token_type(runner, Token::Call(name.to_string(), format!("{},{}", actual_prev, args)), vars, return_original)
+                                // The accessed value becomes the first argument.
+                                let mut args_with_prev = vec![vec![actual_prev.clone()]];
+                                args_with_prev.extend(args.iter().cloned());
+
+                                if let Ok(result) = handle_token_type(runner, Token::Call(name.to_string(), args_with_prev, *call_span), span, vars, return_original)
                                 {
                                     return Ok(result);
                                 }
@@ -247,7 +338,7 @@ fn handle_token_type(runner: &mut Runner, token: Token, vars: &VarMap, return_or
                             if let Token::Var(var1) = &**prev_token
                             {
                                 let var = Token::Var(format!("{}.{}", var1, var2));
-                                if let Err(_) = handle_token_type(runner, var.clone(), vars, false)
+                                if handle_token_type(runner, var.clone(), span, vars, false).is_err()
                                 {
                                     return Ok(Some(var));
                                 }
@@ -269,28 +360,165 @@ fn handle_token_type(runner: &mut Runner, token: Token, vars: &VarMap, return_or
     }
 }
 
-fn check_alias_chain(runner: &mut Runner, token: &Token, vars: &VarMap) -> Result<Option<Token>, String>
+pub(crate) fn check_alias_chain(runner: &mut Runner, token: &Token, span: Span, vars: &dyn LocalVars) -> Result<Option<Token>, ScriptError>
 {
     if let Token::Var(name) = token
     {
-        if vars.contains_key(name)
+        match find_var(runner, vars, name)
         {
             // Check for recursive aliases.
-            return check_alias_chain(runner, &vars[name].0.clone(), vars);
-        }
-        else
-        {
-            return Err(format!("Could not find a variable by the name: {}", name));
+            Some(value) => { return check_alias_chain(runner, &value, span, vars); }
+            None => { return Err(ScriptError::new(format!("Could not find a variable by the name: {}", name), span)); }
         }
     }
-    if let Token::Call(_, _) = token
+    if let Token::Call(_, _, _) = token
     {
-        return handle_token_type(runner, token.clone(), &vars, false);
+        return handle_token_type(runner, token.clone(), span, vars, false);
     }
     
     Ok(Some(token.clone()))
 }
 
+// Accesses the script an external variable refers to.
+// Anything accessed on the variable or function's result is handled as usual. (Eg. other.pos.x)
+fn access_external(runner: &mut Runner, name: &str, path: &str, accessor: &Token, span: Span, vars: &dyn LocalVars) -> Result<Option<Token>, ScriptError>
+{
+    match accessor
+    {
+        Token::Var(variable) => runner.get_external_variable(path, variable).map(Some).map_err(|error| ScriptError::new(error, span)),
+        // Called by its full name, which the runner finds the script for.
+        Token::Call(function_name, args, call_span) =>
+        {
+            handle_token_type(runner, Token::Call(format!("{}.{}", name, function_name), args.clone(), *call_span), span, vars, false)
+        },
+        Token::Accessor(first, rest) =>
+        {
+            let value = access_external(runner, name, path, first, span, vars)?.unwrap_or(Token::Null);
+            handle_token_type(runner, Token::Accessor(Box::new(value), rest.clone()), span, vars, false)
+        },
+        _ => Err(ScriptError::new(format!("Cannot access '{}' on the external script '{}'.", accessor, path), span))
+    }
+}
+
+// Gets a value from a map by name (m.hp), or by the result of an index (m["hp"]).
+// Anything else accessed on the map, like a call, is left to the usual accessor handling.
+fn access_map(runner: &mut Runner, map: &LinkedHashMap<String, Token>, accessor: &Token, span: Span, vars: &dyn LocalVars) -> Option<Result<Option<Token>, ScriptError>>
+{
+    // Further accessors are run on the found value. (Eg. m.stats.hp)
+    let (key_token, rest) = match accessor
+    {
+        Token::Accessor(key, rest) => (&**key, Some(rest)),
+        key => (key, None)
+    };
+
+    let key = match key_token
+    {
+        Token::Var(name) => name.clone(),
+        Token::Operation(op, op_span) =>
+        {
+            match run_operation(runner, op, *op_span, vars)
+            {
+                Ok(Some(Token::String(key))) => key,
+                Ok(Some(other)) => { return Some(Err(ScriptError::new(format!("Map keys need to be strings. Found: '{}'", other), *op_span))); }
+                Ok(None) => { return None; }
+                Err(error) => { return Some(Err(error)); }
+            }
+        },
+        _ => { return None; }
+    };
+
+    let value = match map.get(&key)
+    {
+        Some(value) => value.clone(),
+        None => { return Some(Err(ScriptError::new(format!("The map has no key '{}'.", key), span))); }
+    };
+
+    match rest
+    {
+        Some(rest) => match &value
+        {
+            Token::Map(inner) => access_map(runner, inner, rest, span, vars),
+            _ => Some(handle_token_type(runner, Token::Accessor(Box::new(value), rest.clone()), span, vars, false))
+        },
+        None => Some(Ok(Some(value)))
+    }
+}
+
+// Gets a field of a struct by name. (Eg. pos.x)
+// Anything else accessed on the struct, like a call, is left to the usual accessor handling.
+fn access_struct(runner: &mut Runner, name: &str, fields: &LinkedHashMap<String, Token>, accessor: &Token, span: Span, vars: &dyn LocalVars) -> Option<Result<Option<Token>, ScriptError>>
+{
+    let (field, rest) = match accessor
+    {
+        Token::Accessor(field, rest) => (&**field, Some(rest)),
+        field => (field, None)
+    };
+
+    let value = match field
+    {
+        Token::Var(field) => match fields.get(field)
+        {
+            Some(value) => value.clone(),
+            None => { return Some(Err(ScriptError::new(format!("Struct '{}' has no field '{}'.", name, field), span))); }
+        },
+        _ => { return None; }
+    };
+
+    match rest
+    {
+        Some(rest) => Some(handle_token_type(runner, Token::Accessor(Box::new(value), rest.clone()), span, vars, false)),
+        None => Some(Ok(Some(value)))
+    }
+}
+
+// Gets a property (player.hp) or calls a method (player.move(1, 0)) of a host object.
+fn access_object(runner: &mut Runner, object: &Object, accessor: &Token, span: Span, vars: &dyn LocalVars) -> Option<Result<Option<Token>, ScriptError>>
+{
+    let (member, rest) = match accessor
+    {
+        Token::Accessor(member, rest) => (&**member, Some(rest)),
+        member => (member, None)
+    };
+
+    let value = match member
+    {
+        Token::Var(property) => object.get(property).map_err(|error| ScriptError::new(error, span)),
+        Token::Call(method, args, call_span) =>
+        {
+            let mut parsed_args: Vec<Token> = Vec::new();
+
+            for arg in args
+            {
+                match run_operation(runner, arg, *call_span, vars)
+                {
+                    Ok(Some(ran_token)) => parsed_args.push(ran_token),
+                    Ok(None) => (),
+                    Err(error) => { return Some(Err(error)); }
+                }
+            }
+
+            object.call_method(method, parsed_args).map(|x| x.unwrap_or(Token::Null)).map_err(|error| ScriptError::new(error, *call_span))
+        },
+        _ => { return None; }
+    };
+
+    match (value, rest)
+    {
+        (Ok(value), Some(rest)) => Some(handle_token_type(runner, Token::Accessor(Box::new(value), rest.clone()), span, vars, false)),
+        (value, _) => Some(value.map(Some))
+    }
+}
+
+// Accessor for a call by a dotted name, as the parser would build it. (Eg. player.move(1, 0))
+pub(crate) fn call_accessor(name: &str, arguments: &[Vec<Token>], span: Span) -> Token
+{
+    match name.split_once('.')
+    {
+        Some((first, rest)) => Token::Accessor(Box::new(Token::Var(first.to_string())), Box::new(call_accessor(rest, arguments, span))),
+        None => Token::Call(name.to_string(), arguments.to_vec(), span)
+    }
+}
+
 fn check_var_chain(token: &Token) -> Option<Result<Option<Token>, String>>
 {
     let mut var = String::new();
@@ -302,7 +530,7 @@ fn check_var_chain(token: &Token) -> Option<Result<Option<Token>, String>>
     else { None }
 }
 
-fn check_var_chain_recursive(token: &Token, built_string: &mut String) -> Result<i32, ()>
+fn check_var_chain_recursive(token: &Token, built_string: &mut String) -> Result<i64, ()>
 {
     match token
     {
@@ -334,8 +562,62 @@ fn check_var_chain_recursive(token: &Token, built_string: &mut String) -> Result
     }
 }
 
+pub(crate) fn run_unary_operation(value: &Token, operation: &str) -> Result<Token, String>
+{
+    let result = match operation
+    {
+        "u-" => value.negate(),
+        "u+" => value.positive(),
+        "!" => value.not(),
+        _ => None
+    };
+
+    match (result, value)
+    {
+        (Some(result), _) => Ok(result),
+        // Applied to every item, so the error is of the first item it failed for.
+        (None, Token::Collection(items)) => Err(items.iter().find_map(|x| run_unary_operation(x, operation).err())
+                                            .unwrap_or_else(|| format!("Cannot apply operation '{}' to '{}'.", operation.trim_start_matches('u'), value))),
+        (None, Token::Int(_)) if operation == "u-" => Err(format!("Integer overflow in '-({})'.", value)),
+        (None, _) => Err(format!("Cannot apply operation '{}' to '{}'.", operation.trim_start_matches('u'), value))
+    }
+}
+
+// Why an operation between values that support it still failed.
+// None if the values don't support the operation at all.
+pub(crate) fn operation_error(a: &Token, b: &Token, operation: &str) -> Option<String>
+{
+    let is_number = |x: &Token| matches!(x, Token::Int(_) | Token::Float(_) | Token::Bool(_));
+
+    // Operations on collections are applied to each item, so the error is of the first items it failed for.
+    let item_error = |x: &Token, y: &Token| match run_operation_by_type(x, y, operation)
+    {
+        Some(_) => None,
+        None => operation_error(x, y, operation)
+    };
+
+    match (a, b)
+    {
+        (Token::Collection(x), Token::Collection(y)) if x.len() != y.len() && matches!(operation, "+" | "-" | "*" | "/" | "%" | "^" | "&&" | "||") =>
+            Some(format!("Cannot apply operation '{}' to collections of different lengths. ({} and {})", operation, x.len(), y.len())),
+        (Token::Collection(x), Token::Collection(y)) => x.iter().zip(y.iter()).find_map(|(x, y)| item_error(x, y)),
+        (Token::Collection(x), _) => x.iter().find_map(|x| item_error(x, b)),
+        // A value raised to a collection is raised to each item, while other operations apply each item to the value.
+        (_, Token::Collection(y)) if operation == "^" => y.iter().find_map(|y| item_error(a, y)),
+        (_, Token::Collection(y)) => y.iter().find_map(|y| item_error(y, a)),
+        _ => match operation
+        {
+            "/" if is_number(a) && b.is_zero() => Some("Division by zero.".to_string()),
+            "%" if is_number(a) && b.is_zero() => Some("Modulo by zero.".to_string()),
+            "+" | "-" | "*" | "/" | "%" | "^" if matches!((a, b), (Token::Int(_) | Token::Bool(_), Token::Int(_) | Token::Bool(_))) =>
+                Some(format!("Integer overflow in '{} {} {}'.", a, operation, b)),
+            _ => None
+        }
+    }
+}
+
 // Handles the various operations and conversions.
-fn run_operation_by_type(a: &Token, b: &Token, operation: &str) -> Option<Token>
+pub(crate) fn run_operation_by_type(a: &Token, b: &Token, operation: &str) -> Option<Token>
 {
     match operation
     {
@@ -345,6 +627,7 @@ fn run_operation_by_type(a: &Token, b: &Token, operation: &str) -> Option<Token>
         "*" => a.multiply(b),
         "/" => a.divide(b),
         "%" => a.modulos(b),
+        "^" => a.power(b),
 
         "||" => a.or(b),
         "&&" => a.and(b),
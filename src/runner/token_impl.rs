@@ -1,5 +1,6 @@
-use crate::types::Token;
-use std::{convert::From, fmt::{self, Display}};
+use crate::types::{Token, Object, Function};
+use std::{convert::{From, TryFrom}, fmt::{self, Display}, collections::HashMap};
+use linked_hash_map::LinkedHashMap;
 
 // Match arms for most functions.
 macro_rules! TI
@@ -23,40 +24,31 @@ macro_rules! TIS
     };
 }
 
+// Applies the operation to every item. Fails if it fails for any item.
 macro_rules! CollectionApply
 {
     ($apply: ident, $name: ident) =>
     {
-        fn $name(collection: &Vec<Token>, other_token: &Token) -> Token
+        fn $name(collection: &[Token], other_token: &Token) -> Option<Token>
         {
-            Token::Collection(collection.iter().map(|x| x.$apply(other_token).unwrap_or(Token::Null)).collect())
+            collection.iter().map(|x| x.$apply(other_token)).collect::<Option<Vec<Token>>>().map(Token::Collection)
         }
     };
 }
 
-
+// Applies the operation to the items at the same index. Fails for collections of different lengths.
 macro_rules! CollectionApplyTogether
 {
     ($apply: ident, $name: ident) =>
     {
-        fn $name(collection: &Vec<Token>, other: &Vec<Token>) -> Token
+        fn $name(collection: &[Token], other: &[Token]) -> Option<Token>
         {
-            if collection.len() == other.len()
+            if collection.len() != other.len()
             {
-                let mut new_collection: Vec<Token> = Vec::new();
-
-                for i in 0..collection.len()
-                {
-                    if let Some(applied) = collection[i].$apply(&other[i])
-                    {
-                        new_collection.push(applied);
-                    }
-                }
-
-                return Token::Collection(new_collection);
+                return None;
             }
 
-            Token::Null
+            collection.iter().zip(other.iter()).map(|(x, y)| x.$apply(y)).collect::<Option<Vec<Token>>>().map(Token::Collection)
         }
     };
 }
@@ -64,6 +56,100 @@ macro_rules! CollectionApplyTogether
 #[allow(unused_variables)]
 impl Token
 {
+    // Builds a struct value from the host. (Eg. Token::new_struct("Vec2", vec![("x", 1), ("y", 2)]))
+    // Runner::build_struct fills in the defaults of a struct declared in a script instead.
+    pub fn new_struct<K, V>(name: &str, fields: Vec<(K, V)>) -> Token
+        where K: Into<String>, V: Into<Token>
+    {
+        Token::Struct(name.to_string(), fields.into_iter().map(|(key, value)| (key.into(), value.into())).collect())
+    }
+
+    // Value of a struct's field, or a map's key.
+    pub fn get_field(&self, name: &str) -> Option<&Token>
+    {
+        match self
+        {
+            Token::Struct(_, fields) | Token::Map(fields) => fields.get(name),
+            _ => None
+        }
+    }
+
+    // Changes a value nested in structs, maps or collections by the path of field names. (Eg. pos.x)
+    // Structs only have the fields they were declared with, but maps get new keys.
+    pub fn set_field<S: AsRef<str>>(&mut self, path: &[S], value: Token) -> Result<(), String>
+    {
+        let (field, rest) = match path.split_first()
+        {
+            Some((field, rest)) => (field.as_ref(), rest),
+            None => { *self = value; return Ok(()); }
+        };
+
+        let inner = match self
+        {
+            Token::Struct(name, fields) => match fields.get_mut(field)
+            {
+                Some(inner) => inner,
+                None => { return Err(format!("Struct '{}' has no field '{}'.", name, field)); }
+            },
+            // Changed keys keep their place, instead of moving to the end.
+            Token::Map(map) if rest.is_empty() => match map.get_mut(field)
+            {
+                Some(slot) => { *slot = value; return Ok(()); }
+                None => { map.insert(field.to_string(), value); return Ok(()); }
+            },
+            Token::Map(map) => match map.get_mut(field)
+            {
+                Some(inner) => inner,
+                None => { return Err(format!("The map has no key '{}'.", field)); }
+            },
+            Token::Collection(items) => match field.parse::<usize>().ok().and_then(move |i| items.get_mut(i))
+            {
+                Some(inner) => inner,
+                None => { return Err(format!("Tried to set '{}' of a collection, which isn't an index in range.", field)); }
+            },
+            // Objects are shared, so their properties are set in place.
+            Token::Object(object) if rest.is_empty() => { return object.set(field, value); }
+            Token::Object(object) =>
+            {
+                let mut property = object.get(field)?;
+                property.set_field(rest, value)?;
+
+                return object.set(field, property);
+            },
+            other => { return Err(format!("Cannot set '{}' on '{}'.", field, other)); }
+        };
+
+        inner.set_field(rest, value)
+    }
+
+    // Name of the variant, for errors.
+    pub fn type_name(&self) -> &'static str
+    {
+        match self
+        {
+            Token::Null => "Null",
+            Token::Int(_) => "Int",
+            Token::Float(_) => "Float",
+            Token::Bool(_) => "Bool",
+            Token::String(_) => "String",
+            Token::Char(_) => "Char",
+            Token::Collection(_) => "Collection",
+            Token::Map(_) => "Map",
+            Token::Struct(_, _) => "Struct",
+            Token::Object(_) => "Object",
+            Token::Function(_) => "Function",
+            Token::Var(_) => "Var",
+            Token::Call(_, _, _) => "Call",
+            Token::Format(_, _, _) => "Format",
+            Token::Named(_, _) => "Named",
+            Token::Operation(_, _) => "Operation",
+            Token::Operator(_) => "Operator",
+            Token::Accessor(_, _) => "Accessor",
+            Token::Break => "Break",
+            Token::Continue => "Continue"
+        }
+    }
+
     // Only check the variant and not the value.
     pub fn variant_equal(&self, other: &Token) -> bool
     {
@@ -93,6 +179,11 @@ impl Token
 
                 all_equal
             }
+            // Keys can be added and removed freely.
+            (Token::Map(_), Token::Map(_)) => true,
+            (Token::Struct(a, _), Token::Struct(b, _)) => a == b,
+            (Token::Object(a), Token::Object(b)) => a.type_name() == b.type_name(),
+            (Token::Function(_), Token::Function(_)) => true,
             _ => false
         }
     }
@@ -101,18 +192,18 @@ impl Token
     {
         match (self, other)
         {
-            TIS!(Int, a, b) => Some(Token::Int(a + b)),
-            TIS!(Float, a, b) => Some(Token::Float(*a as f32 + b)),
-            TIS!(Bool, a, b) => Some(Token::Int(*a as i32 + *b as i32)),
+            TIS!(Int, a, b) => a.checked_add(*b).map(Token::Int),
+            TIS!(Float, a, b) => Some(Token::Float(a + b)),
+            TIS!(Bool, a, b) => Some(Token::Int(*a as i64 + *b as i64)),
             TIS!(Char, a, b) => Some(Token::String(format!("{}{}", a, b))),
             TIS!(String, a, b) => Some(Token::String(format!("{}{}", a, b))),
 
             TI!(Int, Float, a, b) =>
-                Some(Token::Float(*a as f32 + b)),
+                Some(Token::Float(*a as f64 + b)),
             TI!(Int, Bool, a, b) =>
-                Some(Token::Int(a + *b as i32)),
+                a.checked_add(*b as i64).map(Token::Int),
             TI!(Float, Bool, a, b) =>
-                Some(Token::Float(a + (*b as i32) as f32)),
+                Some(Token::Float(a + (*b as i64) as f64)),
 
             TI!(String, Int, a, b) => Some(Token::String(format!("{}{}", a, b))),
             TI!(String, Float, a, b) => Some(Token::String(format!("{}{}", a, b))),
@@ -123,19 +214,19 @@ impl Token
             TI!(Char, Float, a, b) => Some(Token::String(format!("{}{}", a, b))),
             TI!(Char, Bool, a, b) => Some(Token::String(format!("{}{}", a, b))),
 
-            TIS!(Collection, Int, a, b) => Some(Token::collection_add(a, other)),
-            TIS!(Int, Collection, a, b) => Some(Token::collection_add(b, self)),
+            TIS!(Collection, Int, a, b) => Token::collection_add(a, other),
+            TIS!(Int, Collection, a, b) => Token::collection_add(b, self),
 
-            TIS!(Collection, Float, a, b) => Some(Token::collection_add(a, other)),
-            TIS!(Float, Collection, a, b) => Some(Token::collection_add(b, self)),
+            TIS!(Collection, Float, a, b) => Token::collection_add(a, other),
+            TIS!(Float, Collection, a, b) => Token::collection_add(b, self),
 
-            TIS!(Collection, String, a, b) => Some(Token::collection_add(a, other)),
-            TIS!(String, Collection, a, b) => Some(Token::collection_add(b, self)),
+            TIS!(Collection, String, a, b) => Token::collection_add(a, other),
+            TIS!(String, Collection, a, b) => Token::collection_add(b, self),
 
-            TIS!(Collection, Bool, a, b) => Some(Token::collection_add(a, other)),
-            TIS!(Bool, Collection, a, b) => Some(Token::collection_add(b, self)),
+            TIS!(Collection, Bool, a, b) => Token::collection_add(a, other),
+            TIS!(Bool, Collection, a, b) => Token::collection_add(b, self),
 
-            TIS!(Collection, a, b) => Some(Token::collection_add_together(a, b)),
+            TIS!(Collection, a, b) => Token::collection_add_together(a, b),
             _ => None
         }
     }
@@ -147,26 +238,26 @@ impl Token
     {
         match (self, other)
         {
-            TIS!(Int, a, b) => Some(Token::Int(a - b)),
+            TIS!(Int, a, b) => a.checked_sub(*b).map(Token::Int),
             TIS!(Float, a, b) => Some(Token::Float(a - b)),
-            TIS!(Bool, a, b) => Some(Token::Int(*a as i32 - *b as i32)),
+            TIS!(Bool, a, b) => Some(Token::Int(*a as i64 - *b as i64)),
 
-            TIS!(Int, Float, a, b) => Some(Token::Float(*a as f32 - b)),
-            TIS!(Float, Int, a, b) => Some(Token::Float(a - *b as f32)),
+            TIS!(Int, Float, a, b) => Some(Token::Float(*a as f64 - b)),
+            TIS!(Float, Int, a, b) => Some(Token::Float(a - *b as f64)),
             
-            TIS!(Int, Bool, a, b) => Some(Token::Int(a - *b as i32)),
-            TIS!(Bool, Int, a, b) => Some(Token::Int(*a as i32 - b)),
+            TIS!(Int, Bool, a, b) => a.checked_sub(*b as i64).map(Token::Int),
+            TIS!(Bool, Int, a, b) => (*a as i64).checked_sub(*b).map(Token::Int),
 
-            TIS!(Float, Bool, a, b) => Some(Token::Float(a - (*b as i32) as f32)),
-            TIS!(Bool, Float, a, b) => Some(Token::Float((*a as i32) as f32 - b)),
+            TIS!(Float, Bool, a, b) => Some(Token::Float(a - (*b as i64) as f64)),
+            TIS!(Bool, Float, a, b) => Some(Token::Float((*a as i64) as f64 - b)),
             
-            TIS!(Collection, Int, a, b) => Some(Token::collection_subtract(a, other)),
-            TIS!(Int, Collection, a, b) => Some(Token::collection_subtract(b, self)),
+            TIS!(Collection, Int, a, b) => Token::collection_subtract(a, other),
+            TIS!(Int, Collection, a, b) => Token::collection_subtract(b, self),
 
-            TIS!(Collection, Float, a, b) => Some(Token::collection_subtract(a, other)),
-            TIS!(Float, Collection, a, b) => Some(Token::collection_subtract(b, self)),
+            TIS!(Collection, Float, a, b) => Token::collection_subtract(a, other),
+            TIS!(Float, Collection, a, b) => Token::collection_subtract(b, self),
 
-            TIS!(Collection, a, b) => Some(Token::collection_subtract_together(a, b)),
+            TIS!(Collection, a, b) => Token::collection_subtract_together(a, b),
             _ => None
         }
     }
@@ -178,21 +269,21 @@ impl Token
     {
         match (self, other)
         {
-            TIS!(Int, a, b) => Some(Token::Int(a * b)),
-            TIS!(Float, a, b) => Some(Token::Float(*a as f32 * b)),
-            TIS!(Bool, a, b) => Some(Token::Int(*a as i32 * *b as i32)),
+            TIS!(Int, a, b) => a.checked_mul(*b).map(Token::Int),
+            TIS!(Float, a, b) => Some(Token::Float(a * b)),
+            TIS!(Bool, a, b) => Some(Token::Int(*a as i64 * *b as i64)),
 
-            TI!(Int, Float, a, b) => Some(Token::Float(*a as f32 * b)),
-            TI!(Int, Bool, a, b) => Some(Token::Int(a * *b as i32)),
-            TI!(Float, Bool, a, b) => Some(Token::Float(a * (*b as i32) as f32)),
+            TI!(Int, Float, a, b) => Some(Token::Float(*a as f64 * b)),
+            TI!(Int, Bool, a, b) => Some(Token::Int(a * *b as i64)),
+            TI!(Float, Bool, a, b) => Some(Token::Float(a * (*b as i64) as f64)),
             
-            TIS!(Collection, Int, a, b) => Some(Token::collection_multiply(a, other)),
-            TIS!(Int, Collection, a, b) => Some(Token::collection_multiply(b, self)),
+            TIS!(Collection, Int, a, b) => Token::collection_multiply(a, other),
+            TIS!(Int, Collection, a, b) => Token::collection_multiply(b, self),
 
-            TIS!(Collection, Float, a, b) => Some(Token::collection_multiply(a, other)),
-            TIS!(Float, Collection, a, b) => Some(Token::collection_multiply(b, self)),
+            TIS!(Collection, Float, a, b) => Token::collection_multiply(a, other),
+            TIS!(Float, Collection, a, b) => Token::collection_multiply(b, self),
 
-            TIS!(Collection, a, b) => Some(Token::collection_multiply_together(a, b)),
+            TIS!(Collection, a, b) => Token::collection_multiply_together(a, b),
             _ => None
         }
     }
@@ -200,30 +291,36 @@ impl Token
     CollectionApply!(multiply, collection_multiply);
     CollectionApplyTogether!(multiply, collection_multiply_together);
 
+    // Dividing by zero fails the operation, for floats as well as ints.
     pub fn divide(&self, other: &Token) -> Option<Token>
     {
+        if other.is_zero()
+        {
+            return None;
+        }
+
         match (self, other)
         {
-            TIS!(Int, a, b) => Some(Token::Int(a / b)),
-            TIS!(Float, a, b) => Some(Token::Float(*a as f32 / b)),
-            TIS!(Bool, a, b) => Some(Token::Int(*a as i32 / *b as i32)),
+            TIS!(Int, a, b) => a.checked_div(*b).map(Token::Int),
+            TIS!(Float, a, b) => Some(Token::Float(a / b)),
+            TIS!(Bool, a, b) => Some(Token::Int(*a as i64 / *b as i64)),
 
-            TIS!(Int, Float, a, b) => Some(Token::Float(*a as f32 / b)),
-            TIS!(Float, Int, a, b) => Some(Token::Float(a / *b as f32)),
+            TIS!(Int, Float, a, b) => Some(Token::Float(*a as f64 / b)),
+            TIS!(Float, Int, a, b) => Some(Token::Float(a / *b as f64)),
             
-            TIS!(Int, Bool, a, b) => Some(Token::Int(a / *b as i32)),
-            TIS!(Bool, Int, a, b) => Some(Token::Int(*a as i32 / b)),
+            TIS!(Int, Bool, a, b) => Some(Token::Int(a / *b as i64)),
+            TIS!(Bool, Int, a, b) => (*a as i64).checked_div(*b).map(Token::Int),
 
-            TIS!(Float, Bool, a, b) => Some(Token::Float(a / (*b as i32) as f32)),
-            TIS!(Bool, Float, a, b) => Some(Token::Float((*a as i32) as f32 / b)),
+            TIS!(Float, Bool, a, b) => Some(Token::Float(a / (*b as i64) as f64)),
+            TIS!(Bool, Float, a, b) => Some(Token::Float((*a as i64) as f64 / b)),
            
-            TIS!(Collection, Int, a, b) => Some(Token::collection_divide(a, other)),
-            TIS!(Int, Collection, a, b) => Some(Token::collection_divide(b, self)),
+            TIS!(Collection, Int, a, b) => Token::collection_divide(a, other),
+            TIS!(Int, Collection, a, b) => Token::collection_divide(b, self),
 
-            TIS!(Collection, Float, a, b) => Some(Token::collection_divide(a, other)),
-            TIS!(Float, Collection, a, b) => Some(Token::collection_divide(b, self)),
+            TIS!(Collection, Float, a, b) => Token::collection_divide(a, other),
+            TIS!(Float, Collection, a, b) => Token::collection_divide(b, self),
 
-            TIS!(Collection, a, b) => Some(Token::collection_divide_together(a, b)),
+            TIS!(Collection, a, b) => Token::collection_divide_together(a, b),
             _ => None
         }
     }
@@ -233,35 +330,84 @@ impl Token
 
     pub fn modulos(&self, other: &Token) -> Option<Token>
     {
+        if other.is_zero()
+        {
+            return None;
+        }
+
         match (self, other)
         {
-            TIS!(Int, a, b) => Some(Token::Int(a % b)),
-            TIS!(Float, a, b) => Some(Token::Float(*a as f32 % b)),
-            TIS!(Bool, a, b) => Some(Token::Int(*a as i32 % *b as i32)),
+            TIS!(Int, a, b) => a.checked_rem(*b).map(Token::Int),
+            TIS!(Float, a, b) => Some(Token::Float(a % b)),
+            TIS!(Bool, a, b) => Some(Token::Int(*a as i64 % *b as i64)),
 
-            TIS!(Int, Float, a, b) => Some(Token::Float(*a as f32 % b)),
-            TIS!(Float, Int, a, b) => Some(Token::Float(a % *b as f32)),
+            TIS!(Int, Float, a, b) => Some(Token::Float(*a as f64 % b)),
+            TIS!(Float, Int, a, b) => Some(Token::Float(a % *b as f64)),
             
-            TIS!(Int, Bool, a, b) => Some(Token::Int(a % *b as i32)),
-            TIS!(Bool, Int, a, b) => Some(Token::Int(*a as i32 % b)),
+            TIS!(Int, Bool, a, b) => Some(Token::Int(a % *b as i64)),
+            TIS!(Bool, Int, a, b) => (*a as i64).checked_rem(*b).map(Token::Int),
 
-            TIS!(Float, Bool, a, b) => Some(Token::Float(a % (*b as i32) as f32)),
-            TIS!(Bool, Float, a, b) => Some(Token::Float((*a as i32) as f32 % b)),
+            TIS!(Float, Bool, a, b) => Some(Token::Float(a % (*b as i64) as f64)),
+            TIS!(Bool, Float, a, b) => Some(Token::Float((*a as i64) as f64 % b)),
             
-            TIS!(Collection, Int, a, b) => Some(Token::collection_modulos(a, other)),
-            TIS!(Int, Collection, a, b) => Some(Token::collection_modulos(b, self)),
+            TIS!(Collection, Int, a, b) => Token::collection_modulos(a, other),
+            TIS!(Int, Collection, a, b) => Token::collection_modulos(b, self),
 
-            TIS!(Collection, Float, a, b) => Some(Token::collection_modulos(a, other)),
-            TIS!(Float, Collection, a, b) => Some(Token::collection_modulos(b, self)),
+            TIS!(Collection, Float, a, b) => Token::collection_modulos(a, other),
+            TIS!(Float, Collection, a, b) => Token::collection_modulos(b, self),
 
-            TIS!(Collection, a, b) => Some(Token::collection_modulos_together(a, b)),
+            TIS!(Collection, a, b) => Token::collection_modulos_together(a, b),
             _ => None
         }
     }
 
+    // Zero as a divisor.
+    pub(crate) fn is_zero(&self) -> bool
+    {
+        match self
+        {
+            Token::Int(a) => *a == 0,
+            Token::Float(a) => *a == 0.0,
+            Token::Bool(a) => !*a,
+            _ => false
+        }
+    }
+
     CollectionApply!(modulos, collection_modulos);
     CollectionApplyTogether!(modulos, collection_modulos_together);
 
+    pub fn power(&self, other: &Token) -> Option<Token>
+    {
+        match (self, other)
+        {
+            // Negative exponents can't stay whole, and overflowing results fail the operation.
+            TIS!(Int, a, b) if *b < 0 => Some(Token::Float((*a as f64).powf(*b as f64))),
+            TIS!(Int, a, b) => u32::try_from(*b).ok().and_then(|b| a.checked_pow(b)).map(Token::Int),
+            TIS!(Float, a, b) => Some(Token::Float(a.powf(*b))),
+
+            TIS!(Int, Float, a, b) => Some(Token::Float((*a as f64).powf(*b))),
+            TIS!(Float, Int, a, b) => Some(Token::Float(a.powf(*b as f64))),
+
+            TIS!(Collection, Int, a, b) => Token::collection_power(a, other),
+            TIS!(Collection, Float, a, b) => Token::collection_power(a, other),
+
+            // Order matters, so the value is raised to each item instead of the other way round.
+            TIS!(Int, Collection, a, b) => Token::power_collection(&Token::Int(*a), b),
+            TIS!(Float, Collection, a, b) => Token::power_collection(&Token::Float(*a), b),
+
+            TIS!(Collection, a, b) => Token::collection_power_together(a, b),
+            _ => None
+        }
+    }
+
+    CollectionApply!(power, collection_power);
+    CollectionApplyTogether!(power, collection_power_together);
+
+    fn power_collection(base: &Token, collection: &[Token]) -> Option<Token>
+    {
+        collection.iter().map(|x| base.power(x)).collect::<Option<Vec<Token>>>().map(Token::Collection)
+    }
+
     pub fn and(&self, other: &Token) -> Option<Token>
     {
         match (self, other)
@@ -276,16 +422,16 @@ impl Token
 
             TI!(Float, Bool, a, b) => Some(Token::Bool(*a != 0.0 && *b)),
             
-            TIS!(Collection, Int, a, b) => Some(Token::collection_and(a, other)),
-            TIS!(Int, Collection, a, b) => Some(Token::collection_and(b, self)),
+            TIS!(Collection, Int, a, b) => Token::collection_and(a, other),
+            TIS!(Int, Collection, a, b) => Token::collection_and(b, self),
 
-            TIS!(Collection, Float, a, b) => Some(Token::collection_and(a, other)),
-            TIS!(Float, Collection, a, b) => Some(Token::collection_and(b, self)),
+            TIS!(Collection, Float, a, b) => Token::collection_and(a, other),
+            TIS!(Float, Collection, a, b) => Token::collection_and(b, self),
 
-            TIS!(Collection, Bool, a, b) => Some(Token::collection_and(a, other)),
-            TIS!(Bool, Collection, a, b) => Some(Token::collection_and(b, self)),
+            TIS!(Collection, Bool, a, b) => Token::collection_and(a, other),
+            TIS!(Bool, Collection, a, b) => Token::collection_and(b, self),
 
-            TIS!(Collection, a, b) => Some(Token::collection_and_together(a, b)),
+            TIS!(Collection, a, b) => Token::collection_and_together(a, b),
             _ => None
         }
     }
@@ -307,16 +453,16 @@ impl Token
 
             TI!(Bool, Float, a, b) => Some(Token::Bool(*a || *b != 0.0)),
             
-            TIS!(Collection, Int, a, b) => Some(Token::collection_or(a, other)),
-            TIS!(Int, Collection, a, b) => Some(Token::collection_or(b, self)),
+            TIS!(Collection, Int, a, b) => Token::collection_or(a, other),
+            TIS!(Int, Collection, a, b) => Token::collection_or(b, self),
 
-            TIS!(Collection, Float, a, b) => Some(Token::collection_or(a, other)),
-            TIS!(Float, Collection, a, b) => Some(Token::collection_or(b, self)),
+            TIS!(Collection, Float, a, b) => Token::collection_or(a, other),
+            TIS!(Float, Collection, a, b) => Token::collection_or(b, self),
 
-            TIS!(Collection, Bool, a, b) => Some(Token::collection_or(a, other)),
-            TIS!(Bool, Collection, a, b) => Some(Token::collection_or(b, self)),
+            TIS!(Collection, Bool, a, b) => Token::collection_or(a, other),
+            TIS!(Bool, Collection, a, b) => Token::collection_or(b, self),
 
-            TIS!(Collection, a, b) => Some(Token::collection_or_together(a, b)),
+            TIS!(Collection, a, b) => Token::collection_or_together(a, b),
             _ => None
         }
     }
@@ -324,6 +470,44 @@ impl Token
     CollectionApply!(or, collection_or);
     CollectionApplyTogether!(or, collection_or_together);
 
+    pub fn negate(&self) -> Option<Token>
+    {
+        match self
+        {
+            Token::Int(a) => a.checked_neg().map(Token::Int),
+            Token::Float(a) => Some(Token::Float(-a)),
+
+            Token::Collection(a) => a.iter().map(|x| x.negate()).collect::<Option<Vec<Token>>>().map(Token::Collection),
+            _ => None
+        }
+    }
+
+    // Unary plus only allows numbers through.
+    pub fn positive(&self) -> Option<Token>
+    {
+        match self
+        {
+            Token::Int(_) | Token::Float(_) => Some(self.clone()),
+
+            Token::Collection(a) => a.iter().map(|x| x.positive()).collect::<Option<Vec<Token>>>().map(Token::Collection),
+            _ => None
+        }
+    }
+
+    pub fn not(&self) -> Option<Token>
+    {
+        match self
+        {
+            Token::Int(_) | Token::Float(_) | Token::Bool(_) =>
+            {
+                if let Some(Token::Bool(a)) = self.check() { Some(Token::Bool(!a)) } else { None }
+            },
+
+            Token::Collection(a) => a.iter().map(|x| x.not()).collect::<Option<Vec<Token>>>().map(Token::Collection),
+            _ => None
+        }
+    }
+
     pub fn check(&self) -> Option<Token>
     {
         match self
@@ -332,7 +516,7 @@ impl Token
             Token::Float(a) => Some(Token::Bool(*a != 0.0)),
             Token::Bool(a) => Some(Token::Bool(*a)),
             
-            Token::Collection(a) => Some(Token::Collection(a.iter().map(|x| x.check().unwrap_or(Token::Null)).collect())),
+            Token::Collection(a) => a.iter().map(|x| x.check()).collect::<Option<Vec<Token>>>().map(Token::Collection),
             _ => None
         }
     }
@@ -347,7 +531,7 @@ impl Token
             TIS!(String, a, b) => Some(Token::Bool(a == b)),
             TIS!(Char, a, b) => Some(Token::Bool(a == b)),
 
-            TI!(Int, Float, a, b) => Some(Token::Bool((*a as f32) == *b)),
+            TI!(Int, Float, a, b) => Some(Token::Bool((*a as f64) == *b)),
             TI!(Int, Char, a, b) => Some(Token::Bool(a.to_string() == b.to_string())),
             TI!(Int, Bool, a, b) => Some(Token::Bool((*a != 0) == *b)),
             TI!(Float, Bool, a, b) => Some(Token::Bool((*a != 0.0) == *b)),
@@ -370,10 +554,23 @@ impl Token
             TIS!(Char, Collection, a, b) => Some(Token::collection_eq(b, self)),
 
             TIS!(Collection, a, b) => Some(Token::collection_eq_together(a, b)),
+            TIS!(Map, a, b) => Some(Token::map_eq(a, b)),
+            // Structs of different types are never equal, even with the same fields.
+            (Token::Struct(name_a, a), Token::Struct(name_b, b)) =>
+                Some(if name_a == name_b { Token::map_eq(a, b) } else { Token::Bool(false) }),
+            TIS!(Object, a, b) => Some(Token::Bool(a.same(b))),
+            TIS!(Function, a, b) => Some(Token::Bool(a.same(b))),
             _ => None
         }
     }
 
+    // Maps are equal with the same keys and equal values, in any order.
+    fn map_eq(map: &LinkedHashMap<String, Token>, other: &LinkedHashMap<String, Token>) -> Token
+    {
+        Token::Bool(map.len() == other.len() && map.iter().all(|(key, value)|
+                                            matches!(other.get(key).and_then(|x| value.compare_eq(x)), Some(Token::Bool(true)))))
+    }
+
     fn collection_eq(collection: &Vec<Token>, other_token: &Token) -> Token
     {
         Token::Bool(collection.iter().all(|x| 
@@ -430,7 +627,7 @@ impl Token
         {
             TIS!(Int, a, b) => Some(Token::Bool(a >= b)),
             TIS!(Float, a, b) => Some(Token::Bool(a >= b)),
-            TI!(Int, Float, a, b) => Some(Token::Bool((*a as f32) >= *b)),
+            TI!(Int, Float, a, b) => Some(Token::Bool((*a as f64) >= *b)),
             
             TIS!(Collection, Int, a, b) => Some(Token::collection_gte(a, other)),
             TIS!(Int, Collection, a, b) => Some(Token::collection_gte(b, self)),
@@ -469,7 +666,7 @@ impl Token
         {
             TIS!(Int, a, b) => Some(Token::Bool(a > b)),
             TIS!(Float, a, b) => Some(Token::Bool(a > b)),
-            TI!(Int, Float, a, b) => Some(Token::Bool((*a as f32) > *b)),
+            TI!(Int, Float, a, b) => Some(Token::Bool((*a as f64) > *b)),
             
             TIS!(Collection, Int, a, b) => Some(Token::collection_gt(a, other)),
             TIS!(Int, Collection, a, b) => Some(Token::collection_gt(b, self)),
@@ -517,39 +714,233 @@ macro_rules! FromToToken
     };
 }
 
+// Smaller numbers are widened to fit.
+macro_rules! WidenToToken
+{
+    ($t: ty, $wide: ty, $token_type: tt) =>
+    {
+        impl From<$t> for Token
+        {
+            fn from(value: $t) -> Self
+            {
+                Token::$token_type(value as $wide)
+            }
+        }
+    };
+}
+
 // From other types
-FromToToken!(i32, Int);
-FromToToken!(f32, Float);
+FromToToken!(i64, Int);
+FromToToken!(f64, Float);
 FromToToken!(String, String);
 FromToToken!(char, Char);
 FromToToken!(bool, Bool);
 FromToToken!(Vec<Token>, Collection);
+FromToToken!(Object, Object);
+FromToToken!(Function, Function);
+
+WidenToToken!(i32, i64, Int);
+WidenToToken!(i16, i64, Int);
+WidenToToken!(i8, i64, Int);
+WidenToToken!(u32, i64, Int);
+WidenToToken!(u16, i64, Int);
+WidenToToken!(u8, i64, Int);
+WidenToToken!(f32, f64, Float);
+
+impl<T> From<HashMap<String, T>> for Token
+    where T: Into<Token>
+{
+    fn from(value: HashMap<String, T>) -> Self
+    {
+        Token::Map(value.into_iter().map(|(key, value)| (key, value.into())).collect())
+    }
+}
 
 macro_rules! TokenToOther
 {
-    ($token_type: tt, $t: ty, $default: expr) =>
+    ($token_type: tt, $t: ty) =>
     {
-        impl From<Token> for $t
+        impl TryFrom<Token> for $t
         {
-            fn from(value: Token) -> Self
+            type Error = String;
+
+            fn try_from(value: Token) -> Result<Self, Self::Error>
             {
                 match value
                 {
-                    Token::$token_type(i) => i,
-                    _ => $default
+                    Token::$token_type(i) => Ok(i),
+                    other => Err(mismatched_type(stringify!($token_type), &other))
                 }
             }
         }
     };
 }
 
+// Integers of other sizes, failing if the value doesn't fit.
+macro_rules! TokenToInteger
+{
+    ($t: ty) =>
+    {
+        impl TryFrom<Token> for $t
+        {
+            type Error = String;
+
+            fn try_from(value: Token) -> Result<Self, Self::Error>
+            {
+                match value
+                {
+                    Token::Int(i) => <$t>::try_from(i).map_err(|_| format!("{} doesn't fit in a {}.", i, stringify!($t))),
+                    other => Err(mismatched_type("Int", &other))
+                }
+            }
+        }
+    };
+}
+
+// Borrowed tokens convert the same as owned ones.
+macro_rules! RefTokenToOther
+{
+    ($t: ty) =>
+    {
+        impl TryFrom<&Token> for $t
+        {
+            type Error = String;
+
+            fn try_from(value: &Token) -> Result<Self, Self::Error>
+            {
+                <$t>::try_from(value.clone())
+            }
+        }
+    };
+}
+
 // Into other types
-TokenToOther!(Int, i32, 0);
-TokenToOther!(Float, f32, 0.0);
-TokenToOther!(String, String, String::new());
-TokenToOther!(Char, char, ' ');
-TokenToOther!(Bool, bool, false);
-TokenToOther!(Collection, Vec<Token>, vec![]);
+TokenToOther!(Int, i64);
+TokenToOther!(String, String);
+TokenToOther!(Char, char);
+TokenToOther!(Bool, bool);
+TokenToOther!(Object, Object);
+TokenToOther!(Function, Function);
+
+TokenToInteger!(i32);
+TokenToInteger!(i8);
+TokenToInteger!(i16);
+TokenToInteger!(u8);
+TokenToInteger!(u16);
+TokenToInteger!(u32);
+TokenToInteger!(u64);
+TokenToInteger!(usize);
+
+RefTokenToOther!(i32);
+RefTokenToOther!(i64);
+RefTokenToOther!(f32);
+RefTokenToOther!(f64);
+RefTokenToOther!(String);
+RefTokenToOther!(char);
+RefTokenToOther!(bool);
+RefTokenToOther!(Object);
+RefTokenToOther!(Function);
+RefTokenToOther!(i8);
+RefTokenToOther!(i16);
+RefTokenToOther!(u8);
+RefTokenToOther!(u16);
+RefTokenToOther!(u32);
+RefTokenToOther!(u64);
+RefTokenToOther!(usize);
+
+// Floats and ints convert when the value is held exactly, and fail instead of rounding.
+macro_rules! TokenToFloat
+{
+    ($t: ty) =>
+    {
+        impl TryFrom<Token> for $t
+        {
+            type Error = String;
+
+            fn try_from(value: Token) -> Result<Self, Self::Error>
+            {
+                match value
+                {
+                    Token::Float(f) if f.is_nan() || (f as $t) as f64 == f => Ok(f as $t),
+                    // Compared wider than an i64, as converting back would saturate.
+                    Token::Int(i) if (i as $t) as i128 == i as i128 => Ok(i as $t),
+                    Token::Float(f) => Err(format!("{} can't be converted to a {} without losing precision.", f, stringify!($t))),
+                    Token::Int(i) => Err(format!("{} can't be converted to a {} without losing precision.", i, stringify!($t))),
+                    other => Err(mismatched_type("Float", &other))
+                }
+            }
+        }
+    };
+}
+
+TokenToFloat!(f32);
+TokenToFloat!(f64);
+
+// Every item has to convert. (Eg. a Vec<f32> from a collection of floats)
+impl<T> TryFrom<Token> for Vec<T>
+    where T: TryFrom<Token>, T::Error: Display
+{
+    type Error = String;
+
+    fn try_from(value: Token) -> Result<Self, Self::Error>
+    {
+        match value
+        {
+            Token::Collection(items) => items.into_iter().enumerate()
+                .map(|(i, item)| T::try_from(item).map_err(|error| format!("Item {} of the collection: {}", i, error)))
+                .collect(),
+            other => Err(mismatched_type("Collection", &other))
+        }
+    }
+}
+
+impl<T> TryFrom<&Token> for Vec<T>
+    where T: TryFrom<Token>, T::Error: Display
+{
+    type Error = String;
+
+    fn try_from(value: &Token) -> Result<Self, Self::Error>
+    {
+        Vec::try_from(value.clone())
+    }
+}
+
+impl<T> TryFrom<Token> for HashMap<String, T>
+    where T: TryFrom<Token>, T::Error: Display
+{
+    type Error = String;
+
+    fn try_from(value: Token) -> Result<Self, Self::Error>
+    {
+        match value
+        {
+            Token::Map(map) => map.into_iter()
+                .map(|(key, value)| match T::try_from(value)
+                {
+                    Ok(value) => Ok((key, value)),
+                    Err(error) => Err(format!("Key \"{}\" of the map: {}", key, error))
+                })
+                .collect(),
+            other => Err(mismatched_type("Map", &other))
+        }
+    }
+}
+
+impl<T> TryFrom<&Token> for HashMap<String, T>
+    where T: TryFrom<Token>, T::Error: Display
+{
+    type Error = String;
+
+    fn try_from(value: &Token) -> Result<Self, Self::Error>
+    {
+        HashMap::try_from(value.clone())
+    }
+}
+
+fn mismatched_type(expected: &str, actual: &Token) -> String
+{
+    format!("Expected a value of type {}, but got {}.", expected, actual.type_name())
+}
 
 impl Display for Token
 {
@@ -575,6 +966,18 @@ impl Display for Token
                 s.push(']');
                 s
             },
+            Token::Map(m) =>
+            {
+                let entries: Vec<String> = m.iter().map(|(key, value)| format!("\"{}\":{}", key, value)).collect();
+                format!("{{{}}}", entries.join(","))
+            },
+            Token::Struct(name, fields) =>
+            {
+                let entries: Vec<String> = fields.iter().map(|(key, value)| format!("{}:{}", key, value)).collect();
+                format!("{}({})", name, entries.join(","))
+            },
+            Token::Object(object) => format!("<{}>", object.type_name()),
+            Token::Function(function) => function.to_string(),
             _ => "".to_string()
         })
     }
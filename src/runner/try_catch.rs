@@ -0,0 +1,86 @@
+use crate::types::{Runner, Token, Span, Statement};
+use crate::types::error::ScriptError;
+
+use super::environment::Environment;
+
+// Type name of the value a catch is given. A struct with the message, line and function name of the error.
+// (Eg. err.message)
+pub const ERROR_STRUCT: &str = "Error";
+
+impl Runner
+{
+    // Runs the body of a try statement. An error in it runs the handler, instead of ending the function.
+    pub(crate) fn handle_try(&mut self, body: &[Statement], variable: Option<&str>, handler: &[Statement], env: &mut Environment) -> Result<Option<Token>, ScriptError>
+    {
+        let error = match self.handle_scope(body, env)
+        {
+            Err(error) => error,
+            result => { return result; }
+        };
+
+        let value = caught_error(error, &env.function_name);
+
+        // The error only exists inside the handler.
+        env.push_frame();
+        if let Some(variable) = variable
+        {
+            env.current_frame().insert(variable.to_string(), (value, false, vec![]));
+        }
+
+        let result = self.handle_scope(handler, env);
+        env.pop_frame();
+
+        result
+    }
+}
+
+// Keeps the function an error happened in, as it leaves it.
+pub(crate) fn name_error<T>(function_name: &str, result: Result<T, ScriptError>) -> Result<T, ScriptError>
+{
+    result.map_err(|mut error|
+    {
+        error.function.get_or_insert_with(|| function_name.to_string());
+        error
+    })
+}
+
+// Error a raise statement ends the function with.
+// Raising a caught error passes it on as it was, but it's reported where it was raised. (Eg. raise err)
+pub(crate) fn raise(value: Token, span: Span) -> ScriptError
+{
+    match value
+    {
+        Token::String(message) => ScriptError::new(message, span),
+        Token::Struct(ref name, ref fields) if name == ERROR_STRUCT =>
+        {
+            let message = match fields.get("message")
+            {
+                Some(Token::String(message)) => message.clone(),
+                _ => String::new()
+            };
+
+            ScriptError { value: Some(Box::new(value)), ..ScriptError::new(message, span) }
+        },
+        value => ScriptError::new(format!("Expected a message or a caught error to raise, but got {}.", value.type_name()), span)
+    }
+}
+
+// Value a catch is given for the error.
+// Errors that happened in the function with the try haven't been named yet.
+fn caught_error(error: ScriptError, function_name: &str) -> Token
+{
+    match error.value
+    {
+        Some(value) => *value,
+        None => error_token(&error.message, error.span, error.function.as_deref().unwrap_or(function_name))
+    }
+}
+
+fn error_token(message: &str, span: Span, function_name: &str) -> Token
+{
+    Token::new_struct(ERROR_STRUCT, vec![
+        ("message", Token::String(message.to_string())),
+        ("line", Token::Int(span.line as i64)),
+        ("function", Token::String(function_name.to_string()))
+    ])
+}
@@ -0,0 +1,244 @@
+use crate::types::{Runner, Token, Span, Chunk, Instruction, Function};
+use crate::types::error::ScriptError;
+use crate::utility;
+
+use super::internal_function::{range_bound, loop_items};
+use super::try_catch::raise;
+use crate::external::format;
+
+use super::operation_runner::{LocalVars, handle_token_type, check_alias_chain, run_operation_by_type, run_unary_operation, operation_error};
+
+// Locals of a running chunk, so the operation runner can find them by name.
+struct Frame<'a>
+{
+    chunk: &'a Chunk,
+    slots: &'a [Option<Token>],
+}
+
+impl<'a> LocalVars for Frame<'a>
+{
+    fn get_local(&self, name: &str) -> Option<&Token>
+    {
+        self.chunk.locals.iter().zip(self.slots.iter())
+            .find_map(|(local, value)| if local == name { value.as_ref() } else { None })
+    }
+}
+
+// Values left to loop over.
+enum LoopValues
+{
+    Range(std::ops::Range<i64>),
+    Items(std::vec::IntoIter<Token>),
+}
+
+impl Runner
+{
+    // Runs a compiled function on a stack, with arguments already matched to its parameters.
+    // Errors use the span of the statement, call or nested operation the failing instruction came from.
+    // Errors from functions it calls keep their own span.
+    pub(crate) fn run_chunk(&mut self, chunk: &Chunk, arguments: Vec<Token>) -> Result<Option<Token>, ScriptError>
+    {
+        let mut slots: Vec<Option<Token>> = vec![None; chunk.locals.len()];
+        for (slot, argument) in slots.iter_mut().zip(arguments)
+        {
+            *slot = Some(argument);
+        }
+
+        let mut stack: Vec<Token> = Vec::new();
+        let mut iterators: Vec<LoopValues> = Vec::new();
+        let mut ip = 0;
+
+        while ip < chunk.instructions.len()
+        {
+            let instruction = chunk.instructions[ip];
+            let instruction_span = chunk.spans[ip];
+            let fail = |error: String| ScriptError::new(error, instruction_span);
+            ip += 1;
+
+            match instruction
+            {
+                Instruction::Constant(index) => { stack.push(chunk.constants[index].clone()); }
+                Instruction::LoadLocal(slot) | Instruction::LoadLocalOrVar(slot) =>
+                {
+                    let name = &chunk.locals[slot];
+
+                    let value = match &slots[slot]
+                    {
+                        Some(value) => self.resolve_alias(value.clone(), instruction_span, &Frame { chunk, slots: &slots }),
+                        // Not set in this function, so it may be a global.
+                        None => self.load_global(name, instruction_span, &Frame { chunk, slots: &slots })
+                    };
+
+                    match value
+                    {
+                        Ok(value) => stack.push(value),
+                        Err(_) if instruction == Instruction::LoadLocalOrVar(slot) => stack.push(Token::Var(name.to_string())),
+                        Err(error) => { return Err(error); }
+                    }
+                },
+                Instruction::StoreLocal(slot) => { slots[slot] = stack.pop(); }
+                Instruction::StoreLocalOrGlobal(slot) =>
+                {
+                    let name = &chunk.locals[slot];
+
+                    if slots[slot].is_none() && self.vars.contains_key(name)
+                    {
+                        let value = stack.pop().unwrap_or(Token::Null);
+                        self.change_global(name, value).map_err(fail)?;
+                    }
+                    else
+                    {
+                        slots[slot] = stack.pop();
+                    }
+                },
+                Instruction::ClearLocal(slot) => { slots[slot] = None; }
+                Instruction::LoadGlobal(index) | Instruction::LoadGlobalOrVar(index) =>
+                {
+                    let name = constant_name(chunk, index);
+                    let value = self.load_global(name, instruction_span, &Frame { chunk, slots: &slots });
+
+                    match value
+                    {
+                        Ok(value) => stack.push(value),
+                        Err(_) if instruction == Instruction::LoadGlobalOrVar(index) => stack.push(Token::Var(name.to_string())),
+                        Err(error) => { return Err(error); }
+                    }
+                },
+                Instruction::StoreGlobal(index) =>
+                {
+                    let value = stack.pop().unwrap_or(Token::Null);
+                    self.change_global(constant_name(chunk, index), value).map_err(fail)?;
+                },
+                Instruction::Evaluate(index) =>
+                {
+                    let token = chunk.constants[index].clone();
+
+                    match handle_token_type(self, token.clone(), instruction_span, &Frame { chunk, slots: &slots }, false)
+                    {
+                        Ok(Some(result)) => stack.push(result),
+                        Ok(None) => stack.push(token),
+                        Err(error) => { return Err(error); }
+                    }
+                },
+                Instruction::Operator(index) =>
+                {
+                    let second = stack.pop().unwrap_or(Token::Null);
+                    let first = stack.pop().unwrap_or(Token::Null);
+                    let operator = utility::OPERATIONS[index];
+
+                    match run_operation_by_type(&first, &second, operator)
+                    {
+                        Some(result) => stack.push(result),
+                        None => { return Err(fail(operation_error(&first, &second, operator)
+                                        .unwrap_or_else(|| format!("Cannot apply operation '{}' to '{}' and '{}'.", operator, first, second)))); }
+                    }
+                },
+                Instruction::Unary(index) =>
+                {
+                    let value = stack.pop().unwrap_or(Token::Null);
+                    stack.push(run_unary_operation(&value, utility::UNARY_OPERATIONS[index]).map_err(fail)?);
+                },
+                Instruction::Collection(count) =>
+                {
+                    let items = stack.split_off(stack.len() - count);
+                    stack.push(Token::Collection(items));
+                },
+                Instruction::Call(index, count) =>
+                {
+                    let arguments = stack.split_off(stack.len() - count);
+
+                    let result = self.call_with_locals(constant_name(chunk, index), arguments, instruction_span, &Frame { chunk, slots: &slots })?;
+                    stack.push(result.unwrap_or(Token::Null));
+                },
+                Instruction::Format(index, count) =>
+                {
+                    let values = stack.split_off(stack.len() - count);
+                    stack.push(Token::String(format::format_values(constant_name(chunk, index), &values).map_err(fail)?));
+                },
+                Instruction::PushRange =>
+                {
+                    let end = range_bound(stack.pop()).map_err(fail)?;
+                    let start = range_bound(stack.pop()).map_err(fail)?;
+
+                    iterators.push(LoopValues::Range(start..end));
+                },
+                Instruction::PushItems =>
+                {
+                    let items = loop_items(stack.pop().unwrap_or(Token::Null)).map_err(fail)?;
+                    iterators.push(LoopValues::Items(items.into_iter()));
+                },
+                Instruction::Next(to) =>
+                {
+                    let value = match iterators.last_mut()
+                    {
+                        Some(LoopValues::Range(range)) => range.next().map(Token::Int),
+                        Some(LoopValues::Items(items)) => items.next(),
+                        None => None
+                    };
+
+                    match value
+                    {
+                        Some(value) => stack.push(value),
+                        None => { ip = to; }
+                    }
+                },
+                Instruction::PopIterator => { iterators.pop(); }
+                Instruction::Pop => { stack.pop(); }
+                Instruction::Jump(to) => { ip = to; }
+                Instruction::JumpIfNotTrue(to) =>
+                {
+                    if !matches!(stack.pop(), Some(Token::Bool(true)))
+                    {
+                        ip = to;
+                    }
+                },
+                Instruction::Return =>
+                {
+                    return match stack.pop()
+                    {
+                        Some(Token::Null) | None => Ok(None),
+                        result => Ok(result)
+                    };
+                },
+                Instruction::ReturnNull => { return Ok(None); }
+                Instruction::Raise =>
+                {
+                    let value = stack.pop().unwrap_or(Token::Null);
+                    return Err(raise(value, instruction_span));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Globals are found when they're loaded. Functions can be used as values by name.
+    fn load_global(&mut self, name: &str, span: Span, frame: &Frame) -> Result<Token, ScriptError>
+    {
+        match self.vars.get(name)
+        {
+            Some(value) => self.resolve_alias(value.0.clone(), span, frame),
+            None if self.has_function(name) => Ok(Token::Function(Function::Named(name.to_string()))),
+            None => Err(ScriptError::new(format!("Could not find a variable by the name: {}", name), span))
+        }
+    }
+
+    // Variables holding another variable or a call are followed to their value.
+    fn resolve_alias(&mut self, value: Token, span: Span, frame: &Frame) -> Result<Token, ScriptError>
+    {
+        match value
+        {
+            Token::Var(_) | Token::Call(_, _, _) => Ok(check_alias_chain(self, &value, span, frame)?.unwrap_or(value)),
+            value => Ok(value)
+        }
+    }
+}
+
+fn constant_name(chunk: &Chunk, index: usize) -> &str
+{
+    match &chunk.constants[index]
+    {
+        Token::String(name) => name.as_str(),
+        _ => ""
+    }
+}
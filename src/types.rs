@@ -1,17 +1,55 @@
-#[path="types/expression_list.rs"]
-mod expression_list;
-pub use expression_list::ExpressionList as ExpressionList;
-pub use expression_list::ExpressionListType as ExpressionListType;
-pub use expression_list::ScopeInfo as ScopeInfo;
-pub use expression_list::SingleOp as SingleOp;
-pub use expression_list::MultiOp as MultiOp;
-pub use expression_list::Internal as Internal;
+#[path="types/ast.rs"]
+mod ast;
+pub use ast::Statement as Statement;
+pub use ast::Branch as Branch;
+pub use ast::Case as Case;
+pub use ast::Pattern as Pattern;
+pub use ast::LoopKind as LoopKind;
+pub use ast::FunctionDef as FunctionDef;
+pub use ast::Parameter as Parameter;
+pub use ast::StructDef as StructDef;
+pub use ast::Operation as Operation;
+
+#[path="types/bytecode.rs"]
+mod bytecode;
+pub use bytecode::Instruction as Instruction;
+pub use bytecode::Chunk as Chunk;
+
+#[path="types/function.rs"]
+mod function;
+pub use function::Function as Function;
+pub use function::HostFunction as HostFunction;
+
+#[path="types/function_binding.rs"]
+mod function_binding;
+pub use function_binding::IntoDrythonFunction as IntoDrythonFunction;
+pub use function_binding::IntoFunctionResult as IntoFunctionResult;
+
+#[path="types/lifecycle_hook.rs"]
+mod lifecycle_hook;
+pub use lifecycle_hook::LifecycleHook as LifecycleHook;
+
+#[path="types/object.rs"]
+mod object;
+pub use object::Object as Object;
+pub use object::ObjectType as ObjectType;
+
+#[path="types/script_provider.rs"]
+mod script_provider;
+pub use script_provider::ScriptProvider as ScriptProvider;
+
+#[path="types/span.rs"]
+mod span;
+pub use span::Span as Span;
 
 #[path="types/variable_modifier.rs"]
 mod variable_modifier;
 pub use variable_modifier::VariableModifier as VariableModifier;
 
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::cell::RefCell;
+use linked_hash_map::LinkedHashMap;
 
 use super::script_type::ScriptType;
 
@@ -20,8 +58,11 @@ pub mod error;
 #[derive(Debug)]
 pub struct Parser
 {
+    // File path or virtual name of the parsed script.
+    pub name: String,
     pub script_type: ScriptType,
-    pub global_expressions: ExpressionList
+    // Global variables, imports and function definitions.
+    pub statements: Vec<Statement>
 }
 
 
@@ -29,26 +70,42 @@ pub struct Parser
 pub enum Token
 {
     Null,
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    Float(f64),
     Bool(bool),
     String(String),
     Char(char),
     Collection(Vec<Token>),
+    // Values by string key. Keeps the order keys were added in.
+    Map(LinkedHashMap<String, Token>),
+    // Value of a struct type. Type name and fields, in the order they were declared.
+    Struct(String, LinkedHashMap<String, Token>),
+    // Handle to data owned by the host.
+    Object(Object),
+    // Function that can be called through a variable. (Eg. f(3))
+    Function(Function),
     
     // Meta variables that store information not usually visible to the dev.
     // Unless debugging.
     Var(String),
-    Call(String, String),
+    // Function name, the operations for each argument, and where the call is in the script.
+    Call(String, Vec<Vec<Token>>, Span),
+    // Template of an f-string, the operations for each of its values, and where it is in the script.
+    // Formatted directly, so a function named format can't take its place. (Eg. f"HP: {hp}")
+    Format(String, Vec<Vec<Token>>, Span),
+    // Argument given by the parameter's name. (Eg. spawn("orc", count=3))
+    Named(String, Box<Token>),
 
-    Operation(Vec<Token>),
+    // Nested operation, with its location for errors inside it. (Eg. the inside of brackets)
+    Operation(Vec<Token>, Span),
     Operator(String),
     // Accessor stores the accessor after the '.', and the token before.
     Accessor(Box<Token>, Box<Token>),
 
     // Internal use only.
-    // Allows an inner scope to break a loop.
-    Break
+    // Allows an inner scope to break or continue a loop.
+    Break,
+    Continue
 }
 
 pub trait ExFnRef
@@ -62,7 +119,13 @@ pub type VarMap = HashMap<String, (Token, bool, Vec<VariableModifier>)>;
 pub type BoxedCall = Box<dyn Fn(Option<*mut dyn ExFnRef>, Vec<Token>) -> Result<Option<Token>, String>>;
 pub type DynamicFunctionCall = (Option<*mut dyn ExFnRef>,
                                 Option<BoxedCall>);
+// Host function given the context passed to Runner::call_function_with.
+pub type ContextCall = Box<dyn Fn(&mut dyn std::any::Any, Vec<Token>) -> Result<Option<Token>, String>>;
+// Calls a function value given to a library function. (Eg. the lambda given to map_collection)
+pub type ValueCaller<'a> = dyn FnMut(&Function, Vec<Token>) -> Result<Option<Token>, String> + 'a;
+pub type CallbackCall = fn(&mut ValueCaller, Vec<Token>) -> Result<Option<Token>, String>;
 pub type RegisteredFunction= (String, DynamicFunctionCall);
+pub type RegisteredCallback = (String, CallbackCall);
 pub type RegisteredVariable = (String, Token);
 
 pub struct Runner
@@ -70,11 +133,23 @@ pub struct Runner
     pub parser: Parser,
 
     pub external_functions: HashMap<String, DynamicFunctionCall>,
+    pub context_functions: HashMap<String, ContextCall>,
+    // Library functions that call the function values they're given. (Eg. map_collection)
+    pub(crate) callback_functions: HashMap<String, CallbackCall>,
+    // Context of the running call_function_with. Only set for the length of that call.
+    pub(crate) context: Option<*mut dyn std::any::Any>,
     // bool - is external var
     // external vars cannot have their types changed.
     pub vars: VarMap,
     pub var_indexes_changed: Vec<String>,
 
-    pub external_scripts: HashMap<String, Box<*mut Runner>>,
-    pub external_script_provider: 
+    // Scripts referenced by external variables, by path. Loaded through the provider when first used.
+    pub external_scripts: HashMap<String, Rc<RefCell<Runner>>>,
+    pub script_provider: Option<Rc<dyn ScriptProvider>>,
+
+    // Functions compiled to bytecode. Run by the vm instead of walking the statements.
+    pub compiled_functions: HashMap<String, Rc<Chunk>>,
+
+    // Lifecycle functions the script defines. Found on setup.
+    pub hooks: Vec<LifecycleHook>,
 }
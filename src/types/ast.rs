@@ -0,0 +1,227 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::{Token, Span, VariableModifier};
+
+// Reverse polish notation of an operation. (Created by the operation parser.)
+pub type Operation = Vec<Token>;
+
+#[derive(Clone, Debug)]
+pub enum Statement
+{
+    // modifier!name = value
+    Assign
+    {
+        name: String,
+        modifiers: Vec<VariableModifier>,
+        value: Operation,
+        // Keys or indexes in brackets after the name, run when assigning. (Eg. m["hp"] = 3)
+        indexes: Vec<Operation>,
+        span: Span
+    },
+    // name(arguments)
+    Call
+    {
+        name: String,
+        arguments: Vec<Operation>,
+        span: Span
+    },
+    // Chain of if, elif and else branches. Only the first passing branch is run.
+    If
+    {
+        branches: Vec<Branch>,
+        span: Span
+    },
+    // match subject: case pattern: ... end
+    // The subject is run once, and only the first matching case is run.
+    Match
+    {
+        subject: Operation,
+        cases: Vec<Case>,
+        span: Span
+    },
+    Loop
+    {
+        kind: LoopKind,
+        body: Vec<Statement>,
+        span: Span
+    },
+    Return
+    {
+        value: Option<Operation>,
+        span: Span
+    },
+    Break(Span),
+    Continue(Span),
+    // try: body catch variable: handler end
+    // Errors in the body run the handler, with the error given to the variable.
+    Try
+    {
+        body: Vec<Statement>,
+        variable: Option<String>,
+        handler: Vec<Statement>,
+        span: Span
+    },
+    // raise "message"
+    Raise
+    {
+        value: Operation,
+        span: Span
+    },
+    // Shared so that calls don't need to copy the whole function body.
+    Function(Rc<FunctionDef>),
+    // struct Name: (Fields with their default values.)
+    Struct(Rc<StructDef>),
+    Import
+    {
+        library: String,
+        span: Span
+    },
+}
+
+// What decides how many times a loop runs.
+#[derive(Clone, Debug)]
+pub enum LoopKind
+{
+    // loop: (Runs until a break or return.)
+    Infinite,
+    // loop while condition:
+    While(Operation),
+    // loop i in start..end: (The end is excluded.)
+    Range
+    {
+        variable: String,
+        start: Operation,
+        end: Operation
+    },
+    // loop for x in collection: (Strings are looped over by character.)
+    Each
+    {
+        variable: String,
+        collection: Operation
+    },
+}
+
+// A single if, elif or else block.
+#[derive(Clone, Debug)]
+pub struct Branch
+{
+    // No condition on else branches.
+    pub condition: Option<Operation>,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
+// A single case of a match statement. (Eg. case [x, y] if x > y:)
+#[derive(Clone, Debug)]
+pub struct Case
+{
+    pub pattern: Pattern,
+    // Checked after the pattern matches, with its names set.
+    pub guard: Option<Operation>,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
+// What a case matches against the subject.
+#[derive(Clone, Debug)]
+pub enum Pattern
+{
+    // Value equal to the subject, the same as '=='. (Eg. case "idle":)
+    Literal(Token),
+    // Matches anything, and gives it to the name for the case. (Eg. case n:)
+    Binding(String),
+    // case _:
+    Wildcard,
+    // Collection of the same length, matching each item. (Eg. case [x, 0]:)
+    Collection(Vec<Pattern>),
+}
+
+impl Pattern
+{
+    // Matches every value, so cases after it can't be reached.
+    pub fn is_catch_all(&self) -> bool
+    {
+        matches!(self, Pattern::Binding(_) | Pattern::Wildcard)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FunctionDef
+{
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
+// A parameter of a function. (Eg. count=1, or *rest)
+#[derive(Clone, Debug)]
+pub struct Parameter
+{
+    pub name: String,
+    // Run for each call that doesn't give a value.
+    pub default: Option<Operation>,
+    // Collects the remaining arguments into a collection. Only the last parameter can be variadic.
+    pub variadic: bool,
+}
+
+// A named type of values with fields. Built by calling its name. (Eg. Vec2(1, 2))
+#[derive(Clone, Debug)]
+pub struct StructDef
+{
+    pub name: String,
+    // Field names in order, with the operation for their default value.
+    pub fields: Vec<(String, Operation)>,
+    pub span: Span,
+}
+
+impl FunctionDef
+{
+    // Whether a call with this many arguments, all in order, gives every parameter a value.
+    pub fn accepts(&self, count: usize) -> bool
+    {
+        let required = self.parameters.iter().filter(|x| x.default.is_none() && !x.variadic).count();
+        let positional = self.parameters.iter().filter(|x| !x.variadic).count();
+
+        count >= required && (count <= positional || self.parameters.iter().any(|x| x.variadic))
+    }
+}
+
+impl fmt::Display for Parameter
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match &self.default
+        {
+            _ if self.variadic => write!(f, "*{}", self.name),
+            // Only simple values are shown.
+            Some(default) if default.len() == 1 && !default[0].to_string().is_empty() => write!(f, "{}={}", self.name, default[0]),
+            Some(_) => write!(f, "{}=...", self.name),
+            None => write!(f, "{}", self.name)
+        }
+    }
+}
+
+impl Statement
+{
+    pub fn span(&self) -> Span
+    {
+        match self
+        {
+            Statement::Assign { span, .. } |
+            Statement::Call { span, .. } |
+            Statement::If { span, .. } |
+            Statement::Match { span, .. } |
+            Statement::Loop { span, .. } |
+            Statement::Return { span, .. } |
+            Statement::Try { span, .. } |
+            Statement::Raise { span, .. } |
+            Statement::Break(span) |
+            Statement::Continue(span) |
+            Statement::Import { span, .. } => *span,
+            Statement::Function(function) => function.span,
+            Statement::Struct(definition) => definition.span,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::{Token, Span, Parameter};
+
+// A single instruction of a compiled function.
+// Indexes point into the chunk's constant pool, local slots or instructions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction
+{
+    // Pushes a value from the constant pool.
+    Constant(usize),
+
+    // Locals that aren't set yet are looked for in the globals by name.
+    // (The compiler can't know about globals the host registers later.)
+    LoadLocal(usize),
+    // Loads the variable token itself if the local can't be found.
+    // (Same as an operation that is only a variable.)
+    LoadLocalOrVar(usize),
+    // Pops the top value into a local slot.
+    StoreLocal(usize),
+    // Assigns the local, or the global of the same name if the local isn't set yet and a global exists.
+    StoreLocalOrGlobal(usize),
+    // Locals are cleared when the scope that defined them ends.
+    ClearLocal(usize),
+
+    // Globals are found by the name stored in the constant pool.
+    LoadGlobal(usize),
+    LoadGlobalOrVar(usize),
+    StoreGlobal(usize),
+
+    // Runs a constant through the operation runner. (Eg. accessors)
+    Evaluate(usize),
+    // Applies the operator at this index of utility::OPERATIONS to the top two values.
+    Operator(usize),
+    // Applies the operator at this index of utility::UNARY_OPERATIONS to the top value.
+    Unary(usize),
+    // Pops the amount of values into a collection.
+    Collection(usize),
+    // Calls the function named in the constant pool with the amount of arguments.
+    // Pushes the result, or Null if nothing was returned.
+    Call(usize, usize),
+    // Pops the amount of values into the f-string template in the constant pool.
+    Format(usize, usize),
+    Pop,
+
+    // Pops the end and start of a range to loop over.
+    PushRange,
+    // Pops a collection or string to loop over.
+    PushItems,
+    // Pushes the next value of the innermost loop, or jumps once there are none left.
+    Next(usize),
+    // Ends the innermost loop over values.
+    PopIterator,
+
+    Jump(usize),
+    // Pops the condition and jumps if it isn't Bool(true).
+    JumpIfNotTrue(usize),
+    Return,
+    ReturnNull,
+    // Pops the message, or caught error, and ends the function with it as an error.
+    Raise
+}
+
+// Bytecode for a single function.
+#[derive(Debug, Clone)]
+pub struct Chunk
+{
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    // Name of the variable in each local slot. Parameters come first.
+    pub locals: Vec<String>,
+    pub constants: Vec<Token>,
+    pub instructions: Vec<Instruction>,
+    // Span of the statement each instruction was compiled from.
+    pub spans: Vec<Span>,
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::{Token, FunctionDef};
+
+pub type HostFunction = Rc<dyn Fn(Vec<Token>) -> Result<Option<Token>, String>>;
+
+// Function as a value, so it can be stored in a variable, passed around and called later. (Eg. f = fn(x) => x * 2)
+#[derive(Clone)]
+pub enum Function
+{
+    // Function of the script, or registered by the host, found by name when called. (Eg. f = print)
+    Named(String),
+    // Anonymous function. Locals it uses are captured by value when it's made.
+    // (No captures until then, as the parser only has the definition.)
+    Lambda
+    {
+        definition: Rc<FunctionDef>,
+        captures: Option<Rc<Vec<(String, Token)>>>
+    },
+    // Closure made by the host, such as a callback passed into a script.
+    Host(HostFunction),
+}
+
+impl Function
+{
+    pub fn host<F>(function: F) -> Function
+        where F: Fn(Vec<Token>) -> Result<Option<Token>, String> + 'static
+    {
+        Function::Host(Rc::new(function))
+    }
+
+    // The same function, not just one with the same body.
+    pub fn same(&self, other: &Function) -> bool
+    {
+        match (self, other)
+        {
+            (Function::Named(a), Function::Named(b)) => a == b,
+            (Function::Lambda { definition: a, captures: captures_a }, Function::Lambda { definition: b, captures: captures_b }) =>
+                Rc::ptr_eq(a, b) && match (captures_a, captures_b)
+                {
+                    (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                    (None, None) => true,
+                    _ => false
+                },
+            (Function::Host(a), Function::Host(b)) => Rc::ptr_eq(a, b),
+            _ => false
+        }
+    }
+}
+
+impl fmt::Display for Function
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Function::Named(name) => write!(f, "<fn {}>", name),
+            Function::Lambda { definition, .. } => write!(f, "<fn({})>", definition.parameters.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")),
+            Function::Host(_) => write!(f, "<host fn>")
+        }
+    }
+}
+
+impl fmt::Debug for Function
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Function::Named(name) => write!(f, "Named({})", name),
+            Function::Lambda { definition, captures } =>
+                write!(f, "Lambda({:?}, {:?})", definition.parameters.iter().map(|x| &x.name).collect::<Vec<_>>(), captures.as_ref().map(|x| x.iter().map(|(name, _)| name).collect::<Vec<_>>())),
+            Function::Host(_) => write!(f, "Host")
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+use super::{Token, BoxedCall};
+
+// Rust functions and closures that can be called from scripts. (Eg. runner.bind_function("clamp", |x: i32, min: i32, max: i32| x.max(min).min(max)))
+// Arguments are converted with TryFrom<Token>, and the result with IntoFunctionResult.
+// Args is the tuple of argument types, which lets a closure of any arity up to 8 implement it.
+pub trait IntoDrythonFunction<Args>
+{
+    fn into_function(self, function_name: &str) -> BoxedCall;
+}
+
+// Values a bound function can return.
+pub trait IntoFunctionResult
+{
+    fn into_result(self) -> Result<Option<Token>, String>;
+}
+
+impl<T> IntoFunctionResult for T
+    where T: Into<Token>
+{
+    fn into_result(self) -> Result<Option<Token>, String>
+    {
+        Ok(Some(self.into()))
+    }
+}
+
+impl IntoFunctionResult for ()
+{
+    fn into_result(self) -> Result<Option<Token>, String>
+    {
+        Ok(None)
+    }
+}
+
+impl<T> IntoFunctionResult for Option<T>
+    where T: IntoFunctionResult
+{
+    fn into_result(self) -> Result<Option<Token>, String>
+    {
+        match self
+        {
+            Some(value) => value.into_result(),
+            None => Ok(None)
+        }
+    }
+}
+
+// Errors are raised in the script as runtime errors.
+impl<T> IntoFunctionResult for Result<T, String>
+    where T: IntoFunctionResult
+{
+    fn into_result(self) -> Result<Option<Token>, String>
+    {
+        self?.into_result()
+    }
+}
+
+fn check_arity(function_name: &str, expected: usize, recieved: usize) -> Result<(), String>
+{
+    if expected != recieved
+    {
+        return Err(format!("Function '{}' takes {} arguments, but {} were given.", function_name, expected, recieved));
+    }
+
+    Ok(())
+}
+
+fn convert_argument<T>(function_name: &str, index: usize, argument: Token) -> Result<T, String>
+    where T: TryFrom<Token>, T::Error: Display
+{
+    T::try_from(argument).map_err(|error| format!("Argument {} of function '{}': {}", index + 1, function_name, error))
+}
+
+macro_rules! IntoFunction
+{
+    ($count: expr $(, $arg: ident $index: expr)*) =>
+    {
+        impl<F, R $(, $arg)*> IntoDrythonFunction<($($arg,)*)> for F
+            where F: Fn($($arg),*) -> R + 'static,
+                  R: IntoFunctionResult,
+                  $($arg: TryFrom<Token>, $arg::Error: Display,)*
+        {
+            #[allow(unused_mut, unused_variables)]
+            fn into_function(self, function_name: &str) -> BoxedCall
+            {
+                let function_name = function_name.to_string();
+
+                Box::new(move |_, args|
+                {
+                    check_arity(&function_name, $count, args.len())?;
+
+                    let mut args = args.into_iter();
+                    self($(convert_argument::<$arg>(&function_name, $index, args.next().unwrap_or(Token::Null))?),*).into_result()
+                })
+            }
+        }
+    };
+}
+
+IntoFunction!(0);
+IntoFunction!(1, A 0);
+IntoFunction!(2, A 0, B 1);
+IntoFunction!(3, A 0, B 1, C 2);
+IntoFunction!(4, A 0, B 1, C 2, D 3);
+IntoFunction!(5, A 0, B 1, C 2, D 3, E 4);
+IntoFunction!(6, A 0, B 1, C 2, D 3, E 4, G 5);
+IntoFunction!(7, A 0, B 1, C 2, D 3, E 4, G 5, H 6);
+IntoFunction!(8, A 0, B 1, C 2, D 3, E 4, G 5, H 6, I 7);
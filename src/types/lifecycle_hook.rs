@@ -0,0 +1,40 @@
+// Well known functions a script can define for the host to call over its lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LifecycleHook
+{
+    // on_start()
+    Start,
+    // on_update(dt)
+    Update,
+    // on_destroy()
+    Destroy,
+    // on_event(name, payload)
+    Event
+}
+
+impl LifecycleHook
+{
+    pub const ALL: [LifecycleHook; 4] = [LifecycleHook::Start, LifecycleHook::Update, LifecycleHook::Destroy, LifecycleHook::Event];
+
+    // Name of the function in the script.
+    pub fn function_name(&self) -> &'static str
+    {
+        match self
+        {
+            LifecycleHook::Start => "on_start",
+            LifecycleHook::Update => "on_update",
+            LifecycleHook::Destroy => "on_destroy",
+            LifecycleHook::Event => "on_event"
+        }
+    }
+
+    pub fn parameter_count(&self) -> usize
+    {
+        match self
+        {
+            LifecycleHook::Start | LifecycleHook::Destroy => 0,
+            LifecycleHook::Update => 1,
+            LifecycleHook::Event => 2
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::Token;
+
+type Method<T> = Box<dyn Fn(&mut T, Vec<Token>) -> Result<Option<Token>, String>>;
+type Getter<T> = Box<dyn Fn(&T) -> Token>;
+type Setter<T> = Box<dyn Fn(&mut T, Token) -> Result<(), String>>;
+
+// Methods and properties scripts can use on host objects of a type.
+// Built once by the host and shared by every object of the type.
+// (Eg. ObjectType::new("Player").method("move", |player: &mut Player, args| ...).getter("hp", |player| player.hp.into()))
+pub struct ObjectType<T>
+{
+    name: String,
+    methods: HashMap<String, Method<T>>,
+    getters: HashMap<String, Getter<T>>,
+    setters: HashMap<String, Setter<T>>,
+}
+
+impl<T: 'static> ObjectType<T>
+{
+    pub fn new(name: &str) -> ObjectType<T>
+    {
+        ObjectType
+        {
+            name: name.to_string(),
+            methods: HashMap::new(),
+            getters: HashMap::new(),
+            setters: HashMap::new(),
+        }
+    }
+
+    // Called from scripts by accessor. (Eg. player.move(1, 0))
+    pub fn method<F>(mut self, name: &str, method: F) -> Self
+        where F: Fn(&mut T, Vec<Token>) -> Result<Option<Token>, String> + 'static
+    {
+        self.methods.insert(name.to_string(), Box::new(method));
+
+        self
+    }
+
+    // Read from scripts by accessor. (Eg. player.hp)
+    pub fn getter<F>(mut self, name: &str, getter: F) -> Self
+        where F: Fn(&T) -> Token + 'static
+    {
+        self.getters.insert(name.to_string(), Box::new(getter));
+
+        self
+    }
+
+    // Assigned from scripts by accessor. (Eg. player.hp = 3)
+    pub fn setter<F>(mut self, name: &str, setter: F) -> Self
+        where F: Fn(&mut T, Token) -> Result<(), String> + 'static
+    {
+        self.setters.insert(name.to_string(), Box::new(setter));
+
+        self
+    }
+}
+
+// Host data along with its type, with the type erased so any object can be held by a token.
+trait ObjectHandle
+{
+    fn type_name(&self) -> &str;
+    fn call_method(&self, name: &str, args: Vec<Token>) -> Result<Option<Token>, String>;
+    fn get(&self, name: &str) -> Result<Token, String>;
+    fn set(&self, name: &str, value: Token) -> Result<(), String>;
+    // Address of the host data, to tell if two objects are the same.
+    fn address(&self) -> *const ();
+    fn as_any(&self) -> &dyn Any;
+}
+
+struct Handle<T>
+{
+    value: Rc<RefCell<T>>,
+    object_type: Rc<ObjectType<T>>,
+}
+
+impl<T: 'static> ObjectHandle for Handle<T>
+{
+    fn type_name(&self) -> &str
+    {
+        &self.object_type.name
+    }
+
+    fn call_method(&self, name: &str, args: Vec<Token>) -> Result<Option<Token>, String>
+    {
+        let method = match self.object_type.methods.get(name)
+        {
+            Some(method) => method,
+            None => { return Err(format!("Object '{}' has no method '{}'.", self.type_name(), name)); }
+        };
+
+        match self.value.try_borrow_mut()
+        {
+            Ok(mut value) => method(&mut value, args),
+            Err(_) => Err(format!("Object '{}' is already in use.", self.type_name()))
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<Token, String>
+    {
+        let getter = match self.object_type.getters.get(name)
+        {
+            Some(getter) => getter,
+            None => { return Err(format!("Object '{}' has no property '{}'.", self.type_name(), name)); }
+        };
+
+        match self.value.try_borrow()
+        {
+            Ok(value) => Ok(getter(&value)),
+            Err(_) => Err(format!("Object '{}' is already in use.", self.type_name()))
+        }
+    }
+
+    fn set(&self, name: &str, value: Token) -> Result<(), String>
+    {
+        let setter = match self.object_type.setters.get(name)
+        {
+            Some(setter) => setter,
+            None => { return Err(format!("Property '{}' of object '{}' can't be set.", name, self.type_name())); }
+        };
+
+        match self.value.try_borrow_mut()
+        {
+            Ok(mut object) => setter(&mut object, value),
+            Err(_) => Err(format!("Object '{}' is already in use.", self.type_name()))
+        }
+    }
+
+    fn address(&self) -> *const ()
+    {
+        self.value.as_ptr() as *const ()
+    }
+
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+}
+
+// Reference counted handle to host data. Copies of the token share the same data.
+#[derive(Clone)]
+pub struct Object
+{
+    handle: Rc<dyn ObjectHandle>,
+}
+
+impl Object
+{
+    pub fn new<T: 'static>(value: Rc<RefCell<T>>, object_type: &Rc<ObjectType<T>>) -> Object
+    {
+        Object { handle: Rc::new(Handle { value, object_type: object_type.clone() }) }
+    }
+
+    pub fn type_name(&self) -> &str
+    {
+        self.handle.type_name()
+    }
+
+    pub fn call_method(&self, name: &str, args: Vec<Token>) -> Result<Option<Token>, String>
+    {
+        self.handle.call_method(name, args)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Token, String>
+    {
+        self.handle.get(name)
+    }
+
+    pub fn set(&self, name: &str, value: Token) -> Result<(), String>
+    {
+        self.handle.set(name, value)
+    }
+
+    // The host data, if the object holds a T.
+    pub fn value<T: 'static>(&self) -> Option<Rc<RefCell<T>>>
+    {
+        self.handle.as_any().downcast_ref::<Handle<T>>().map(|x| x.value.clone())
+    }
+
+    // Objects are only equal to the ones holding the same data.
+    pub fn same(&self, other: &Object) -> bool
+    {
+        std::ptr::eq(self.handle.address(), other.handle.address())
+    }
+}
+
+impl fmt::Debug for Object
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "Object({})", self.type_name())
+    }
+}
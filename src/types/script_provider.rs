@@ -0,0 +1,13 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use super::Runner;
+
+// Supplies the scripts that external variables refer to. (Eg. external!other = "enemies/orc.dry")
+// Implemented by the host, which decides how paths map to running scripts.
+// Scripts are known by path, so a runner should be parsed with the same path it's provided by.
+pub trait ScriptProvider
+{
+    // Returns the runner of the script at the path. Its setup should already be run.
+    fn get_script(&self, path: &str) -> Result<Rc<RefCell<Runner>>, String>;
+}
@@ -0,0 +1,36 @@
+// Location of a piece of source code within a script.
+// Offsets are in bytes, lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span
+{
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span
+{
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span
+    {
+        Span
+        {
+            start,
+            end,
+            line,
+            column
+        }
+    }
+
+    // Creates a span starting at this span and ending at the other.
+    pub fn to(&self, other: &Span) -> Span
+    {
+        Span
+        {
+            start: self.start,
+            end: other.end.max(self.end),
+            line: self.line,
+            column: self.column
+        }
+    }
+}
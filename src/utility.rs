@@ -48,6 +48,20 @@ pub const OPERATIONS: [&str; 20] = [
     ">", "<", "<=", ">=", "==", "!="
 ];
 
+// Operators that apply to the single value after them.
+// Named apart from the binary operators that share a symbol.
+pub const UNARY_OPERATIONS: [&str; 3] = ["u-", "u+", "!"];
+
+pub fn is_unary(operator: &str) -> bool
+{
+    UNARY_OPERATIONS.contains(&operator)
+}
+
+pub fn is_right_associative(operator: &str) -> bool
+{
+    operator == "^"
+}
+
 // Determines whether the passed char can be found within the allowed operations array.
 pub fn operations_contains(c: char) -> bool
 {
@@ -64,6 +78,9 @@ pub fn get_operator_worth(string: &str) -> u8
         "||" => 4,
         "&&" => 5,
         ">"|"<"|"<="|">="|"=="|"!=" => 6,
+        // Unary operators apply to the value right after them, before anything else.
+        // Except a power of that value, which goes first. (Eg. -2^2 is -(2^2), like in Lua and Python)
+        "u-"|"u+"|"!" => 7,
         _ => 0
     }
 }
@@ -0,0 +1,117 @@
+mod common;
+
+use drython::types::{Parser, Statement, LoopKind, Token};
+use drython::types::error::ErrorManager;
+
+use common::*;
+
+fn parse(source: &str) -> Parser
+{
+    let mut error_manager = ErrorManager::new();
+    let parser = Parser::parse_str(source, "test.dry", &mut error_manager).unwrap();
+
+    assert!(error_manager.errors.is_empty(), "{:?}", messages(&error_manager));
+    parser
+}
+
+#[test]
+fn globals_imports_and_functions()
+{
+    let parser = parse(&script(&[
+        "System",
+        "",
+        "use math",
+        "hp = 10 + 2",
+        "",
+        "heal(amount, scale=2):",
+        "    hp += amount * scale",
+        "end",
+    ]));
+
+    match &parser.statements[..]
+    {
+        [Statement::Import { library, .. }, Statement::Assign { name, value, .. }, Statement::Function(function)] =>
+        {
+            assert_eq!(library, "math");
+            assert_eq!(name, "hp");
+            // Operations are stored in reverse polish notation, back to front.
+            assert_eq!(format!("{:?}", value), "[Operator(\"+\"), Int(2), Int(10)]");
+
+            assert_eq!(function.name, "heal");
+            let parameters: Vec<String> = function.parameters.iter().map(|x| x.to_string()).collect();
+            assert_eq!(parameters, vec!["amount", "scale=2"]);
+            assert!(matches!(&function.body[..], [Statement::Assign { name, .. }] if name == "hp"));
+        },
+        statements => panic!("Unexpected statements: {:?}", statements)
+    }
+}
+
+#[test]
+fn scopes_nest_statements()
+{
+    let parser = parse(&script(&[
+        "System",
+        "",
+        "f(x):",
+        "    if x > 1:",
+        "        return 1",
+        "    elif x == 1:",
+        "        print(x)",
+        "    else:",
+        "        loop while x < 3:",
+        "            x += 1",
+        "            break",
+        "        end",
+        "    end",
+        "    loop i in 0..x:",
+        "        continue",
+        "    end",
+        "    return",
+        "end",
+    ]));
+
+    let body = match &parser.statements[..]
+    {
+        [Statement::Function(function)] => &function.body,
+        statements => panic!("Unexpected statements: {:?}", statements)
+    };
+
+    match &body[..]
+    {
+        [Statement::If { branches, .. }, Statement::Loop { kind: LoopKind::Range { variable, .. }, body: loop_body, .. }, Statement::Return { value: None, .. }] =>
+        {
+            assert_eq!(branches.len(), 3);
+            assert!(branches[0].condition.is_some() && branches[1].condition.is_some() && branches[2].condition.is_none());
+            assert!(matches!(&branches[0].body[..], [Statement::Return { value: Some(_), .. }]));
+            assert!(matches!(&branches[1].body[..], [Statement::Call { name, arguments, .. }] if name == "print" && arguments.len() == 1));
+            assert!(matches!(&branches[2].body[..], [Statement::Loop { kind: LoopKind::While(_), body, .. }]
+                if matches!(&body[..], [Statement::Assign { .. }, Statement::Break(_)])));
+
+            assert_eq!(variable, "i");
+            assert!(matches!(&loop_body[..], [Statement::Continue(_)]));
+        },
+        statements => panic!("Unexpected statements: {:?}", statements)
+    }
+}
+
+#[test]
+fn if_chains_run_the_first_passing_branch()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f(x):",
+        "    if x > 1:",
+        "        return \"big\"",
+        "    elif x > 0:",
+        "        return \"small\"",
+        "    else:",
+        "        return \"none\"",
+        "    end",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![Token::Int(5)]), "Some(String(\"big\")) []");
+    assert_eq!(run(&source, "f", vec![Token::Int(1)]), "Some(String(\"small\")) []");
+    assert_eq!(run(&source, "f", vec![Token::Int(0)]), "Some(String(\"none\")) []");
+}
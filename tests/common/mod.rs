@@ -0,0 +1,67 @@
+// Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use drython::types::{Parser, Runner, Token};
+use drython::types::error::ErrorManager;
+
+// Joins lines into a script, so indentation stays readable in tests.
+pub fn script(lines: &[&str]) -> String
+{
+    lines.join("\n") + "\n"
+}
+
+pub fn messages(error_manager: &ErrorManager) -> Vec<String>
+{
+    error_manager.errors.iter().map(|x| x.to_string()).collect()
+}
+
+pub fn parse_errors(source: &str) -> Vec<String>
+{
+    let mut error_manager = ErrorManager::new();
+    let _ = Parser::parse_str(source, "test.dry", &mut error_manager);
+
+    messages(&error_manager)
+}
+
+// Parses a script and runs its setup.
+pub fn runner(source: &str) -> (Runner, ErrorManager)
+{
+    let mut error_manager = ErrorManager::new();
+    let mut runner = Runner::new(Parser::parse_str(source, "test.dry", &mut error_manager).unwrap());
+
+    runner.run_setup(&mut error_manager);
+    (runner, error_manager)
+}
+
+// Runs a function of a script, and gives its result with any errors as "result [errors]".
+pub fn run(source: &str, function_name: &str, args: Vec<Token>) -> String
+{
+    run_with(source, function_name, args, false)
+}
+
+// Runs a function of the script by walking it, and again compiled to bytecode.
+// Both have to give the same result and errors.
+pub fn run_both(source: &str, function_name: &str, args: Vec<Token>) -> String
+{
+    let walked = run_with(source, function_name, args.clone(), false);
+    let compiled = run_with(source, function_name, args, true);
+
+    assert_eq!(walked, compiled, "Walked and compiled runs of '{}' differ.", function_name);
+
+    walked
+}
+
+pub fn run_with(source: &str, function_name: &str, args: Vec<Token>, compile: bool) -> String
+{
+    let (mut runner, mut error_manager) = runner(source);
+
+    if compile
+    {
+        runner.compile();
+        assert!(runner.is_compiled(function_name), "'{}' wasn't compiled.", function_name);
+    }
+
+    let result = runner.call_function(function_name, args, &mut error_manager);
+
+    format!("{:?} {:?}", result, messages(&error_manager))
+}
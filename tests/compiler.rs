@@ -0,0 +1,89 @@
+mod common;
+
+use drython::types::{Parser, Runner, Token};
+use drython::types::error::ErrorManager;
+
+use common::*;
+
+#[test]
+fn arithmetic_and_branches()
+{
+    let source = "System\n\nscale = 3\n\nf(a, b):\n    x = a * scale + b ^ 2\n    if x > 10:\n        return x - 1\n    elif x == 10:\n        return \"ten\"\n    else:\n        return -x\n    end\nend\n";
+
+    assert_eq!(run_both(source, "f", vec![Token::Int(2), Token::Int(3)]), "Some(Int(14)) []");
+    assert_eq!(run_both(source, "f", vec![Token::Int(2), Token::Int(2)]), "Some(String(\"ten\")) []");
+    assert_eq!(run_both(source, "f", vec![Token::Int(0), Token::Int(-1)]), "Some(Int(-1)) []");
+}
+
+#[test]
+fn loops_and_calls()
+{
+    let source = "System\n\ntotal = 0\n\nsquare(x):\n    return x * x\nend\n\nf(items):\n    sum = 0\n    loop for item in items:\n        if item == 3:\n            continue\n        end\n        sum += square(item)\n    end\n    loop for i in 0..4:\n        total += i\n    end\n    n = 0\n    loop while n < 5:\n        n += 2\n    end\n    return [sum, total, n]\nend\n";
+
+    let items = Token::Collection(vec![Token::Int(1), Token::Int(2), Token::Int(3), Token::Int(4)]);
+    assert_eq!(run_both(source, "f", vec![items]), "Some(Collection([Int(21), Int(6), Int(6)])) []");
+}
+
+#[test]
+fn errors_point_at_the_same_place()
+{
+    let source = "System\n\ninner(x):\n    y = 1\n    return y + (x * \"a\")\nend\n\nf():\n    return inner(2)\nend\n\ng():\n    return 10 / (1 - 1)\nend\n";
+
+    assert_eq!(run_both(source, "f", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['inner'] Line [5:17] - Cannot apply operation '*' to '2' and '\\\"a\\\"'.\"]");
+    assert_eq!(run_both(source, "g", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['g'] Line [13:5] - Division by zero.\"]");
+}
+
+#[test]
+fn globals_registered_after_compiling()
+{
+    let source = "System\n\nf():\n    count = 5\n    return count\nend\n\ng():\n    return count\nend\n";
+
+    let mut results: Vec<String> = Vec::new();
+
+    for compile in [false, true]
+    {
+        let mut error_manager = ErrorManager::new();
+        let mut runner = Runner::new(Parser::parse_str(source, "test.dry", &mut error_manager).unwrap());
+
+        runner.run_setup(&mut error_manager);
+        if compile
+        {
+            runner.compile();
+        }
+        runner.register_variable("count", Token::Int(1));
+
+        let f = runner.call_function("f", vec![], &mut error_manager);
+        let g = runner.call_function("g", vec![], &mut error_manager);
+
+        // The global is changed, instead of f defining a local of the same name.
+        results.push(format!("{:?} {:?} {:?} {}", f, g, runner.vars["count"].0, error_manager.errors.len()));
+    }
+
+    assert_eq!(results[0], "Some(Int(5)) Some(Int(5)) Int(5) 0");
+    assert_eq!(results[0], results[1]);
+}
+
+#[test]
+fn element_wise_errors()
+{
+    let source = "System\n\nf():\n    return [4, 2] / [0, 1]\nend\n\ng():\n    return [9223372036854775807, 1] * 2\nend\n\nh():\n    x = [1 / 0, 2]\n    return x\nend\n";
+
+    assert_eq!(run_both(source, "f", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['f'] Line [4:5] - Division by zero.\"]");
+    assert_eq!(run_both(source, "g", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['g'] Line [8:5] - Integer overflow in '9223372036854775807 * 2'.\"]");
+    assert_eq!(run_both(source, "h", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['h'] Line [12:10] - Division by zero.\"]");
+}
+
+#[test]
+fn format_strings_ignore_locals_named_format()
+{
+    let source = "System\n\nformat(template, value):\n    return \"replaced\"\nend\n\nf(hp):\n    format = fn(template, value) => \"local\"\n    return f\"HP: {hp + 1:>3}|{{}}\"\nend\n\ng():\n    return f\"{1 / 0}\"\nend\n";
+
+    assert_eq!(run_both(source, "f", vec![Token::Int(4)]), "Some(String(\"HP:   5|{}\")) []");
+    assert_eq!(run_both(source, "g", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['g'] Line [13:12] - Division by zero.\"]");
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use drython::types::{Parser, Runner, ScriptProvider};
+use drython::types::error::ErrorManager;
+
+use common::*;
+
+// Scripts by path, set up when they're added.
+#[derive(Default)]
+struct Scripts
+{
+    runners: RefCell<HashMap<String, Rc<RefCell<Runner>>>>
+}
+
+impl Scripts
+{
+    fn add(self: &Rc<Self>, path: &str, source: &str) -> Rc<RefCell<Runner>>
+    {
+        let mut error_manager = ErrorManager::new();
+        let mut runner = Runner::new(Parser::parse_str(source, path, &mut error_manager).unwrap());
+
+        runner.set_script_provider(self.clone());
+        runner.run_setup(&mut error_manager);
+        assert!(error_manager.errors.is_empty(), "{:?}", messages(&error_manager));
+
+        let runner = Rc::new(RefCell::new(runner));
+        self.runners.borrow_mut().insert(path.to_string(), runner.clone());
+
+        runner
+    }
+}
+
+impl ScriptProvider for Scripts
+{
+    fn get_script(&self, path: &str) -> Result<Rc<RefCell<Runner>>, String>
+    {
+        self.runners.borrow().get(path).cloned().ok_or_else(|| format!("No script at '{}'.", path))
+    }
+}
+
+const ORC: &[&str] = &[
+    "Component",
+    "",
+    "pub!hp = 10",
+    "armor = 2",
+    "",
+    "take_damage(amount):",
+    "    hp -= amount - armor",
+    "    return hp",
+    "end",
+];
+
+#[test]
+fn reads_and_calls_other_scripts()
+{
+    let scripts = Rc::new(Scripts::default());
+    let orc = scripts.add("orc.dry", &script(ORC));
+    let player = scripts.add("player.dry", &script(&[
+        "Component",
+        "",
+        "external!enemy = \"orc.dry\"",
+        "",
+        "attack():",
+        "    left = enemy.take_damage(5)",
+        "    return [left, enemy.hp]",
+        "end",
+        "",
+        "peek():",
+        "    return enemy.armor",
+        "end",
+    ]));
+
+    let mut error_manager = ErrorManager::new();
+    let attacked = player.borrow_mut().call_function("attack", vec![], &mut error_manager);
+    assert_eq!(format!("{:?}", attacked), "Some(Collection([Int(7), Int(7)]))");
+    assert_eq!(format!("{:?}", orc.borrow().vars["hp"].0), "Int(7)");
+
+    let peeked = player.borrow_mut().call_function("peek", vec![], &mut error_manager);
+    assert!(peeked.is_none());
+    assert_eq!(messages(&error_manager),
+        vec!["Drython Runtime Error: [player.dry] Function ['peek'] Line [11:5] - Variable 'armor' of 'orc.dry' isn't public."]);
+}
+
+#[test]
+fn missing_scripts_are_reported_when_linked()
+{
+    let scripts = Rc::new(Scripts::default());
+    let player = scripts.add("player.dry", "Component\n\nexternal!enemy = \"gone.dry\"\n");
+
+    let mut error_manager = ErrorManager::new();
+    player.borrow_mut().link_external_scripts(&mut error_manager);
+
+    assert_eq!(messages(&error_manager),
+        vec!["Drython Runtime Error: [player.dry] Line [3:1] - No script at 'gone.dry'."]);
+}
+
+#[test]
+fn cycles_are_reported()
+{
+    let scripts = Rc::new(Scripts::default());
+    let a = scripts.add("a.dry", "Component\n\nexternal!next = \"b.dry\"\n\nf():\n    return next.f()\nend\n");
+    scripts.add("b.dry", "Component\n\nexternal!next = \"c.dry\"\n\nf():\n    return next.f()\nend\n");
+    scripts.add("c.dry", "Component\n\nexternal!next = \"a.dry\"\n\nf():\n    return next.f()\nend\n");
+
+    let mut error_manager = ErrorManager::new();
+    a.borrow_mut().link_external_scripts(&mut error_manager);
+    let called = a.borrow_mut().call_function("f", vec![], &mut error_manager);
+
+    assert!(called.is_none());
+    assert_eq!(messages(&error_manager), vec![
+        "Drython Runtime Error: [a.dry] Line [3:1] - Cyclic external reference: a.dry -> b.dry -> c.dry -> a.dry.",
+        "Drython Runtime Error: [a.dry] Function ['f'] Line [6:17] - Cyclic external reference: a.dry -> b.dry -> c.dry -> a.dry.",
+    ]);
+}
+
+#[test]
+fn errors_raised_in_other_scripts_can_be_caught()
+{
+    let scripts = Rc::new(Scripts::default());
+    let loader = scripts.add("loader.dry", &script(&[
+        "Component",
+        "",
+        "load(name):",
+        "    raise \"asset not found\"",
+        "end",
+        "",
+        "local():",
+        "    try:",
+        "        x = 1",
+        "        raise \"asset not found\"",
+        "    catch err:",
+        "        return [err.line, err.function]",
+        "    end",
+        "end",
+    ]));
+    let player = scripts.add("player.dry", &script(&[
+        "Component",
+        "",
+        "external!assets = \"loader.dry\"",
+        "",
+        "spawn():",
+        "    try:",
+        "        assets.load(\"orc\")",
+        "    catch err:",
+        "        return [err.message, err.line, err.function]",
+        "    end",
+        "end",
+    ]));
+
+    let mut error_manager = ErrorManager::new();
+    let spawned = player.borrow_mut().call_function("spawn", vec![], &mut error_manager);
+    assert_eq!(format!("{:?}", spawned), "Some(Collection([String(\"asset not found (In 'loader.dry')\"), Int(7), String(\"spawn\")]))");
+
+    // Nothing about the error is left in the other script, for its own catches to find.
+    let local = loader.borrow_mut().call_function("local", vec![], &mut error_manager);
+    assert_eq!(format!("{:?}", local), "Some(Collection([Int(10), String(\"local\")]))");
+    assert!(error_manager.errors.is_empty(), "{:?}", messages(&error_manager));
+}
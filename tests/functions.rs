@@ -0,0 +1,116 @@
+mod common;
+
+use drython::types::{Function, Token};
+
+use common::*;
+
+#[test]
+fn lambdas_capture_locals_when_made()
+{
+    let source = script(&[
+        "System",
+        "",
+        "make(n):",
+        "    add = fn(x) => x + n",
+        "    n = 100",
+        "    return add",
+        "end",
+        "",
+        "f():",
+        "    add = make(2)",
+        "    double = twice",
+        "    return [add(3), double(add(1))]",
+        "end",
+        "",
+        "twice(x):",
+        "    return x * 2",
+        "end",
+    ]);
+
+    assert_eq!(run_both(&source, "f", vec![]), "Some(Collection([Int(5), Int(6)])) []");
+}
+
+#[test]
+fn collection_functions_take_function_values()
+{
+    let source = script(&[
+        "use collection",
+        "",
+        "System",
+        "",
+        "f():",
+        "    bonus = 10",
+        "    hps = map_collection([1, 2, 3], fn(x) => x + bonus)",
+        "    return hps.filter_collection(fn(x) => x > 11)",
+        "end",
+        "",
+        "g():",
+        "    return map_collection([1, 0], fn(x) => 10 / x)",
+        "end",
+        "",
+        "h():",
+        "    return map_collection([1], 2)",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![]), "Some(Collection([Int(12), Int(13)])) []");
+    // Errors inside the function keep their own place.
+    assert_eq!(run(&source, "g", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['fn'] Line [12:44] - Division by zero.\"]");
+    assert_eq!(run(&source, "h", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['h'] Line [16:12] - 'map_collection' expects a collection and a function.\"]");
+}
+
+#[test]
+fn host_closures_are_values()
+{
+    let source = script(&[
+        "System",
+        "",
+        "apply(f, x):",
+        "    return f(x)",
+        "end",
+    ]);
+
+    let triple = Token::Function(Function::host(|args| match args.as_slice()
+    {
+        [Token::Int(x)] => Ok(Some(Token::Int(x * 3))),
+        _ => Err("Expected an Int.".to_string())
+    }));
+
+    assert_eq!(run_both(&source, "apply", vec![triple.clone(), Token::Int(4)]), "Some(Int(12)) []");
+    assert_eq!(run_both(&source, "apply", vec![triple, Token::Null]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['apply'] Line [4:12] - Expected an Int.\"]");
+}
+
+#[test]
+fn arguments_match_parameters()
+{
+    let source = script(&[
+        "System",
+        "",
+        "spawn(kind, count=1, *tags):",
+        "    return [kind, count, tags]",
+        "end",
+        "",
+        "f():",
+        "    return [spawn(\"orc\"), spawn(\"imp\", count=3), spawn(\"elf\", 2, \"a\", \"b\")]",
+        "end",
+        "",
+        "missing():",
+        "    return spawn(count=2)",
+        "end",
+        "",
+        "unknown():",
+        "    return spawn(\"orc\", level=2)",
+        "end",
+    ]);
+
+    assert_eq!(run_both(&source, "f", vec![]), "Some(Collection([Collection([String(\"orc\"), Int(1), Collection([])]), \
+        Collection([String(\"imp\"), Int(3), Collection([])]), Collection([String(\"elf\"), Int(2), Collection([String(\"a\"), String(\"b\")])])])) []");
+    // Errors matching the arguments are the caller's, at the call.
+    assert_eq!(run_both(&source, "missing", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['missing'] Line [12:12] - Missing argument 'kind' for spawn(kind, count=1, *tags).\"]");
+    assert_eq!(run_both(&source, "unknown", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['unknown'] Line [16:12] - spawn(kind, count=1, *tags) has no parameter called 'level'.\"]");
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use drython::types::Token;
+
+use common::*;
+
+#[test]
+fn context_functions_get_the_context_of_the_call()
+{
+    let (mut runner, mut error_manager) = runner("System\n\nf():\n    return score(2)\nend\n");
+    runner.register_context_function("score", |score: &mut i64, args: Vec<Token>|
+    {
+        if *score < 0
+        {
+            panic!("Negative score.");
+        }
+
+        *score += args.len() as i64;
+        Ok(Some(Token::Int(*score)))
+    });
+
+    let mut score: i64 = 1;
+    let result = runner.call_function_with("f", vec![], &mut score, &mut error_manager);
+    assert_eq!((format!("{:?}", result), score), ("Some(Int(2))".to_string(), 2));
+
+    // The context is put back once the call unwinds, instead of pointing at the dropped value.
+    let unwound = panic::catch_unwind(AssertUnwindSafe(|| runner.call_function_with("f", vec![], &mut -1_i64, &mut error_manager)));
+    assert!(unwound.is_err());
+
+    assert!(runner.call_function("f", vec![], &mut error_manager).is_none());
+    assert_eq!(messages(&error_manager),
+        vec!["Drython Runtime Error: [test.dry] Function ['f'] Line [4:12] - Function 'score' needs a context. Call it through call_function_with."]);
+}
+
+#[test]
+fn changed_variables_are_copied_back()
+{
+    let (mut runner, mut error_manager) = runner("System\n\nf():\n    hp = 7\n    name = \"orc\"\nend\n");
+    runner.register_variable("hp", Token::Int(1)).register_variable("name", Token::String("a".to_string()));
+    runner.call_function("f", vec![], &mut error_manager);
+
+    let (mut hp, mut name, mut wrong) = (0_i32, String::new(), 5_u8);
+    runner.update_variable(("hp", &mut hp)).update_variable(("name", &mut name)).update_variable(("name", &mut wrong));
+    assert_eq!((hp, name.as_str(), wrong), (7, "orc", 5));
+
+    assert_eq!(runner.try_update_variable(("name", &mut wrong)).err(),
+        Some("Variable 'name': Expected a value of type Int, but got String.".to_string()));
+}
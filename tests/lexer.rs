@@ -0,0 +1,74 @@
+mod common;
+
+use drython::parser::lexer::{self, LexKind};
+use drython::types::Span;
+use drython::types::error::ErrorManager;
+
+use common::*;
+
+#[test]
+fn tokens_have_lines_and_columns()
+{
+    let mut error_manager = ErrorManager::new();
+    let tokens = lexer::tokenize("hp = 10\n  name = \"é\" + 'x'", &mut error_manager);
+
+    assert!(error_manager.errors.is_empty());
+    assert_eq!(tokens[0].kind, LexKind::Identifier("hp".to_string()));
+    assert_eq!(tokens[0].span, Span::new(0, 2, 1, 1));
+    assert_eq!(tokens[2].span, Span::new(5, 7, 1, 6));
+    assert_eq!(tokens[3].kind, LexKind::Newline);
+    assert_eq!(tokens[4].span, Span::new(10, 14, 2, 3));
+
+    // Offsets are in bytes, while columns count characters.
+    assert_eq!(tokens[6].kind, LexKind::StringLiteral("é".to_string()));
+    assert_eq!(tokens[6].span, Span::new(17, 21, 2, 10));
+    assert_eq!(tokens[7].span, Span::new(22, 23, 2, 14));
+    assert_eq!(tokens[8].span.column, 16);
+}
+
+#[test]
+fn lex_errors_point_at_the_token()
+{
+    let mut error_manager = ErrorManager::new();
+    lexer::tokenize("x = 1\ny = \"open", &mut error_manager);
+
+    assert_eq!(messages(&error_manager), vec!["Drython Parse Error: Line [2:5] - String was not enclosed."]);
+}
+
+#[test]
+fn parse_errors_point_at_the_statement()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f():",
+        "    x = 1",
+        "    y = (x + 2",
+        "end",
+    ]);
+
+    let errors = parse_errors(&source);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("Line [5:"), "{:?}", errors);
+}
+
+#[test]
+fn runtime_errors_point_at_the_failing_line()
+{
+    let source = script(&[
+        "System",
+        "",
+        "inner(x):",
+        "    y = x",
+        "    return y / 0",
+        "end",
+        "",
+        "f():",
+        "    a = 1",
+        "    return inner(a)",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['inner'] Line [5:5] - Division by zero.\"]");
+}
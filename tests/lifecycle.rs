@@ -0,0 +1,76 @@
+mod common;
+
+use drython::script_type::ScriptType;
+use drython::types::{LifecycleHook, Token};
+
+use common::*;
+
+#[test]
+fn script_type_header()
+{
+    assert_eq!(runner("Component\n\nx = 1\n").0.parser.script_type, ScriptType::Component);
+    assert_eq!(runner("System\n\nx = 1\n").0.parser.script_type, ScriptType::System);
+    assert_eq!(parse_errors("Widget\n\nx = 1\n"), vec!["Drython Unkown Script Type: Widget. Please use a know script type such as \"Component\"."]);
+}
+
+#[test]
+fn hooks_are_dispatched()
+{
+    let source = script(&[
+        "Component",
+        "",
+        "log = []",
+        "",
+        "on_start():",
+        "    log = push_collection(log, \"start\")",
+        "end",
+        "",
+        "on_update(dt):",
+        "    log = push_collection(log, dt)",
+        "end",
+        "",
+        "on_event(name, payload):",
+        "    return f\"{name}: {payload}\"",
+        "end",
+        "",
+        "on_destroy():",
+        "    log = push_collection(log, \"destroy\")",
+        "end",
+        "",
+        "use collection",
+    ]);
+
+    let (mut runner, mut error_manager) = runner(&source);
+    assert!(LifecycleHook::ALL.iter().all(|x| runner.has_hook(*x)));
+
+    runner.start(&mut error_manager).update(0.5, &mut error_manager).destroy(&mut error_manager);
+    let answer = runner.send_event("hit", Token::Int(3), &mut error_manager);
+
+    assert_eq!(format!("{:?}", runner.vars["log"].0), "Collection([String(\"start\"), Float(0.5), String(\"destroy\")])");
+    assert_eq!(format!("{:?}", answer), "Some(String(\"hit: 3\"))");
+    assert!(error_manager.errors.is_empty(), "{:?}", messages(&error_manager));
+}
+
+#[test]
+fn missing_hooks_do_nothing()
+{
+    let (mut runner, mut error_manager) = runner("Component\n\nx = 1\n");
+
+    assert!(!runner.has_hook(LifecycleHook::Update));
+    runner.update(0.1, &mut error_manager);
+    assert!(runner.send_event("hit", Token::Null, &mut error_manager).is_none());
+    assert!(error_manager.errors.is_empty());
+}
+
+#[test]
+fn hooks_with_the_wrong_parameters_are_reported()
+{
+    let (mut runner, mut error_manager) = runner("Component\n\non_update():\n    return 1\nend\n");
+
+    assert!(!runner.has_hook(LifecycleHook::Update));
+    runner.update(0.1, &mut error_manager);
+
+    // Reported once when the script is set up, and never called.
+    assert_eq!(messages(&error_manager),
+        vec!["Drython Runtime Error: [test.dry] Function ['on_update'] Line [3:1] - 'on_update' should take 1 parameters, but is on_update()."]);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use drython::types::Token;
+
+use common::*;
+
+#[test]
+fn range_loops_exclude_the_end()
+{
+    let source = script(&[
+        "System",
+        "",
+        "use collection",
+        "",
+        "f(start, end):",
+        "    items = []",
+        "    loop i in start..end:",
+        "        items = push_collection(items, i)",
+        "    end",
+        "    return items",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![Token::Int(2), Token::Int(5)]), "Some(Collection([Int(2), Int(3), Int(4)])) []");
+    assert_eq!(run(&source, "f", vec![Token::Int(3), Token::Int(3)]), "Some(Collection([])) []");
+}
+
+#[test]
+fn collection_and_string_loops()
+{
+    let source = script(&[
+        "System",
+        "",
+        "total(items):",
+        "    sum = 0",
+        "    loop for item in items:",
+        "        sum += item",
+        "    end",
+        "    return sum",
+        "end",
+        "",
+        "letters(text):",
+        "    count = 0",
+        "    loop for c in text:",
+        "        if c == 'a':",
+        "            count += 1",
+        "        end",
+        "    end",
+        "    return count",
+        "end",
+    ]);
+
+    let items = Token::Collection(vec![Token::Int(1), Token::Int(2), Token::Int(3)]);
+    assert_eq!(run(&source, "total", vec![items]), "Some(Int(6)) []");
+    assert_eq!(run(&source, "letters", vec![Token::String("banana".to_string())]), "Some(Int(3)) []");
+}
+
+#[test]
+fn while_loops_with_break_and_continue()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f():",
+        "    n = 0",
+        "    odd = 0",
+        "    loop while n < 10:",
+        "        n += 1",
+        "        if (n % 2) == 0:",
+        "            continue",
+        "        end",
+        "        if n > 7:",
+        "            break",
+        "        end",
+        "        odd += 1",
+        "    end",
+        "    return [n, odd]",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![]), "Some(Collection([Int(9), Int(4)])) []");
+}
+
+#[test]
+fn loops_over_other_values_fail()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f():",
+        "    loop for x in 5:",
+        "        return x",
+        "    end",
+        "end",
+        "",
+        "g():",
+        "    loop i in 0..\"a\":",
+        "        return i",
+        "    end",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['f'] Line [4:5] - Cannot loop over '5'. Expected a collection or string.\"]");
+    assert_eq!(run(&source, "g", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['g'] Line [10:5] - Loop ranges need Int values. Found: '\\\"a\\\"'\"]");
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use drython::types::Token;
+
+use common::*;
+
+#[test]
+fn map_literals_and_key_access()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f():",
+        "    m = {\"hp\": 10, \"name\": \"orc\"}",
+        "    key = \"name\"",
+        "    return [m.hp, m[\"hp\"], m[key], m == {\"hp\": 10, \"name\": \"orc\"}]",
+        "end",
+    ]);
+
+    assert_eq!(run_both(&source, "f", vec![]), "Some(Collection([Int(10), Int(10), String(\"orc\"), Bool(true)])) []");
+}
+
+#[test]
+fn indexes_out_of_range_fail()
+{
+    let source = script(&[
+        "System",
+        "",
+        "over():",
+        "    return [1, 2][5]",
+        "end",
+        "",
+        "negative():",
+        "    items = [1, 2]",
+        "    return items[-1]",
+        "end",
+        "",
+        "text():",
+        "    text = \"ab\"",
+        "    return text[2]",
+        "end",
+    ]);
+
+    assert_eq!(run_both(&source, "over", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['over'] Line [4:5] - Tried to access a collection index out of range.\"]");
+    assert_eq!(run_both(&source, "negative", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['negative'] Line [9:5] - Tried to access a collection index out of range.\"]");
+    assert_eq!(run_both(&source, "text", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['text'] Line [14:5] - Tried to access a string index out of range.\"]");
+}
+
+#[test]
+fn string_indexes_count_characters()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f(text):",
+        "    return [text[0], text[1], text[2]]",
+        "end",
+    ]);
+
+    assert_eq!(run_both(&source, "f", vec![Token::String("é✓a".to_string())]),
+        "Some(Collection([String(\"é\"), String(\"✓\"), String(\"a\")])) []");
+}
+
+#[test]
+fn index_assignment()
+{
+    let source = script(&[
+        "System",
+        "",
+        "stats = {\"hp\": 1}",
+        "stats[\"mp\"] = 2",
+        "",
+        "f():",
+        "    grid = [[0, 0], [0, 0]]",
+        "    grid[1][0] = 5",
+        "    m = {\"hp\": 10, \"inner\": {\"x\": 1}}",
+        "    key = \"hp\"",
+        "    m[key] -= 3",
+        "    m.inner[\"x\"] = [1, 2]",
+        "    m[\"new\"] = true",
+        "    return [grid, m, stats]",
+        "end",
+        "",
+        "out_of_range():",
+        "    items = [1]",
+        "    items[3] = 0",
+        "end",
+        "",
+        "bad_key():",
+        "    m = {}",
+        "    m[1.5] = 0",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![]), concat!("Some(Collection([Collection([Collection([Int(0), Int(0)]), Collection([Int(5), Int(0)])]), ",
+        "Map({\"hp\": Int(7), \"inner\": Map({\"x\": Collection([Int(1), Int(2)])}), \"new\": Bool(true)}), Map({\"hp\": Int(1), \"mp\": Int(2)})])) []"));
+    assert_eq!(run(&source, "out_of_range", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['out_of_range'] Line [19:5] - Tried to set '3' of a collection, which isn't an index in range.\"]");
+    assert_eq!(run(&source, "bad_key", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['bad_key'] Line [24:5] - Keys need to be strings, and indexes Ints. Found: '1.5'\"]");
+}
+
+#[test]
+fn index_assignment_needs_a_key()
+{
+    let errors = parse_errors(&script(&["System", "", "f():", "    m[] = 1", "    m[\"a\"].b = 1", "end"]));
+
+    assert_eq!(errors, vec![
+        "Drython Parse Error: [test.dry] Line [4:5] - Expected a key or index between '[' and ']'.",
+        "Drython Parse Error: [test.dry] Line [5:5] - Failed to parse variable assignment. Only indexes can follow an index. (Eg. grid[x][y] = 0)",
+    ]);
+}
+
+#[test]
+fn host_conversion()
+{
+    let map: HashMap<String, i64> = vec![("hp".to_string(), 3)].into_iter().collect();
+
+    assert_eq!(HashMap::<String, i64>::try_from(Token::from(map.clone())).unwrap(), map);
+}
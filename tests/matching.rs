@@ -0,0 +1,77 @@
+mod common;
+
+use drython::types::Token;
+
+use common::*;
+
+const CLASSIFY: &[&str] = &[
+    "System",
+    "",
+    "classify(v):",
+    "    match v:",
+    "        case \"idle\":",
+    "            return \"resting\"",
+    "        case [x, 0]:",
+    "            return [\"on x axis\", x]",
+    "        case [x, y] if x == y:",
+    "            return [\"diagonal\", x]",
+    "        case [_, [a, b]]:",
+    "            return [\"nested\", a, b]",
+    "        case n if n > 3:",
+    "            return [\"big\", n]",
+    "        case _:",
+    "            return \"other\"",
+    "    end",
+    "end",
+];
+
+#[test]
+fn cases_destructure_and_check_guards()
+{
+    let source = script(CLASSIFY);
+    let pair = |x: Token, y: Token| Token::Collection(vec![x, y]);
+
+    assert_eq!(run(&source, "classify", vec![Token::String("idle".to_string())]), "Some(String(\"resting\")) []");
+    assert_eq!(run(&source, "classify", vec![pair(Token::Int(4), Token::Int(0))]), "Some(Collection([String(\"on x axis\"), Int(4)])) []");
+    assert_eq!(run(&source, "classify", vec![pair(Token::Int(2), Token::Int(2))]), "Some(Collection([String(\"diagonal\"), Int(2)])) []");
+    // A failed guard moves on to the next case.
+    assert_eq!(run(&source, "classify", vec![pair(Token::Int(2), Token::Int(3))]), "Some(String(\"other\")) []");
+    assert_eq!(run(&source, "classify", vec![pair(Token::Int(1), pair(Token::Int(7), Token::Int(8)))]), "Some(Collection([String(\"nested\"), Int(7), Int(8)])) []");
+    assert_eq!(run(&source, "classify", vec![Token::Int(9)]), "Some(Collection([String(\"big\"), Int(9)])) []");
+    assert_eq!(run(&source, "classify", vec![Token::Int(2)]), "Some(String(\"other\")) []");
+}
+
+#[test]
+fn missing_cases_are_warned_about()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f(v):",
+        "    match v:",
+        "        case true:",
+        "            return 1",
+        "    end",
+        "    match v:",
+        "        case n:",
+        "            return n",
+        "        case 2:",
+        "            return 2",
+        "    end",
+        "    match v:",
+        "        case 1 if v > 0:",
+        "            return 1",
+        "        case _:",
+        "            return 0",
+        "    end",
+        "end",
+    ]);
+
+    let warnings = vec![
+        "Drython Parse Warning: [test.dry] Line [4:5] - Match isn't exhaustive, as 'false' isn't handled. Add 'case false:' or 'case _:'.",
+        "Drython Parse Warning: [test.dry] Line [11:9] - Case can't be reached, as a case before it matches everything.",
+    ];
+    assert_eq!(parse_errors(&source), warnings);
+    // Warnings don't stop the script from running.
+    assert_eq!(run(&source, "f", vec![Token::Bool(false)]), format!("Some(Bool(false)) {:?}", warnings));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use drython::types::{Object, ObjectType, Token};
+
+use common::*;
+
+struct Player
+{
+    hp: i64,
+    x: i64,
+}
+
+fn player(hp: i64) -> (Rc<RefCell<Player>>, Token)
+{
+    let object_type = Rc::new(ObjectType::new("Player")
+        .method("walk", |player: &mut Player, args| { player.x += i64::try_from(args[0].clone())?; Ok(Some(Token::Int(player.x))) })
+        .getter("hp", |player| Token::Int(player.hp))
+        .getter("x", |player| Token::Int(player.x))
+        .setter("hp", |player, value| { player.hp = i64::try_from(value)?; Ok(()) }));
+
+    let value = Rc::new(RefCell::new(Player { hp, x: 0 }));
+    (value.clone(), Token::Object(Object::new(value, &object_type)))
+}
+
+const SOURCE: &[&str] = &[
+    "System",
+    "",
+    "hit(p, damage):",
+    "    p.hp -= damage",
+    "    p.walk(2)",
+    "    return [p.hp, p.x]",
+    "end",
+    "",
+    "set_x(p):",
+    "    p.x = 1",
+    "end",
+    "",
+    "set_hp(p):",
+    "    p.hp = \"full\"",
+    "end",
+    "",
+    "unknown(p):",
+    "    return p.mp",
+    "end",
+];
+
+#[test]
+fn getters_setters_and_methods_change_the_host_value()
+{
+    let (value, object) = player(10);
+
+    assert_eq!(run(&script(SOURCE), "hit", vec![object, Token::Int(3)]), "Some(Collection([Int(7), Int(2)])) []");
+    assert_eq!((value.borrow().hp, value.borrow().x), (7, 2));
+}
+
+#[test]
+fn properties_without_a_setter_or_getter_fail()
+{
+    let source = script(SOURCE);
+
+    assert_eq!(run(&source, "set_x", vec![player(1).1]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['set_x'] Line [10:5] - Property 'x' of object 'Player' can't be set.\"]");
+    assert_eq!(run(&source, "set_hp", vec![player(1).1]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['set_hp'] Line [14:5] - Expected a value of type Int, but got String.\"]");
+    assert_eq!(run(&source, "unknown", vec![player(1).1]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['unknown'] Line [18:5] - Object 'Player' has no property 'mp'.\"]");
+}
+
+#[test]
+fn objects_are_shared()
+{
+    let (_, object) = player(1);
+
+    match (&object, object.clone())
+    {
+        (Token::Object(a), Token::Object(b)) => assert!(a.same(&b)),
+        _ => unreachable!()
+    }
+    assert!(matches!(object, Token::Object(ref x) if x.value::<Player>().is_some() && x.value::<String>().is_none()));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use drython::parser::lexer::{self, LexKind};
+use drython::types::error::ErrorManager;
+
+use common::*;
+
+// The value of each string or char literal in the source, along with any errors.
+fn literals(source: &str) -> (Vec<LexKind>, Vec<String>)
+{
+    let mut error_manager = ErrorManager::new();
+    let tokens = lexer::tokenize(source, &mut error_manager);
+
+    let literals = tokens.into_iter().map(|x| x.kind)
+        .filter(|x| matches!(x, LexKind::StringLiteral(_) | LexKind::CharLiteral(_) | LexKind::FormatString(..)))
+        .collect();
+
+    (literals, messages(&error_manager))
+}
+
+#[test]
+fn escape_sequences()
+{
+    let (literals, errors) = literals(r#"x = "a\tb\n\"q\" \\ \u{e9}\0" + '\n' + '\''"#);
+
+    assert_eq!(literals, vec![
+        LexKind::StringLiteral("a\tb\n\"q\" \\ é\0".to_string()),
+        LexKind::CharLiteral("\n".to_string()),
+        LexKind::CharLiteral("'".to_string()),
+    ]);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn raw_strings_keep_backslashes()
+{
+    assert_eq!(literals(r#"x = r"C:\path\n""#), (vec![LexKind::StringLiteral(r"C:\path\n".to_string())], vec![]));
+}
+
+#[test]
+fn triple_quoted_strings_span_lines()
+{
+    let source = "System\n\nf():\n    text = \"\"\"\nfirst \"quoted\"\n  second\"\"\"\n    return [text, 1 / 0]\nend\n";
+
+    assert_eq!(literals(source), (vec![LexKind::StringLiteral("first \"quoted\"\n  second".to_string())], vec![]));
+    // Lines after the string keep their numbers.
+    assert_eq!(run(source, "f", vec![]), "None [\"Drython Runtime Error: [test.dry] Function ['f'] Line [7:19] - Division by zero.\"]");
+}
+
+#[test]
+fn bad_escapes_are_reported()
+{
+    assert_eq!(literals(r#"x = "\q" + "\u{zz}""#).1, vec![
+        "Drython Parse Error: Line [1:5] - Unknown escape sequence: '\\q'",
+        "Drython Parse Error: Line [1:12] - Invalid unicode escape. Expected a hex code point such as '\\u{1F600}'.",
+    ]);
+    assert_eq!(literals("x = \"\"\"never closed\n").1, vec!["Drython Parse Error: Line [1:5] - String was not enclosed."]);
+}
+
+#[test]
+fn format_string_errors_point_into_the_string()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f(hp):",
+        "    b = f\"\"\"HP:",
+        "  {hp:>x}{} }\"\"\"",
+        "end",
+    ]);
+
+    assert_eq!(parse_errors(&source), vec![
+        "Drython Parse Error: [test.dry] Line [5:3] - Invalid format spec '>x'. Expected [[fill]align][0][width][.precision].",
+        "Drython Parse Error: [test.dry] Line [5:10] - Expected an expression between '{' and '}' in format string.",
+        "Drython Parse Error: [test.dry] Line [5:13] - Unmatched '}' in format string. Use '}}' for a brace.",
+    ]);
+
+    // Expressions are parsed from their own tokens, so their errors keep their place after escapes.
+    let source = script(&["System", "", "f(hp):", "    return f\"\\t{hp} {hp.}\"", "end"]);
+    assert_eq!(parse_errors(&source), vec!["Drython Parse Error: [test.dry] Line [4:24] - In format string: Expected a value after '.'."]);
+}
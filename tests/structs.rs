@@ -0,0 +1,90 @@
+mod common;
+
+use drython::types::Token;
+
+use common::*;
+
+const SOURCE: &[&str] = &[
+    "System",
+    "",
+    "struct Unit:",
+    "    name",
+    "    hp = 10",
+    "    pos = [0, 0]",
+    "end",
+    "",
+    "make():",
+    "    return Unit(\"orc\")",
+    "end",
+    "",
+    "moved():",
+    "    u = Unit(\"orc\", pos=[1, 2])",
+    "    u.hp -= 3",
+    "    u.pos.1 = 5",
+    "    return [u, u.hp, u.pos]",
+    "end",
+    "",
+    "equal():",
+    "    return [Unit(\"a\") == Unit(\"a\"), Unit(\"a\") == Unit(\"b\")]",
+    "end",
+    "",
+    "missing():",
+    "    u = Unit(\"orc\")",
+    "    u.mp = 1",
+    "end",
+];
+
+#[test]
+fn construction_fills_in_defaults()
+{
+    assert_eq!(run(&script(SOURCE), "make", vec![]),
+        "Some(Struct(\"Unit\", {\"name\": String(\"orc\"), \"hp\": Int(10), \"pos\": Collection([Int(0), Int(0)])})) []");
+}
+
+#[test]
+fn field_assignment_keeps_field_order()
+{
+    assert_eq!(run(&script(SOURCE), "moved", vec![]), concat!("Some(Collection([",
+        "Struct(\"Unit\", {\"name\": String(\"orc\"), \"hp\": Int(7), \"pos\": Collection([Int(1), Int(5)])}), ",
+        "Int(7), Collection([Int(1), Int(5)])])) []"));
+}
+
+#[test]
+fn assigned_map_keys_keep_their_place()
+{
+    let source = script(&[
+        "System",
+        "",
+        "f():",
+        "    m = {\"hp\": 1, \"mp\": 2}",
+        "    m.hp = 5",
+        "    m.xp = 0",
+        "    return m",
+        "end",
+    ]);
+
+    assert_eq!(run(&source, "f", vec![]), "Some(Map({\"hp\": Int(5), \"mp\": Int(2), \"xp\": Int(0)})) []");
+}
+
+#[test]
+fn structural_equality()
+{
+    assert_eq!(run(&script(SOURCE), "equal", vec![]), "Some(Collection([Bool(true), Bool(false)])) []");
+}
+
+#[test]
+fn unknown_fields_fail()
+{
+    assert_eq!(run(&script(SOURCE), "missing", vec![]),
+        "None [\"Drython Runtime Error: [test.dry] Function ['missing'] Line [26:5] - Struct 'Unit' has no field 'mp'.\"]");
+}
+
+#[test]
+fn host_built_structs()
+{
+    let unit = Token::new_struct("Unit", vec![("name", Token::String("orc".to_string())), ("hp", Token::Int(3))]);
+
+    assert_eq!(unit.get_field("hp").map(|x| x.to_string()), Some("3".to_string()));
+    assert!(unit.get_field("mp").is_none());
+    assert_eq!(unit.to_string(), "Unit(name:\"orc\",hp:3)");
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::*;
+
+const SCRIPT: &[&str] = &[
+    "System",
+    "",
+    "check(x):",
+    "    if x > 2:",
+    "        raise \"too big\"",
+    "    end",
+    "    return x",
+    "end",
+    "",
+    "nested():",
+    "    out = []",
+    "    try:",
+    "        try:",
+    "            check(5)",
+    "        catch err:",
+    "            out = [err.message, err.line, err.function]",
+    "            raise f\"again: {err.message}\"",
+    "        end",
+    "    catch err:",
+    "        return [out, err.message, err.line, err.function]",
+    "    end",
+    "end",
+    "",
+    "rethrow():",
+    "    try:",
+    "        check(9)",
+    "    catch err:",
+    "        raise err",
+    "    end",
+    "end",
+    "",
+    "caught_rethrow():",
+    "    try:",
+    "        rethrow()",
+    "    catch err:",
+    "        return [err.message, err.line, err.function]",
+    "    end",
+    "end",
+    "",
+    "host():",
+    "    try:",
+    "        load_asset(\"hero.png\")",
+    "    catch err:",
+    "        return [err.message, err.line, err.function]",
+    "    end",
+    "end",
+];
+
+#[test]
+fn nested_tries_catch_the_inner_error_first()
+{
+    assert_eq!(run(&script(SCRIPT), "nested", vec![]),
+        "Some(Collection([Collection([String(\"too big\"), Int(5), String(\"check\")]), String(\"again: too big\"), Int(17), String(\"nested\")])) []");
+}
+
+#[test]
+fn raising_a_caught_error_passes_it_on()
+{
+    let source = script(SCRIPT);
+
+    // A catch further up is given the error as it first happened.
+    assert_eq!(run(&source, "caught_rethrow", vec![]), "Some(Collection([String(\"too big\"), Int(5), String(\"check\")])) []");
+    // Left uncaught, it's reported where it was raised.
+    assert_eq!(run(&source, "rethrow", vec![]), "None [\"Drython Runtime Error: [test.dry] Function ['rethrow'] Line [28:9] - too big\"]");
+}
+
+#[test]
+fn host_errors_can_be_caught()
+{
+    let (mut runner, mut error_manager) = runner(&script(SCRIPT));
+    runner.register_function("load_asset", |args| Err(format!("Asset not found: {}", args[0])));
+
+    let result = runner.call_function("host", vec![], &mut error_manager);
+
+    assert_eq!(format!("{:?} {:?}", result, messages(&error_manager)),
+        "Some(Collection([String(\"Asset not found: \\\"hero.png\\\"\"), Int(42), String(\"host\")])) []");
+}